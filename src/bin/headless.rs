@@ -0,0 +1,12 @@
+use network_vizualizer::cli::{exit_with_error, Args};
+use network_vizualizer::headless;
+
+fn main() {
+    let args = match Args::load() {
+        Ok(args) => args,
+        Err(e) => exit_with_error(e),
+    };
+    if let Err(e) = headless::run(&args) {
+        exit_with_error(e);
+    }
+}
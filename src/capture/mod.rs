@@ -0,0 +1,6 @@
+mod network_manager;
+mod recorder;
+
+pub use network_manager::NetworkManager;
+pub use recorder::{PacketRing, Recorder, RecorderConfig, DEFAULT_RING_MAX_BYTES};
+pub(crate) use recorder::header_time;
@@ -0,0 +1,325 @@
+
+use crate::capture::{header_time, PacketRing, Recorder, RecorderConfig, DEFAULT_RING_MAX_BYTES};
+use crate::data::{is_dns_port, parse_arp, parse_dhcp, parse_ethernet, parse_encapsulation, parse_fragment, parse_link_tags, parse_response, parse_server_name, parse_tcp_response, FrameInfo, HostDiscovery, IPPacketInfo, Reassembler, Tunnel, DHCP_CLIENT_PORT, DHCP_SERVER_PORT};
+use etherparse::{ether_type, PacketHeaders, IpHeader, TransportHeader, VlanHeader};
+use pcap::{Activated, Capture, Device, Linktype};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, SystemTime};
+
+// Tunnels inside tunnels are taken out up to this depth
+const MAX_TUNNEL_DEPTH: usize = 4;
+
+pub struct NetworkManager {
+    sender: Sender<IPPacketInfo>,
+    discovery_sender: Option<Sender<HostDiscovery>>,
+    frame_sender: Option<Sender<FrameInfo>>,
+    cap: Option<Capture<dyn Activated>>,
+    finished: bool,
+    recorder: Option<Recorder>,
+    ring: Option<Arc<Mutex<PacketRing>>>,
+    decapsulate: bool,
+    reassembler: Reassembler,
+    // Addresses of the opened device
+    local_addresses: Vec<IpAddr>,
+}
+
+impl NetworkManager{
+    pub fn new(channel: Sender<IPPacketInfo>) -> NetworkManager{
+        NetworkManager{
+            sender: channel,
+            discovery_sender: None,
+            frame_sender: None,
+            cap: None,
+            finished: false,
+            recorder: None,
+            ring: None,
+            decapsulate: false,
+            reassembler: Reassembler::new(),
+            local_addresses: Vec::new(),
+        }
+    }
+
+    pub fn set_device(&mut self, device: Device){
+        self.open_device(device).unwrap();
+    }
+
+    pub fn open_device<D: Into<Device>>(&mut self, device: D) -> Result<(), pcap::Error>{
+        let device: Device = device.into();
+        self.local_addresses = NetworkManager::get_device_addresses(&device);
+        let capture = Capture::from_device(device)?.immediate_mode(true).open()?.setnonblock()?;
+        self.cap = Some(capture.into());
+        self.finished = false;
+        Ok(())
+    }
+
+    pub fn open_file(&mut self, path: &Path) -> Result<(), pcap::Error>{
+        self.local_addresses.clear();
+        self.cap = Some(Capture::from_file(path)?.into());
+        self.finished = false;
+        Ok(())
+    }
+
+    // A device opened by name has no addresses, they are the ones of the listed device with that name
+    fn get_device_addresses(device: &Device) -> Vec<IpAddr>{
+        if !device.addresses.is_empty(){
+            return device.addresses.iter().map(|address| address.addr).collect();
+        }
+        Device::list().unwrap_or_default().into_iter()
+            .find(|listed| listed.name == device.name)
+            .map(|listed| listed.addresses.iter().map(|address| address.addr).collect())
+            .unwrap_or_default()
+    }
+
+    // Addresses of the host on the opened device, none for capture files
+    pub fn get_local_addresses(&self) -> &Vec<IpAddr>{
+        &self.local_addresses
+    }
+
+    // Applies a BPF filter to the opened capture
+    pub fn set_filter(&mut self, filter: &str) -> Result<(), pcap::Error>{
+        match &mut self.cap {
+            Some(capture) => capture.filter(filter, true),
+            None => Ok(()),
+        }
+    }
+
+    // Every Ethernet frame, IP or not, is sent there for the MAC view
+    pub fn set_frame_sender(&mut self, sender: Sender<FrameInfo>){
+        self.frame_sender = Some(sender);
+    }
+
+    // Packets of GRE, VXLAN, GENEVE and IP-in-IP tunnels are replaced by the packet they carry
+    pub fn set_decapsulate(&mut self, decapsulate: bool){
+        self.decapsulate = decapsulate;
+    }
+
+    // Hosts found in ARP and DHCP messages are sent there
+    pub fn set_discovery_sender(&mut self, sender: Sender<HostDiscovery>){
+        self.discovery_sender = Some(sender);
+    }
+
+    // Records every frame of the opened capture to rolling pcap files
+    pub fn start_recording(&mut self, config: RecorderConfig) -> Result<(), pcap::Error>{
+        let linktype = self.get_linktype()?;
+        self.recorder = Some(Recorder::new(config, linktype)?);
+        Ok(())
+    }
+
+    // Keeps the last frames of the opened capture in memory, the returned ring is
+    // shared with the capture thread and can be saved at any time
+    pub fn start_ring(&mut self, duration: Duration) -> Result<Arc<Mutex<PacketRing>>, pcap::Error>{
+        let linktype = self.get_linktype()?;
+        let ring = Arc::new(Mutex::new(PacketRing::new(linktype, duration, DEFAULT_RING_MAX_BYTES)));
+        self.ring = Some(ring.clone());
+        Ok(ring)
+    }
+
+    fn get_linktype(&self) -> Result<pcap::Linktype, pcap::Error>{
+        match &self.cap {
+            Some(capture) => Ok(capture.get_datalink()),
+            None => Err(pcap::Error::PcapError("no capture opened".to_string())),
+        }
+    }
+
+    // True once a capture file has been read until the end
+    pub fn is_finished(&self) -> bool{
+        self.finished
+    }
+
+    pub fn get_network_devices() -> Vec<Device>{
+        return Device::list().unwrap();
+    }
+
+    // The payload length leaves out the extension headers, it starts at the transport header
+    fn convert_to_ip_packet_info(network_header: &IpHeader) -> IPPacketInfo{
+        let mut packet = match network_header {
+            IpHeader::Version4(ipv4header, extensions) => {
+                IPPacketInfo::new(
+                    IpAddr::V4(ipv4header.source.into()),
+                    IpAddr::V4(ipv4header.destination.into()),
+                    ipv4header.payload_len.saturating_sub(extensions.header_len() as u16),
+                )
+            }
+            IpHeader::Version6(ipv6header, extensions) => {
+                IPPacketInfo::new(
+                    IpAddr::V6(ipv6header.source.into()),
+                    IpAddr::V6(ipv6header.destination.into()),
+                    ipv6header.payload_length.saturating_sub(extensions.header_len() as u16),
+                )
+            }
+        };
+        packet.protocol = network_header.next_header().unwrap_or(0);
+        packet
+    }
+
+    fn set_transport_info(packet: &mut IPPacketInfo, transport_header: &TransportHeader){
+        let (source_port, dest_port) = match transport_header {
+            TransportHeader::Udp(udp) => (udp.source_port, udp.destination_port),
+            TransportHeader::Tcp(tcp) => (tcp.source_port, tcp.destination_port),
+            TransportHeader::Icmpv4(_) | TransportHeader::Icmpv6(_) => return,
+        };
+        packet.source_port = Some(source_port);
+        packet.dest_port = Some(dest_port);
+    }
+
+    // Names of DNS and mDNS responses, only read from the packets, no lookup is made
+    fn set_dns_records(packet: &mut IPPacketInfo, transport_header: &TransportHeader, payload: &[u8]){
+        if !packet.source_port.is_some_and(is_dns_port){
+            return;
+        }
+        packet.dns_records = match transport_header {
+            TransportHeader::Udp(_) => parse_response(payload),
+            TransportHeader::Tcp(_) => parse_tcp_response(payload),
+            _ => return,
+        };
+    }
+
+    // ARP frames have no IP header, they are decoded before the IP packets
+    fn get_discovery(headers: &PacketHeaders, timestamp: SystemTime) -> Option<HostDiscovery>{
+        // Packets out of MPLS and IP tunnels have no link header
+        let ether_type = match &headers.vlan {
+            Some(VlanHeader::Single(vlan)) => Some(vlan.ether_type),
+            Some(VlanHeader::Double(vlan)) => Some(vlan.inner.ether_type),
+            None => headers.link.as_ref().map(|link| link.ether_type),
+        };
+        if ether_type == Some(ether_type::ARP){
+            return parse_arp(headers.payload, timestamp);
+        }
+        match &headers.transport {
+            Some(TransportHeader::Udp(udp)) => {
+                let ports = [udp.source_port, udp.destination_port];
+                if ports.contains(&DHCP_SERVER_PORT) && ports.contains(&DHCP_CLIENT_PORT){
+                    parse_dhcp(headers.payload, timestamp)
+                }else{
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn get_packet_info(headers: &PacketHeaders, timestamp: SystemTime) -> Option<IPPacketInfo>{
+        let mut packet = NetworkManager::convert_to_ip_packet_info(headers.ip.as_ref()?);
+        if let Some(transport_header) = &headers.transport {
+            NetworkManager::set_transport_info(&mut packet, transport_header);
+            NetworkManager::set_dns_records(&mut packet, transport_header, headers.payload);
+            if let TransportHeader::Tcp(_) = transport_header {
+                packet.server_name = parse_server_name(headers.payload);
+            }
+        }
+        packet.timestamp = timestamp;
+        Some(packet)
+    }
+
+    // Replaces the headers by the ones of the packet inside the tunnels, returns the outermost tunnel
+    fn decapsulate(headers: &mut PacketHeaders, timestamp: SystemTime) -> Option<Tunnel>{
+        let mut tunnel = None;
+        for _ in 0..MAX_TUNNEL_DEPTH{
+            let Some(protocol) = headers.ip.as_ref().and_then(|ip| ip.next_header().ok()) else {
+                break;
+            };
+            let dest_port = match &headers.transport {
+                Some(TransportHeader::Udp(udp)) => Some(udp.destination_port),
+                _ => None,
+            };
+            let Some(encapsulation) = parse_encapsulation(protocol, dest_port, headers.payload) else {
+                break;
+            };
+            let payload = &headers.payload[encapsulation.offset..];
+            let inner = if encapsulation.ethernet {
+                PacketHeaders::from_ethernet_slice(payload)
+            }else{
+                PacketHeaders::from_ip_slice(payload)
+            };
+            let Ok(inner) = inner else {
+                break;
+            };
+            if tunnel.is_none(){
+                tunnel = NetworkManager::get_packet_info(headers, timestamp)
+                    .map(|outer| Tunnel{kind: encapsulation.kind, id: encapsulation.id, outer});
+            }
+            *headers = inner;
+        }
+        tunnel
+    }
+
+
+    pub fn listen_packets(&mut self) -> Option<()>{
+        if let Some(capture) = &mut self.cap{
+            let ethernet = capture.get_datalink() == Linktype::ETHERNET;
+            loop {
+                let packet = match capture.next_packet() {
+                    Ok(packet) => packet,
+                    Err(pcap::Error::NoMorePackets) => {
+                        self.finished = true;
+                        break;
+                    }
+                    Err(_) => break,
+                };
+                if let Some(recorder) = &mut self.recorder{
+                    if let Err(e) = recorder.write(&packet){
+                        eprintln!("Recording stopped: {}", e);
+                        self.recorder = None;
+                    }
+                }
+                if let Some(ring) = &self.ring{
+                    if let Ok(mut ring) = ring.lock(){
+                        ring.push(&packet);
+                    }
+                }
+                if let (Some(sender), true) = (&self.frame_sender, ethernet){
+                    let timestamp = header_time(packet.header);
+                    if let Some(frame) = parse_ethernet(&packet, packet.header.len, timestamp){
+                        sender.send(frame).ok()?;
+                    }
+                }
+                let timestamp = header_time(packet.header);
+                let tags = if ethernet {parse_link_tags(&packet)} else {None}.unwrap_or_default();
+                // Fragments are kept until the datagram is whole, it is then read as a single packet
+                let is_ip = [ether_type::IPV4, ether_type::IPV6].contains(&tags.ether_type);
+                let fragment = if ethernet && (is_ip || tags.is_mpls()) {packet.get(tags.network_offset..).and_then(parse_fragment)} else {None};
+                let datagram = match fragment {
+                    Some(fragment) => match self.reassembler.add(&fragment, timestamp) {
+                        Some(datagram) => Some(datagram),
+                        None => continue,
+                    },
+                    None => None,
+                };
+                // etherparse stops at the MPLS header, the IP header follows the bottom label
+                let mut ip_packet = if let Some(datagram) = &datagram{
+                    PacketHeaders::from_ip_slice(datagram).ok()?
+                }else if tags.is_mpls(){
+                    PacketHeaders::from_ip_slice(packet.get(tags.network_offset..)?).ok()?
+                }else{
+                    PacketHeaders::from_ethernet_slice(&packet).ok()?
+                };
+                let tunnel = if self.decapsulate {NetworkManager::decapsulate(&mut ip_packet, timestamp)} else {None};
+                if let Some(sender) = &self.discovery_sender{
+                    if let Some(mut discovery) = NetworkManager::get_discovery(&ip_packet, timestamp){
+                        discovery.vlan = tags.vlans.last().copied();
+                        sender.send(discovery).ok()?;
+                    }
+                }
+                let mut ip_packet_info = NetworkManager::get_packet_info(&ip_packet, timestamp)?;
+                // The tags are the ones of the outer packet on the wire
+                if let Some(mut tunnel) = tunnel{
+                    tunnel.outer.vlans = tags.vlans.clone();
+                    tunnel.outer.mpls_labels = tags.mpls_labels.clone();
+                    ip_packet_info.tunnel = Some(Box::new(tunnel));
+                }
+                ip_packet_info.vlans = tags.vlans;
+                ip_packet_info.mpls_labels = tags.mpls_labels;
+                self.sender.send(ip_packet_info).ok()?;
+            }
+        }
+        // No packet left for now, the capture thread may be stopped at any time
+        if let Some(recorder) = &mut self.recorder{
+            recorder.flush().ok()?;
+        }
+        return Some(());
+    }
+
+}
\ No newline at end of file
@@ -0,0 +1,201 @@
+use pcap::{Capture, Linktype, Packet, PacketHeader, Savefile};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::math::capture_time;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RECORD_FILE_PREFIX: &str = "capture";
+// Upper bound of the memory used by the ring buffer, whatever its duration
+pub const DEFAULT_RING_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+// The fields are i32 on 32-bit targets
+#[allow(clippy::useless_conversion)]
+pub(crate) fn header_time(header: &PacketHeader) -> SystemTime{
+    capture_time(i64::from(header.ts.tv_sec), i64::from(header.ts.tv_usec))
+}
+
+fn open_savefile(linktype: Linktype, path: &Path) -> Result<Savefile, pcap::Error>{
+    Capture::dead(linktype)?.savefile(path)
+}
+
+#[derive(Debug, Clone)]
+pub struct RecorderConfig{
+    pub dir: PathBuf,
+    // A new file is started when one of the limits is reached
+    pub max_file_size: Option<u64>,
+    pub max_file_duration: Option<Duration>,
+    // Oldest files are deleted when there are more
+    pub max_files: Option<usize>,
+}
+
+struct RecordFile{
+    savefile: Savefile,
+    path: PathBuf,
+    size: u64,
+    start: SystemTime,
+}
+
+// Writes every captured frame to rolling pcap files
+pub struct Recorder{
+    config: RecorderConfig,
+    linktype: Linktype,
+    current: Option<RecordFile>,
+    // Finished files, oldest first
+    files: VecDeque<PathBuf>,
+    file_index: u32,
+}
+
+impl Recorder{
+    pub fn new(config: RecorderConfig, linktype: Linktype) -> Result<Recorder, pcap::Error>{
+        fs::create_dir_all(&config.dir)?;
+        Ok(Recorder{
+            config,
+            linktype,
+            current: None,
+            files: VecDeque::new(),
+            file_index: 0,
+        })
+    }
+
+    pub fn write(&mut self, packet: &Packet) -> Result<(), pcap::Error>{
+        let time = header_time(packet.header);
+        if self.should_rotate(time){
+            self.rotate(time)?;
+        }
+        if let Some(file) = &mut self.current{
+            file.savefile.write(packet);
+            // Record header of 16 bytes in front of every frame
+            file.size += 16 + packet.header.caplen as u64;
+        }
+        Ok(())
+    }
+
+    // Frames are buffered, flushing makes them visible to other programs reading the file
+    pub fn flush(&mut self) -> Result<(), pcap::Error>{
+        match &mut self.current {
+            Some(file) => file.savefile.flush(),
+            None => Ok(()),
+        }
+    }
+
+    // Path of the file being written
+    pub fn current_path(&self) -> Option<&Path>{
+        self.current.as_ref().map(|file| file.path.as_path())
+    }
+
+    fn should_rotate(&self, time: SystemTime) -> bool{
+        let Some(file) = &self.current else {
+            return true;
+        };
+        let too_big = self.config.max_file_size.is_some_and(|max| file.size >= max);
+        let too_old = self.config.max_file_duration.is_some_and(|max| {
+            time.duration_since(file.start).is_ok_and(|elapsed| elapsed >= max)
+        });
+        too_big || too_old
+    }
+
+    fn rotate(&mut self, time: SystemTime) -> Result<(), pcap::Error>{
+        if let Some(mut file) = self.current.take(){
+            file.savefile.flush()?;
+            self.files.push_back(file.path);
+        }
+        let seconds = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let path = self.config.dir.join(format!("{}_{}_{}.pcap", RECORD_FILE_PREFIX, seconds, self.file_index));
+        self.file_index += 1;
+        self.current = Some(RecordFile{
+            savefile: open_savefile(self.linktype, &path)?,
+            path,
+            size: 0,
+            start: time,
+        });
+        self.apply_retention();
+        Ok(())
+    }
+
+    fn apply_retention(&mut self){
+        let Some(max_files) = self.config.max_files else {
+            return;
+        };
+        // The file being written counts in the limit
+        while self.files.len() + 1 > max_files.max(1){
+            if let Some(path) = self.files.pop_front(){
+                // A file deleted by hand is not an error
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+impl Drop for Recorder{
+    fn drop(&mut self){
+        let _ = self.flush();
+    }
+}
+
+// Last minutes of captured frames kept in memory, shared with the interface
+// so they can be saved when something interesting shows up
+pub struct PacketRing{
+    linktype: Linktype,
+    duration: Duration,
+    max_bytes: usize,
+    bytes: usize,
+    packets: VecDeque<(PacketHeader, Vec<u8>)>,
+}
+
+impl PacketRing{
+    pub fn new(linktype: Linktype, duration: Duration, max_bytes: usize) -> PacketRing{
+        PacketRing{
+            linktype,
+            duration,
+            max_bytes,
+            bytes: 0,
+            packets: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, packet: &Packet){
+        self.bytes += packet.data.len();
+        self.packets.push_back((*packet.header, packet.data.to_vec()));
+
+        let newest = header_time(packet.header);
+        while let Some((header, data)) = self.packets.front(){
+            let expired = newest.duration_since(header_time(header)).is_ok_and(|age| age > self.duration);
+            if !expired && self.bytes <= self.max_bytes{
+                break;
+            }
+            self.bytes -= data.len();
+            self.packets.pop_front();
+        }
+    }
+
+    pub fn get_duration(&self) -> Duration{
+        self.duration
+    }
+
+    pub fn len(&self) -> usize{
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.packets.is_empty()
+    }
+
+    // Writes the packets of the last `duration` to a pcap file, returns the number of packets written
+    pub fn save(&self, path: &Path, duration: Duration) -> Result<usize, pcap::Error>{
+        let Some((newest, _)) = self.packets.back() else {
+            return Ok(0);
+        };
+        let newest = header_time(newest);
+        let mut savefile = open_savefile(self.linktype, path)?;
+        let mut count = 0;
+        for (header, data) in &self.packets{
+            if newest.duration_since(header_time(header)).is_ok_and(|age| age <= duration){
+                savefile.write(&Packet::new(header, data));
+                count += 1;
+            }
+        }
+        savefile.flush()?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,407 @@
+use crate::engine::{ColorMode, GroupMode, LayoutMode, Session};
+use crate::capture::{NetworkManager, RecorderConfig};
+use crate::data::{AddressClass, Alert, GeoIpDatabase, NodeData, OuiTable, Rule, ScanConfig, TimeWindow};
+use crate::export::{self, ExportNode, GraphFormat, StatsFormat};
+use crate::report;
+use crate::resolver::{self, ResolverConfig};
+use clap::Parser;
+use serde::Deserialize;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Command line options, every option can also be set in the TOML config file.
+// Options given on the command line take precedence over the config file.
+#[derive(Parser, Deserialize, Debug, Default)]
+#[command(name = "network_vizualizer", about = "Visualize IP packets paths in a network")]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Network interface to capture from, skips the device selection menu
+    #[arg(short, long)]
+    pub interface: Option<String>,
+
+    /// Read packets from a pcap file instead of a network interface
+    #[arg(short = 'r', long)]
+    pub pcap_file: Option<PathBuf>,
+
+    /// BPF filter applied to the capture, e.g. "tcp port 443"
+    #[arg(short, long)]
+    pub filter: Option<String>,
+
+    /// Maximum number of packets drawn at the same time
+    #[arg(long)]
+    pub max_packets: Option<u32>,
+
+    /// Node placement strategy: random, spiral or world (needs --geoip-city)
+    #[arg(long)]
+    pub layout: Option<LayoutMode>,
+
+    /// Node attribute giving its color: none, vendor, country, asn, vlan or class
+    #[arg(long)]
+    pub color_by: Option<ColorMode>,
+
+    /// How multicast and broadcast destinations are drawn: node, bus or burst (packets fan out to the local nodes) [default: bus]
+    #[arg(long)]
+    pub group_mode: Option<GroupMode>,
+
+    /// Address classes not drawn: multicast, broadcast, link-local, loopback, private, public
+    #[arg(long, value_delimiter = ',')]
+    pub hide_classes: Option<Vec<AddressClass>>,
+
+    /// IEEE OUI file (oui.txt or oui.csv) used to name the vendors of MAC addresses [default: small embedded list]
+    #[arg(long)]
+    pub oui_file: Option<PathBuf>,
+
+    /// MaxMind City database (GeoLite2-City.mmdb) giving the country and city of public addresses
+    #[arg(long)]
+    pub geoip_city: Option<PathBuf>,
+
+    /// MaxMind ASN database (GeoLite2-ASN.mmdb) giving the AS of public addresses
+    #[arg(long)]
+    pub geoip_asn: Option<PathBuf>,
+
+    /// Also show every Ethernet frame, IP or not, between nodes keyed by MAC address in a MAC view, not in headless mode
+    #[arg(long)]
+    pub l2: bool,
+
+    /// Make nodes unique per (VLAN, IP), for networks reusing the same addresses on several VLANs
+    #[arg(long)]
+    pub vlan_nodes: bool,
+
+    /// Show the packets inside GRE, VXLAN, GENEVE and IP-in-IP tunnels, the tunnel endpoints get an outer view
+    #[arg(long)]
+    pub decapsulate: bool,
+
+    /// Addresses of this host, added to the ones of the capture interface, their nodes are pinned to the center
+    #[arg(long, value_delimiter = ',')]
+    pub local_addresses: Option<Vec<IpAddr>>,
+
+    /// Leave the nodes of this host where they are placed instead of pinning them to the center
+    #[arg(long)]
+    pub no_pin_local: bool,
+
+    /// Seconds of capture new nodes and connections glow for, 0 disables it [default: 30]
+    #[arg(long)]
+    pub new_highlight: Option<u64>,
+
+    /// Saved session whose nodes and connections are not highlighted as new, not in headless mode
+    #[arg(long)]
+    pub baseline: Option<PathBuf>,
+
+    /// Seconds over which the distinct ports and hosts contacted by a source are counted [default: 60]
+    #[arg(long)]
+    pub scan_window: Option<u64>,
+
+    /// Distinct ports of one host raising a port scan alert, 0 disables it [default: 20]
+    #[arg(long)]
+    pub scan_ports: Option<usize>,
+
+    /// Distinct hosts on one port raising a host sweep alert, 0 disables it [default: 50]
+    #[arg(long)]
+    pub scan_hosts: Option<usize>,
+
+    /// Alert rule, repeatable: "<new|port:N|sent>SIZE[/WINDOW]> [src:ADDR] [dst:ADDR] [=> ACTION,...]"
+    /// with ADDR an IP, a CIDR prefix or an address class and ACTION highlight, sound, log:FILE or webhook:URL
+    #[arg(long = "rule")]
+    pub rules: Option<Vec<Rule>>,
+
+    /// TOML file with default values for these options
+    #[arg(short, long)]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
+    /// Stop after this many seconds
+    #[arg(short, long)]
+    pub duration: Option<u64>,
+
+    /// Directory where the report is written when the program ends
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Run without a window, printing statistics to stdout
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Seconds between two summaries in headless mode [default: 10]
+    #[arg(long)]
+    pub summary_interval: Option<u64>,
+
+    /// Number of nodes listed in each headless summary [default: 10]
+    #[arg(long)]
+    pub top: Option<usize>,
+
+    /// Graph formats written to the output directory at the end: graphml, gexf, dot
+    #[arg(long, value_delimiter = ',')]
+    pub export_graph: Option<Vec<GraphFormat>>,
+
+    /// Node and pair statistics formats written to the output directory at the end: csv, jsonl
+    #[arg(long, value_delimiter = ',')]
+    pub export_stats: Option<Vec<StatsFormat>>,
+
+    /// Alert formats written to the output directory at the end: csv, jsonl
+    #[arg(long, value_delimiter = ',')]
+    pub export_alerts: Option<Vec<StatsFormat>>,
+
+    /// Only export the statistics of the last seconds of the capture, at most one hour
+    #[arg(long)]
+    pub stats_window: Option<u64>,
+
+    /// Record every captured frame to rolling pcap files in this directory
+    #[arg(long)]
+    pub record_dir: Option<PathBuf>,
+
+    /// Start a new recording file after this many megabytes
+    #[arg(long)]
+    pub record_max_size: Option<u64>,
+
+    /// Start a new recording file after this many seconds
+    #[arg(long)]
+    pub record_max_duration: Option<u64>,
+
+    /// Keep at most this many recording files, the oldest are deleted
+    #[arg(long)]
+    pub record_max_files: Option<usize>,
+
+    /// Minutes of packets kept in memory to be saved from the interface, off by default
+    #[arg(long)]
+    pub ring_minutes: Option<u64>,
+
+    /// Look up the names of the nodes without one with PTR queries, off by default so nothing is sent, not in headless mode
+    #[arg(long)]
+    pub reverse_dns: bool,
+
+    /// DNS server used for the reverse lookups, e.g. 127.0.0.1:53 [default: first nameserver of /etc/resolv.conf]
+    #[arg(long)]
+    pub resolver: Option<SocketAddr>,
+
+    /// Maximum number of reverse lookups per second [default: 10]
+    #[arg(long)]
+    pub reverse_dns_rate: Option<u32>,
+}
+
+impl Args {
+    pub fn load() -> Result<Args, String> {
+        let args = Args::parse();
+        let args = match &args.config {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("Could not read config file {}: {}", path.display(), e))?;
+                let config: Args = toml::from_str(&text)
+                    .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+                args.or(config)
+            }
+            None => args,
+        };
+        if args.export_graph.is_some() && args.output_dir.is_none() {
+            return Err("--export-graph requires --output-dir".to_string());
+        }
+        if args.export_stats.is_some() && args.output_dir.is_none() {
+            return Err("--export-stats requires --output-dir".to_string());
+        }
+        if args.export_alerts.is_some() && args.output_dir.is_none() {
+            return Err("--export-alerts requires --output-dir".to_string());
+        }
+        // The headless statistics have no names, the looked up ones would never be shown
+        if args.headless && args.reverse_dns {
+            return Err("--reverse-dns is not available in headless mode".to_string());
+        }
+        // New nodes are only highlighted and frames only drawn in the window
+        if args.headless && args.baseline.is_some() {
+            return Err("--baseline is not available in headless mode".to_string());
+        }
+        if args.headless && args.l2 {
+            return Err("--l2 is not available in headless mode".to_string());
+        }
+        Ok(args)
+    }
+
+    pub fn recorder_config(&self) -> Option<RecorderConfig> {
+        Some(RecorderConfig {
+            dir: self.record_dir.clone()?,
+            max_file_size: self.record_max_size.map(|megabytes| megabytes * 1024 * 1024),
+            max_file_duration: self.record_max_duration.map(Duration::from_secs),
+            max_files: self.record_max_files,
+        })
+    }
+
+    // Resolver configuration when reverse lookups are enabled
+    pub fn resolver_config(&self) -> Result<Option<ResolverConfig>, String> {
+        if !self.reverse_dns {
+            return Ok(None);
+        }
+        let server = self.resolver.or_else(resolver::system_resolver)
+            .ok_or("No DNS server found for --reverse-dns, set one with --resolver")?;
+        let mut config = ResolverConfig::new(server);
+        if let Some(rate) = self.reverse_dns_rate {
+            config.max_per_second = rate;
+        }
+        Ok(Some(config))
+    }
+
+    // Vendor table read from --oui-file, None to keep the embedded one
+    pub fn oui_table(&self) -> Result<Option<OuiTable>, String> {
+        let Some(path) = &self.oui_file else {
+            return Ok(None);
+        };
+        OuiTable::load(path)
+            .map(Some)
+            .map_err(|e| format!("Could not read OUI file {}: {}", path.display(), e))
+    }
+
+    // Databases given with --geoip-city and --geoip-asn, None if there are none
+    pub fn geoip_database(&self) -> Result<Option<GeoIpDatabase>, String> {
+        let mut database = GeoIpDatabase::new();
+        if let Some(path) = &self.geoip_city {
+            database.load_city(path).map_err(|e| format!("Could not read GeoIP database {}: {}", path.display(), e))?;
+        }
+        if let Some(path) = &self.geoip_asn {
+            database.load_asn(path).map_err(|e| format!("Could not read GeoIP database {}: {}", path.display(), e))?;
+        }
+        Ok(Some(database).filter(|database| !database.is_empty()))
+    }
+
+    // Session given with --baseline
+    pub fn baseline(&self) -> Result<Option<Session>, String> {
+        self.baseline.as_ref()
+            .map(|path| Session::load(path).map_err(|e| format!("Could not read baseline session {}: {}", path.display(), e)))
+            .transpose()
+    }
+
+    pub fn scan_config(&self) -> ScanConfig {
+        let mut config = ScanConfig::default();
+        if let Some(seconds) = self.scan_window {
+            config.window = Duration::from_secs(seconds);
+        }
+        if let Some(ports) = self.scan_ports {
+            config.port_threshold = ports;
+        }
+        if let Some(hosts) = self.scan_hosts {
+            config.host_threshold = hosts;
+        }
+        config
+    }
+
+    pub fn stats_window(&self) -> TimeWindow {
+        match self.stats_window {
+            Some(seconds) => TimeWindow::Last(Duration::from_secs(seconds)),
+            None => TimeWindow::All,
+        }
+    }
+
+    fn or(self, config: Args) -> Args {
+        Args {
+            interface: self.interface.or(config.interface),
+            pcap_file: self.pcap_file.or(config.pcap_file),
+            filter: self.filter.or(config.filter),
+            max_packets: self.max_packets.or(config.max_packets),
+            layout: self.layout.or(config.layout),
+            color_by: self.color_by.or(config.color_by),
+            group_mode: self.group_mode.or(config.group_mode),
+            hide_classes: self.hide_classes.or(config.hide_classes),
+            oui_file: self.oui_file.or(config.oui_file),
+            geoip_city: self.geoip_city.or(config.geoip_city),
+            geoip_asn: self.geoip_asn.or(config.geoip_asn),
+            l2: self.l2 || config.l2,
+            vlan_nodes: self.vlan_nodes || config.vlan_nodes,
+            decapsulate: self.decapsulate || config.decapsulate,
+            local_addresses: self.local_addresses.or(config.local_addresses),
+            no_pin_local: self.no_pin_local || config.no_pin_local,
+            new_highlight: self.new_highlight.or(config.new_highlight),
+            baseline: self.baseline.or(config.baseline),
+            scan_window: self.scan_window.or(config.scan_window),
+            scan_ports: self.scan_ports.or(config.scan_ports),
+            scan_hosts: self.scan_hosts.or(config.scan_hosts),
+            rules: self.rules.or(config.rules),
+            config: self.config,
+            duration: self.duration.or(config.duration),
+            output_dir: self.output_dir.or(config.output_dir),
+            headless: self.headless || config.headless,
+            summary_interval: self.summary_interval.or(config.summary_interval),
+            top: self.top.or(config.top),
+            export_graph: self.export_graph.or(config.export_graph),
+            export_stats: self.export_stats.or(config.export_stats),
+            export_alerts: self.export_alerts.or(config.export_alerts),
+            stats_window: self.stats_window.or(config.stats_window),
+            record_dir: self.record_dir.or(config.record_dir),
+            record_max_size: self.record_max_size.or(config.record_max_size),
+            record_max_duration: self.record_max_duration.or(config.record_max_duration),
+            record_max_files: self.record_max_files.or(config.record_max_files),
+            ring_minutes: self.ring_minutes.or(config.ring_minutes),
+            reverse_dns: self.reverse_dns || config.reverse_dns,
+            resolver: self.resolver.or(config.resolver),
+            reverse_dns_rate: self.reverse_dns_rate.or(config.reverse_dns_rate),
+        }
+    }
+}
+
+// Opens the capture source and filter given in the arguments,
+// returns false if no source was given
+pub fn open_capture(args: &Args, network_manager: &mut NetworkManager) -> Result<bool, String> {
+    let opened = if let Some(path) = &args.pcap_file {
+        network_manager.open_file(path)
+    } else if let Some(interface) = &args.interface {
+        network_manager.open_device(interface.as_str())
+    } else {
+        return Ok(false);
+    };
+    opened.map_err(|e| format!("Could not open capture: {}", e))?;
+    set_filter(args, network_manager)?;
+    start_recording(args, network_manager)?;
+    Ok(true)
+}
+
+// Addresses of the capture interface and the ones given with --local-addresses
+pub fn local_addresses(args: &Args, network_manager: &NetworkManager) -> Vec<IpAddr> {
+    let mut addresses = network_manager.get_local_addresses().clone();
+    for address in args.local_addresses.iter().flatten() {
+        if !addresses.contains(address) {
+            addresses.push(*address);
+        }
+    }
+    addresses
+}
+
+pub fn start_recording(args: &Args, network_manager: &mut NetworkManager) -> Result<(), String> {
+    let Some(config) = args.recorder_config() else {
+        return Ok(());
+    };
+    let dir = config.dir.clone();
+    network_manager
+        .start_recording(config)
+        .map_err(|e| format!("Could not record to {}: {}", dir.display(), e))
+}
+
+pub fn set_filter(args: &Args, network_manager: &mut NetworkManager) -> Result<(), String> {
+    let Some(filter) = &args.filter else {
+        return Ok(());
+    };
+    network_manager
+        .set_filter(filter)
+        .map_err(|e| format!("Invalid filter '{}': {}", filter, e))
+}
+
+// Writes the report and the requested exports to the output directory,
+// `stats` are the nodes inside the statistics window, returns the paths of the written files
+pub fn write_outputs(args: &Args, nodes: &[ExportNode], stats: &[NodeData], alerts: &[Alert]) -> Result<Vec<PathBuf>, String> {
+    let Some(dir) = &args.output_dir else {
+        return Ok(Vec::new());
+    };
+    let error = |e: std::io::Error| format!("Could not write to {}: {}", dir.display(), e);
+    let mut paths = vec![report::write_report_to_dir(dir, nodes.iter().map(|node| node.data)).map_err(error)?];
+    for format in args.export_graph.iter().flatten() {
+        paths.push(export::write_graph_to_dir(dir, *format, nodes).map_err(error)?);
+    }
+    for format in args.export_stats.iter().flatten() {
+        paths.extend(export::write_stats_to_dir(dir, *format, stats).map_err(error)?);
+    }
+    for format in args.export_alerts.iter().flatten() {
+        paths.push(export::write_alerts_to_dir(dir, *format, alerts).map_err(error)?);
+    }
+    Ok(paths)
+}
+
+pub fn exit_with_error(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
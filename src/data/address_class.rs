@@ -0,0 +1,101 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+// Scope of an address, multicast and broadcast ones are shared by many hosts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressClass{
+    Multicast,
+    Broadcast,
+    // Unspecified addresses too, they are only used on the link
+    LinkLocal,
+    Loopback,
+    // RFC 1918, shared, documentation and unique local addresses
+    Private,
+    Public,
+}
+
+impl AddressClass{
+    pub const ALL: [AddressClass; 6] = [
+        AddressClass::Multicast,
+        AddressClass::Broadcast,
+        AddressClass::LinkLocal,
+        AddressClass::Loopback,
+        AddressClass::Private,
+        AddressClass::Public,
+    ];
+
+    // Only the limited broadcast is known, directed ones depend on the netmask
+    pub fn of(ip: &IpAddr) -> AddressClass{
+        match ip {
+            IpAddr::V4(ip) => {
+                let shared = ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64;
+                if ip.is_broadcast(){
+                    AddressClass::Broadcast
+                }else if ip.is_multicast(){
+                    AddressClass::Multicast
+                }else if ip.is_loopback(){
+                    AddressClass::Loopback
+                }else if ip.is_link_local() || ip.is_unspecified(){
+                    AddressClass::LinkLocal
+                }else if ip.is_private() || ip.is_documentation() || shared{
+                    AddressClass::Private
+                }else{
+                    AddressClass::Public
+                }
+            }
+            IpAddr::V6(ip) => {
+                if let Some(ipv4) = ip.to_ipv4_mapped(){
+                    return AddressClass::of(&IpAddr::V4(ipv4));
+                }
+                let segment = ip.segments()[0];
+                if ip.is_multicast(){
+                    AddressClass::Multicast
+                }else if ip.is_loopback(){
+                    AddressClass::Loopback
+                }else if segment & 0xffc0 == 0xfe80 || ip.is_unspecified(){
+                    AddressClass::LinkLocal
+                }else if segment & 0xfe00 == 0xfc00{
+                    AddressClass::Private
+                }else{
+                    AddressClass::Public
+                }
+            }
+        }
+    }
+
+    // Addresses of a group of hosts rather than of a single one
+    pub fn is_group(&self) -> bool{
+        matches!(self, AddressClass::Multicast | AddressClass::Broadcast)
+    }
+
+    // Hosts of the local network
+    pub fn is_local(&self) -> bool{
+        matches!(self, AddressClass::LinkLocal | AddressClass::Private)
+    }
+}
+
+impl FromStr for AddressClass{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AddressClass::ALL.into_iter()
+            .find(|class| class.to_string() == s.to_lowercase())
+            .ok_or_else(|| format!("Unknown address class '{}', expected 'multicast', 'broadcast', 'link-local', 'loopback', 'private' or 'public'", s))
+    }
+}
+
+impl fmt::Display for AddressClass{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressClass::Multicast => write!(f, "multicast"),
+            AddressClass::Broadcast => write!(f, "broadcast"),
+            AddressClass::LinkLocal => write!(f, "link-local"),
+            AddressClass::Loopback => write!(f, "loopback"),
+            AddressClass::Private => write!(f, "private"),
+            AddressClass::Public => write!(f, "public"),
+        }
+    }
+}
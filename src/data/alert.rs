@@ -0,0 +1,74 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::data::{NodeKey, RuleAction};
+use crate::math::convert_bytes_str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertKind{
+    // One source contacting many ports of one host
+    PortScan,
+    // One source contacting many hosts on one port
+    HostSweep,
+    // Condition of a user rule met
+    Rule,
+}
+
+impl fmt::Display for AlertKind{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertKind::PortScan => write!(f, "port-scan"),
+            AlertKind::HostSweep => write!(f, "host-sweep"),
+            AlertKind::Rule => write!(f, "rule"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert{
+    // Time of the packet that raised the alert
+    pub timestamp: SystemTime,
+    pub kind: AlertKind,
+    // Node the alert is about, the one flashed on the screen
+    pub source: NodeKey,
+    // Host scanned by a port scan, destination of a port rule
+    pub target: Option<IpAddr>,
+    // Port swept by a host sweep or matched by a port rule
+    pub port: Option<u16>,
+    pub protocol: u8,
+    // Distinct ports or hosts contacted inside the window, bytes sent for a rate rule
+    pub count: usize,
+    // Condition of the rule that raised the alert
+    pub rule: Option<String>,
+    pub actions: Vec<RuleAction>,
+}
+
+impl fmt::Display for Alert{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            AlertKind::PortScan => {
+                let target = self.target.map(|ip| ip.to_string()).unwrap_or_default();
+                write!(f, "Port scan from {} to {}, {} ports", self.source, target, self.count)
+            }
+            AlertKind::HostSweep => {
+                let port = self.port.map(|port| port.to_string()).unwrap_or_default();
+                write!(f, "Host sweep from {} on port {}, {} hosts", self.source, port, self.count)
+            }
+            AlertKind::Rule => {
+                write!(f, "Rule '{}' matched by {}", self.rule.as_deref().unwrap_or_default(), self.source)?;
+                if let Some(target) = self.target{
+                    write!(f, " to {}", target)?;
+                }
+                if let Some(port) = self.port{
+                    write!(f, " on port {}", port)?;
+                }
+                if self.count > 0{
+                    write!(f, ", {} sent", convert_bytes_str(self.count as u64))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+use crate::data::IPPacketInfo;
+
+// Way a packet goes relative to the local host, the addresses of the capture interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PacketDirection{
+    Inbound,
+    // Packets the host sends to itself too
+    Outbound,
+    // Neither from nor to the host, seen through a mirror port or a router
+    Transit,
+}
+
+impl PacketDirection{
+    pub const ALL: [PacketDirection; 3] = [PacketDirection::Inbound, PacketDirection::Outbound, PacketDirection::Transit];
+
+    pub fn of(packet: &IPPacketInfo, local_addresses: &[IpAddr]) -> PacketDirection{
+        if local_addresses.contains(&packet.source){
+            PacketDirection::Outbound
+        }else if local_addresses.contains(&packet.dest){
+            PacketDirection::Inbound
+        }else{
+            PacketDirection::Transit
+        }
+    }
+}
+
+impl fmt::Display for PacketDirection{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketDirection::Inbound => write!(f, "inbound"),
+            PacketDirection::Outbound => write!(f, "outbound"),
+            PacketDirection::Transit => write!(f, "transit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectionStats{
+    pub packet_count: u64,
+    pub packet_bytes: u64,
+}
+
+// Packets and bytes of each direction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectionTotals{
+    inbound: DirectionStats,
+    outbound: DirectionStats,
+    transit: DirectionStats,
+}
+
+impl DirectionTotals{
+    pub fn add_packet(&mut self, direction: PacketDirection, packet: &IPPacketInfo){
+        let stats = match direction {
+            PacketDirection::Inbound => &mut self.inbound,
+            PacketDirection::Outbound => &mut self.outbound,
+            PacketDirection::Transit => &mut self.transit,
+        };
+        stats.packet_count += 1;
+        stats.packet_bytes += packet.payload_len as u64;
+    }
+
+    pub fn get(&self, direction: PacketDirection) -> DirectionStats{
+        match direction {
+            PacketDirection::Inbound => self.inbound,
+            PacketDirection::Outbound => self.outbound,
+            PacketDirection::Transit => self.transit,
+        }
+    }
+
+    pub fn clear(&mut self){
+        *self = DirectionTotals::default();
+    }
+}
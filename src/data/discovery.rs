@@ -0,0 +1,217 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::data::NodeKey;
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+const ARP_HARDWARE_ETHERNET: u16 = 1;
+const ARP_PROTOCOL_IPV4: u16 = 0x0800;
+const DHCP_MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const DHCP_OPTIONS_START: usize = 240;
+const OPTION_PAD: u8 = 0;
+const OPTION_HOSTNAME: u8 = 12;
+const OPTION_REQUESTED_IP: u8 = 50;
+const OPTION_LEASE_TIME: u8 = 51;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_VENDOR_CLASS: u8 = 60;
+const OPTION_END: u8 = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress{
+    // Key of the node of the MAC address in the MAC view: the link-local address derived
+    // from it (modified EUI-64), so the engine stays keyed by IP address
+    pub fn to_node_ip(&self) -> IpAddr{
+        let [a, b, c, d, e, g] = self.0;
+        IpAddr::V6(Ipv6Addr::new(
+            0xfe80, 0, 0, 0,
+            u16::from_be_bytes([a ^ 0x02, b]),
+            u16::from_be_bytes([c, 0xff]),
+            u16::from_be_bytes([0xfe, d]),
+            u16::from_be_bytes([e, g]),
+        ))
+    }
+
+    pub fn from_node_ip(ip: &IpAddr) -> Option<MacAddress>{
+        let IpAddr::V6(ip) = ip else {
+            return None;
+        };
+        let octets = ip.octets();
+        if octets[..8] != [0xfe, 0x80, 0, 0, 0, 0, 0, 0] || octets[11..13] != [0xff, 0xfe]{
+            return None;
+        }
+        Some(MacAddress([octets[8] ^ 0x02, octets[9], octets[10], octets[13], octets[14], octets[15]]))
+    }
+}
+
+impl fmt::Display for MacAddress{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", a, b, c, d, e, g)
+    }
+}
+
+impl FromStr for MacAddress{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let octets: Vec<u8> = s.split([':', '-'])
+            .map(|octet| u8::from_str_radix(octet, 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid MAC address '{}'", s))?;
+        let octets: [u8; 6] = octets.try_into().map_err(|_| format!("Invalid MAC address '{}'", s))?;
+        Ok(MacAddress(octets))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscoverySource{
+    Arp,
+    Dhcp,
+    // Source or destination of a frame, for the nodes of the MAC view
+    Ethernet,
+}
+
+// DHCP message types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DhcpMessageType{
+    Discover,
+    Offer,
+    Request,
+    Decline,
+    Ack,
+    Nak,
+    Release,
+    Inform,
+}
+
+impl DhcpMessageType{
+    fn from_code(code: u8) -> Option<DhcpMessageType>{
+        match code {
+            1 => Some(DhcpMessageType::Discover),
+            2 => Some(DhcpMessageType::Offer),
+            3 => Some(DhcpMessageType::Request),
+            4 => Some(DhcpMessageType::Decline),
+            5 => Some(DhcpMessageType::Ack),
+            6 => Some(DhcpMessageType::Nak),
+            7 => Some(DhcpMessageType::Release),
+            8 => Some(DhcpMessageType::Inform),
+            _ => None,
+        }
+    }
+}
+
+// What a DHCP message tells about its client
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DhcpInfo{
+    pub hostname: Option<String>,
+    pub vendor_class: Option<String>,
+    // Seconds, given by the server in the acknowledgement
+    pub lease_time: Option<u32>,
+    pub server: Option<IpAddr>,
+}
+
+// Address of a host on the local network, learned from an ARP or DHCP message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostDiscovery{
+    pub ip: IpAddr,
+    pub mac: MacAddress,
+    pub source: DiscoverySource,
+    pub dhcp: Option<DhcpInfo>,
+    pub timestamp: SystemTime,
+    // Inner VLAN tag of the frame, set by the capture
+    pub vlan: Option<u16>,
+}
+
+impl HostDiscovery{
+    pub fn node_key(&self, per_vlan: bool) -> NodeKey{
+        NodeKey::new(self.ip, self.vlan.filter(|_| per_vlan))
+    }
+}
+
+// Sender address of an ARP request or reply, probes without sender address are skipped
+pub fn parse_arp(payload: &[u8], timestamp: SystemTime) -> Option<HostDiscovery>{
+    let header = payload.get(0..8)?;
+    let hardware = u16::from_be_bytes([header[0], header[1]]);
+    let protocol = u16::from_be_bytes([header[2], header[3]]);
+    if hardware != ARP_HARDWARE_ETHERNET || protocol != ARP_PROTOCOL_IPV4 || header[4] != 6 || header[5] != 4{
+        return None;
+    }
+    let mac: [u8; 6] = payload.get(8..14)?.try_into().ok()?;
+    let ip: [u8; 4] = payload.get(14..18)?.try_into().ok()?;
+    if ip == [0; 4]{
+        return None;
+    }
+    Some(HostDiscovery{
+        ip: IpAddr::V4(Ipv4Addr::from(ip)),
+        mac: MacAddress(mac),
+        source: DiscoverySource::Arp,
+        dhcp: None,
+        timestamp,
+        vlan: None,
+    })
+}
+
+// Client of a DHCP message whose address is known: the one given in an acknowledgement,
+// or the one requested or used by the client
+pub fn parse_dhcp(payload: &[u8], timestamp: SystemTime) -> Option<HostDiscovery>{
+    if payload.get(236..DHCP_OPTIONS_START)? != DHCP_MAGIC_COOKIE || payload[1] != 1 || payload[2] != 6{
+        return None;
+    }
+    let read_ip = |pos: usize| -> Option<Ipv4Addr> {
+        let octets: [u8; 4] = payload.get(pos..pos + 4)?.try_into().ok()?;
+        Some(Ipv4Addr::from(octets)).filter(|ip| !ip.is_unspecified())
+    };
+    let client_ip = read_ip(12);
+    let your_ip = read_ip(16);
+    let mac: [u8; 6] = payload.get(28..34)?.try_into().ok()?;
+
+    let mut message_type = None;
+    let mut requested_ip = None;
+    let mut info = DhcpInfo::default();
+    let mut pos = DHCP_OPTIONS_START;
+    while let Some(&code) = payload.get(pos){
+        if code == OPTION_END{
+            break;
+        }
+        if code == OPTION_PAD{
+            pos += 1;
+            continue;
+        }
+        let length = *payload.get(pos + 1)? as usize;
+        let data = payload.get(pos + 2..pos + 2 + length)?;
+        pos += 2 + length;
+        let text = || Some(String::from_utf8_lossy(data).trim_end_matches('\0').to_string()).filter(|text| !text.is_empty());
+        let ip = || <[u8; 4]>::try_from(data).ok().map(Ipv4Addr::from);
+        match code {
+            OPTION_MESSAGE_TYPE => message_type = data.first().and_then(|code| DhcpMessageType::from_code(*code)),
+            OPTION_HOSTNAME => info.hostname = text(),
+            OPTION_VENDOR_CLASS => info.vendor_class = text(),
+            OPTION_LEASE_TIME => info.lease_time = <[u8; 4]>::try_from(data).ok().map(u32::from_be_bytes),
+            OPTION_REQUESTED_IP => requested_ip = ip(),
+            OPTION_SERVER_ID => info.server = ip().map(IpAddr::V4),
+            _ => {}
+        }
+    }
+
+    let ip = match message_type? {
+        DhcpMessageType::Ack => your_ip.or(client_ip),
+        DhcpMessageType::Request => requested_ip.or(client_ip),
+        DhcpMessageType::Inform => client_ip,
+        _ => None,
+    }?;
+    Some(HostDiscovery{
+        ip: IpAddr::V4(ip),
+        mac: MacAddress(mac),
+        source: DiscoverySource::Dhcp,
+        dhcp: Some(info),
+        timestamp,
+        vlan: None,
+    })
+}
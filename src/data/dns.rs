@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub const DNS_PORT: u16 = 53;
+pub const MDNS_PORT: u16 = 5353;
+
+const HEADER_LEN: usize = 12;
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+// Bounds the compression pointers and CNAME chains followed, against loops in crafted packets
+const MAX_JUMPS: usize = 16;
+
+// Name of an address learned from a DNS or mDNS response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord{
+    pub ip: IpAddr,
+    pub name: String,
+    pub ttl: u32,
+}
+
+// Name of the PTR record of an address, such as 4.3.2.1.in-addr.arpa
+pub fn reverse_name(ip: &IpAddr) -> String{
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, d] = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(ip) => {
+            let mut name = String::with_capacity(72);
+            for octet in ip.octets().iter().rev(){
+                name.push_str(&format!("{:x}.{:x}.", octet & 0x0f, octet >> 4));
+            }
+            name.push_str("ip6.arpa");
+            name
+        }
+    }
+}
+
+// Recursive query for the PTR record of an address
+pub fn build_ptr_query(id: u16, ip: &IpAddr) -> Vec<u8>{
+    let mut query = Vec::with_capacity(HEADER_LEN + 80);
+    query.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired, one question
+    query.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in reverse_name(ip).split('.'){
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    // Class IN
+    query.extend_from_slice(&[0x00, 0x01]);
+    query
+}
+
+// Identifier of a message, to match a response with its query
+pub fn message_id(message: &[u8]) -> Option<u16>{
+    read_u16(message, 0)
+}
+
+pub fn is_dns_port(port: u16) -> bool{
+    port == DNS_PORT || port == MDNS_PORT
+}
+
+// Parses a DNS message carried over TCP, prefixed by its length. Messages split
+// over several segments are ignored
+pub fn parse_tcp_response(payload: &[u8]) -> Vec<DnsRecord>{
+    let Some(length) = payload.get(0..2).map(|length| u16::from_be_bytes([length[0], length[1]]) as usize) else {
+        return Vec::new();
+    };
+    payload.get(2..2 + length).map(parse_response).unwrap_or_default()
+}
+
+// Addresses and names of the A, AAAA and PTR records of a response, CNAME chains
+// are followed back to the name that was asked. Queries and errors give no record,
+// a truncated message gives the records read before the end
+pub fn parse_response(message: &[u8]) -> Vec<DnsRecord>{
+    let mut records = Vec::new();
+    let Some(flags) = read_u16(message, 2) else {
+        return records;
+    };
+    let is_response = flags & 0x8000 != 0;
+    let error = flags & 0x000f != 0;
+    if !is_response || error{
+        return records;
+    }
+    let counts: Vec<u16> = (4..HEADER_LEN).step_by(2).filter_map(|pos| read_u16(message, pos)).collect();
+    let [questions, answers, authorities, additionals] = counts[..] else {
+        return records;
+    };
+
+    let mut pos = HEADER_LEN;
+    for _ in 0..questions{
+        let Some((_, next)) = read_name(message, pos) else {
+            return records;
+        };
+        pos = next + 4;
+    }
+
+    let mut addresses = Vec::new();
+    // Target of a CNAME to the name it is an alias of
+    let mut aliases: HashMap<String, String> = HashMap::new();
+    for _ in 0..(answers as u32 + authorities as u32 + additionals as u32){
+        let Some((owner, next)) = read_name(message, pos) else {
+            break;
+        };
+        let (Some(record_type), Some(ttl), Some(length)) = (read_u16(message, next), read_u32(message, next + 4), read_u16(message, next + 8)) else {
+            break;
+        };
+        let start = next + 10;
+        let Some(data) = message.get(start..start + length as usize) else {
+            break;
+        };
+        pos = start + length as usize;
+        match record_type {
+            TYPE_A if data.len() == 4 => {
+                let ip = Ipv4Addr::new(data[0], data[1], data[2], data[3]);
+                addresses.push((owner, IpAddr::V4(ip), ttl));
+            }
+            TYPE_AAAA if data.len() == 16 => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(data);
+                addresses.push((owner, IpAddr::V6(Ipv6Addr::from(octets)), ttl));
+            }
+            TYPE_CNAME => {
+                if let Some((target, _)) = read_name(message, start){
+                    aliases.insert(target, owner);
+                }
+            }
+            TYPE_PTR => {
+                if let (Some(ip), Some((name, _))) = (parse_reverse_name(&owner), read_name(message, start)){
+                    records.push(DnsRecord{ip, name, ttl});
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (mut name, ip, ttl) in addresses{
+        for _ in 0..MAX_JUMPS{
+            match aliases.get(&name) {
+                Some(alias) => name = alias.clone(),
+                None => break,
+            }
+        }
+        records.push(DnsRecord{ip, name, ttl});
+    }
+    records
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16>{
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32>{
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Reads a possibly compressed name, returns it in lowercase without the final dot
+// with the position right after it
+fn read_name(data: &[u8], mut pos: usize) -> Option<(String, usize)>{
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let length = *data.get(pos)? as usize;
+        if length & 0xc0 == 0xc0{
+            let pointer = (length & 0x3f) << 8 | *data.get(pos + 1)? as usize;
+            end.get_or_insert(pos + 2);
+            jumps += 1;
+            if jumps > MAX_JUMPS{
+                return None;
+            }
+            pos = pointer;
+        }else if length & 0xc0 != 0{
+            return None;
+        }else if length == 0{
+            end.get_or_insert(pos + 1);
+            break;
+        }else{
+            let label = data.get(pos + 1..pos + 1 + length)?;
+            labels.push(String::from_utf8_lossy(label).to_lowercase());
+            pos += 1 + length;
+        }
+    }
+    Some((labels.join("."), end?))
+}
+
+// Address of a reverse lookup name such as 4.3.2.1.in-addr.arpa
+fn parse_reverse_name(name: &str) -> Option<IpAddr>{
+    if let Some(octets) = name.strip_suffix(".in-addr.arpa"){
+        let octets: Vec<u8> = octets.split('.').rev().map(|octet| octet.parse().ok()).collect::<Option<_>>()?;
+        let octets: [u8; 4] = octets.try_into().ok()?;
+        return Some(IpAddr::V4(Ipv4Addr::from(octets)));
+    }
+    if let Some(nibbles) = name.strip_suffix(".ip6.arpa"){
+        let nibbles: Vec<u32> = nibbles.split('.').rev().map(|nibble| {
+            if nibble.len() == 1 {nibble.chars().next()?.to_digit(16)} else {None}
+        }).collect::<Option<_>>()?;
+        if nibbles.len() != 32{
+            return None;
+        }
+        let address = nibbles.iter().fold(0u128, |address, nibble| address << 4 | *nibble as u128);
+        return Some(IpAddr::V6(Ipv6Addr::from(address)));
+    }
+    None
+}
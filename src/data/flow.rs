@@ -0,0 +1,136 @@
+use std::net::IpAddr;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::data::IPPacketInfo;
+
+// Flows kept at most, in memory and in the sessions. Once full, the flows idle for longer
+// than the timeout are forgotten, then the least recently seen ones
+pub const MAX_FLOWS: usize = 100_000;
+pub const FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+// Packets with the same addresses, transport protocol and ports, in one direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FlowKey{
+    pub source: IpAddr,
+    pub dest: IpAddr,
+    pub protocol: u8,
+    pub source_port: Option<u16>,
+    pub dest_port: Option<u16>,
+}
+
+impl FlowKey{
+    pub fn new(packet: &IPPacketInfo) -> FlowKey{
+        FlowKey{
+            source: packet.source,
+            dest: packet.dest,
+            protocol: packet.protocol,
+            source_port: packet.source_port,
+            dest_port: packet.dest_port,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowStats{
+    pub packet_count: u64,
+    pub packet_bytes: u64,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+    // First server name seen in the flow
+    #[serde(default)]
+    pub server_name: Option<String>,
+}
+
+pub struct FlowTable{
+    flows: HashMap<FlowKey, FlowStats>,
+    max_flows: usize,
+    idle_timeout: Duration,
+}
+
+impl FlowTable{
+    pub fn new() -> FlowTable{
+        FlowTable::with_limits(MAX_FLOWS, FLOW_IDLE_TIMEOUT)
+    }
+
+    pub fn with_limits(max_flows: usize, idle_timeout: Duration) -> FlowTable{
+        FlowTable{
+            flows: HashMap::new(),
+            max_flows,
+            idle_timeout,
+        }
+    }
+
+    pub fn from_entries(entries: Vec<(FlowKey, FlowStats)>) -> FlowTable{
+        let mut table = FlowTable::new();
+        table.flows = entries.into_iter().collect();
+        if let Some(newest) = table.flows.values().map(|stats| stats.last_seen).max().filter(|_| table.flows.len() > table.max_flows){
+            table.evict(newest);
+        }
+        table
+    }
+
+    pub fn add_packet(&mut self, packet: &IPPacketInfo){
+        if !self.flows.contains_key(&FlowKey::new(packet)) && self.flows.len() >= self.max_flows{
+            self.evict(packet.timestamp);
+        }
+        let stats = self.flows.entry(FlowKey::new(packet)).or_insert_with(|| FlowStats{
+            packet_count: 0,
+            packet_bytes: 0,
+            first_seen: packet.timestamp,
+            last_seen: packet.timestamp,
+            server_name: None,
+        });
+        stats.packet_count += 1;
+        stats.packet_bytes += packet.payload_len as u64;
+        stats.last_seen = stats.last_seen.max(packet.timestamp);
+        if stats.server_name.is_none(){
+            stats.server_name = packet.server_name.clone();
+        }
+    }
+
+    // Forgets the idle flows, then the least recently seen ones until a tenth of the table is free,
+    // so that a scan filling the table doesn't sort it on every packet
+    fn evict(&mut self, now: SystemTime){
+        let idle_timeout = self.idle_timeout;
+        self.flows.retain(|_, stats| now.duration_since(stats.last_seen).unwrap_or_default() <= idle_timeout);
+        let target = self.max_flows - self.max_flows / 10;
+        if self.flows.len() <= target{
+            return;
+        }
+        if target == 0{
+            self.flows.clear();
+            return;
+        }
+        let mut last_seen: Vec<SystemTime> = self.flows.values().map(|stats| stats.last_seen).collect();
+        last_seen.sort();
+        let oldest_kept = last_seen[self.flows.len() - target];
+        self.flows.retain(|_, stats| stats.last_seen >= oldest_kept);
+    }
+
+    pub fn get(&self, key: &FlowKey) -> Option<&FlowStats>{
+        self.flows.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&FlowKey, &FlowStats)>{
+        self.flows.iter()
+    }
+
+    pub fn len(&self) -> usize{
+        self.flows.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.flows.is_empty()
+    }
+
+    pub fn entries(&self) -> Vec<(FlowKey, FlowStats)>{
+        self.flows.iter().map(|(key, stats)| (*key, stats.clone())).collect()
+    }
+}
+
+impl Default for FlowTable{
+    fn default() -> Self {
+        FlowTable::new()
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
+
+// Fragments of a datagram not completed in this time are dropped
+pub const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(30);
+// Fragment bytes kept at most, the oldest datagrams are dropped beyond
+pub const MAX_FRAGMENT_BYTES: usize = 4 * 1024 * 1024;
+// Datagrams being reassembled at most
+pub const MAX_PENDING_DATAGRAMS: usize = 1024;
+
+const MAX_DATAGRAM_LEN: usize = 65535;
+const IPV4_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+const IPV4_MORE_FRAGMENTS: u16 = 0x2000;
+const IPV4_OFFSET: u16 = 0x1fff;
+const IPV6_MORE_FRAGMENTS: u16 = 0x0001;
+const IPV6_OFFSET: u16 = 0xfff8;
+
+const IP_PROTOCOL_HOP_BY_HOP: u8 = 0;
+const IP_PROTOCOL_ROUTING: u8 = 43;
+const IP_PROTOCOL_FRAGMENT: u8 = 44;
+const IP_PROTOCOL_AUTH: u8 = 51;
+const IP_PROTOCOL_DEST_OPTIONS: u8 = 60;
+
+// Fragments with the same key belong to the same datagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FragmentKey{
+    pub source: IpAddr,
+    pub dest: IpAddr,
+    pub protocol: u8,
+    pub id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IpFragment<'a>{
+    pub key: FragmentKey,
+    // Position of the data in the datagram, in bytes
+    pub offset: usize,
+    pub more: bool,
+    // IP header of the reassembled packet, the IPv6 fragment header taken out
+    pub header: Vec<u8>,
+    pub data: &'a [u8],
+}
+
+// Fragment of an IPv4 or IPv6 packet, None for a whole packet
+pub fn parse_fragment(packet: &[u8]) -> Option<IpFragment<'_>>{
+    match packet.first()? >> 4 {
+        4 => parse_ipv4_fragment(packet),
+        6 => parse_ipv6_fragment(packet),
+        _ => None,
+    }
+}
+
+fn parse_ipv4_fragment(packet: &[u8]) -> Option<IpFragment<'_>>{
+    let header_len = (packet[0] & 0x0f) as usize * 4;
+    if header_len < IPV4_HEADER_LEN || packet.len() < header_len{
+        return None;
+    }
+    let flags = u16::from_be_bytes([packet[6], packet[7]]);
+    if flags & (IPV4_MORE_FRAGMENTS | IPV4_OFFSET) == 0{
+        return None;
+    }
+    // Ethernet pads short frames, the padding is not part of the packet
+    let total_len = (u16::from_be_bytes([packet[2], packet[3]]) as usize).min(packet.len());
+    let source: [u8; 4] = packet[12..16].try_into().ok()?;
+    let dest: [u8; 4] = packet[16..20].try_into().ok()?;
+    Some(IpFragment{
+        key: FragmentKey{
+            source: IpAddr::V4(Ipv4Addr::from(source)),
+            dest: IpAddr::V4(Ipv4Addr::from(dest)),
+            protocol: packet[9],
+            id: u16::from_be_bytes([packet[4], packet[5]]) as u32,
+        },
+        offset: (flags & IPV4_OFFSET) as usize * 8,
+        more: flags & IPV4_MORE_FRAGMENTS != 0,
+        header: packet[..header_len].to_vec(),
+        data: packet.get(header_len..total_len)?,
+    })
+}
+
+// Walks the extension headers before the fragment header, they are repeated in every fragment
+fn parse_ipv6_fragment(packet: &[u8]) -> Option<IpFragment<'_>>{
+    let header = packet.get(..IPV6_HEADER_LEN)?;
+    let end = (IPV6_HEADER_LEN + u16::from_be_bytes([header[4], header[5]]) as usize).min(packet.len());
+    // Position of the next header field pointing at the current header
+    let mut next_header_at = 6;
+    let mut offset = IPV6_HEADER_LEN;
+    loop {
+        let extension = packet.get(offset..end)?;
+        match packet[next_header_at] {
+            IP_PROTOCOL_HOP_BY_HOP | IP_PROTOCOL_ROUTING | IP_PROTOCOL_DEST_OPTIONS => {
+                next_header_at = offset;
+                offset += (*extension.get(1)? as usize + 1) * 8;
+            }
+            IP_PROTOCOL_AUTH => {
+                next_header_at = offset;
+                offset += (*extension.get(1)? as usize + 2) * 4;
+            }
+            IP_PROTOCOL_FRAGMENT => {
+                let fragment = extension.get(..8)?;
+                let flags = u16::from_be_bytes([fragment[2], fragment[3]]);
+                let mut header = packet[..offset].to_vec();
+                header[next_header_at] = fragment[0];
+                let source: [u8; 16] = packet[8..24].try_into().ok()?;
+                let dest: [u8; 16] = packet[24..40].try_into().ok()?;
+                return Some(IpFragment{
+                    key: FragmentKey{
+                        source: IpAddr::V6(Ipv6Addr::from(source)),
+                        dest: IpAddr::V6(Ipv6Addr::from(dest)),
+                        protocol: fragment[0],
+                        id: u32::from_be_bytes(fragment[4..8].try_into().ok()?),
+                    },
+                    offset: (flags & IPV6_OFFSET) as usize,
+                    more: flags & IPV6_MORE_FRAGMENTS != 0,
+                    header,
+                    data: &packet[offset + 8..end],
+                }).filter(|fragment| fragment.more || fragment.offset != 0);
+            }
+            _ => return None,
+        }
+    }
+}
+
+struct Datagram{
+    // Header of the first fragment
+    header: Option<Vec<u8>>,
+    parts: BTreeMap<usize, Vec<u8>>,
+    // Known once the last fragment is in
+    len: Option<usize>,
+    bytes: usize,
+    first_seen: SystemTime,
+}
+
+impl Datagram{
+    fn is_complete(&self) -> bool{
+        let (Some(len), Some(_)) = (self.len, &self.header) else {
+            return false;
+        };
+        let mut end = 0;
+        for (offset, data) in &self.parts {
+            if *offset > end{
+                return false;
+            }
+            end = end.max(offset + data.len());
+        }
+        end >= len
+    }
+
+    // None when the datagram with its header is too long for the length field
+    fn into_packet(self) -> Option<Vec<u8>>{
+        let len = self.len.unwrap_or(0);
+        let mut packet = self.header.unwrap_or_default();
+        let header_len = packet.len();
+        let length_field = if packet[0] >> 4 == 4 { header_len + len } else { header_len - IPV6_HEADER_LEN + len };
+        let length_field = u16::try_from(length_field).ok()?;
+        packet.resize(header_len + len, 0);
+        for (offset, data) in self.parts {
+            let end = (offset + data.len()).min(len);
+            if offset < end{
+                packet[header_len + offset..header_len + end].copy_from_slice(&data[..end - offset]);
+            }
+        }
+        if packet[0] >> 4 == 4{
+            packet[2..4].copy_from_slice(&length_field.to_be_bytes());
+            // No more fragments and no offset, the don't fragment flag is kept
+            packet[6] &= 0x40;
+            packet[7] = 0;
+            packet[10..12].copy_from_slice(&[0, 0]);
+            let checksum = ipv4_checksum(&packet[..header_len]);
+            packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+        }else{
+            packet[4..6].copy_from_slice(&length_field.to_be_bytes());
+        }
+        Some(packet)
+    }
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16{
+    let mut sum: u32 = header.chunks(2).map(|word| u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)]) as u32).sum();
+    while sum > 0xffff{
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// Puts fragmented datagrams back together, with bounded memory
+pub struct Reassembler{
+    datagrams: HashMap<FragmentKey, Datagram>,
+    bytes: usize,
+    timeout: Duration,
+    max_bytes: usize,
+}
+
+impl Default for Reassembler{
+    fn default() -> Self {
+        Reassembler::new()
+    }
+}
+
+impl Reassembler{
+    pub fn new() -> Reassembler{
+        Reassembler::with_limits(FRAGMENT_TIMEOUT, MAX_FRAGMENT_BYTES)
+    }
+
+    pub fn with_limits(timeout: Duration, max_bytes: usize) -> Reassembler{
+        Reassembler{
+            datagrams: HashMap::new(),
+            bytes: 0,
+            timeout,
+            max_bytes,
+        }
+    }
+
+    // Whole IP packet once every fragment of the datagram is in
+    pub fn add(&mut self, fragment: &IpFragment, timestamp: SystemTime) -> Option<Vec<u8>>{
+        self.expire(timestamp);
+        let end = fragment.offset + fragment.data.len();
+        if end > MAX_DATAGRAM_LEN || fragment.data.len() > self.max_bytes{
+            self.remove(&fragment.key);
+            return None;
+        }
+        if !self.datagrams.contains_key(&fragment.key) && self.datagrams.len() >= MAX_PENDING_DATAGRAMS{
+            self.remove_oldest();
+        }
+        while self.bytes + fragment.data.len() > self.max_bytes{
+            self.remove_oldest();
+        }
+        let datagram = self.datagrams.entry(fragment.key).or_insert_with(|| Datagram{
+            header: None,
+            parts: BTreeMap::new(),
+            len: None,
+            bytes: 0,
+            first_seen: timestamp,
+        });
+        if fragment.offset == 0{
+            datagram.header = Some(fragment.header.clone());
+        }
+        if !fragment.more{
+            datagram.len = Some(end);
+        }
+        if let Some(previous) = datagram.parts.insert(fragment.offset, fragment.data.to_vec()){
+            datagram.bytes -= previous.len();
+            self.bytes -= previous.len();
+        }
+        datagram.bytes += fragment.data.len();
+        self.bytes += fragment.data.len();
+        if !datagram.is_complete(){
+            return None;
+        }
+        self.remove(&fragment.key).and_then(Datagram::into_packet)
+    }
+
+    // Drops the datagrams still incomplete after the timeout
+    pub fn expire(&mut self, now: SystemTime){
+        let timeout = self.timeout;
+        let expired: Vec<FragmentKey> = self.datagrams.iter()
+            .filter(|(_, datagram)| now.duration_since(datagram.first_seen).unwrap_or_default() > timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired {
+            self.remove(&key);
+        }
+    }
+
+    fn remove(&mut self, key: &FragmentKey) -> Option<Datagram>{
+        let datagram = self.datagrams.remove(key)?;
+        self.bytes -= datagram.bytes;
+        Some(datagram)
+    }
+
+    fn remove_oldest(&mut self){
+        let oldest = self.datagrams.iter().min_by_key(|(_, datagram)| datagram.first_seen).map(|(key, _)| *key);
+        if let Some(key) = oldest{
+            self.remove(&key);
+        }
+    }
+
+    // Datagrams waiting for fragments
+    pub fn pending(&self) -> usize{
+        self.datagrams.len()
+    }
+
+    // Fragment bytes kept
+    pub fn bytes(&self) -> usize{
+        self.bytes
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::SystemTime;
+use crate::data::{IPPacketInfo, MacAddress};
+
+const ETHERNET_HEADER_LEN: usize = 14;
+const ETHER_TYPE_VLAN: u16 = 0x8100;
+const ETHER_TYPE_QINQ: u16 = 0x88a8;
+const ETHER_TYPE_MPLS: u16 = 0x8847;
+const ETHER_TYPE_MPLS_MULTICAST: u16 = 0x8848;
+const VLAN_TAG_LEN: usize = 4;
+const MPLS_LABEL_LEN: usize = 4;
+const VLAN_ID_MASK: u16 = 0x0fff;
+const MPLS_BOTTOM_OF_STACK: u32 = 0x100;
+// Smaller values are the length of an 802.3 frame, STP frames are sent this way
+const MIN_ETHER_TYPE: u16 = 0x0600;
+
+// Ethernet frame of any protocol, shown in the MAC view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo{
+    pub source: MacAddress,
+    pub dest: MacAddress,
+    // Type of the payload after the VLAN tags, None for 802.3 frames
+    pub ether_type: Option<u16>,
+    // Length of the frame on the wire
+    pub length: u32,
+    pub timestamp: SystemTime,
+}
+
+impl FrameInfo{
+    // Packet between the nodes of the two MAC addresses, see MacAddress::to_node_ip
+    pub fn to_packet_info(&self) -> IPPacketInfo{
+        let mut packet = IPPacketInfo::new(self.source.to_node_ip(), self.dest.to_node_ip(), self.length.min(u16::MAX as u32) as u16);
+        packet.timestamp = self.timestamp;
+        packet
+    }
+}
+
+// Tags between the Ethernet header and the network header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkTags{
+    // VLAN IDs, outer first
+    pub vlans: Vec<u16>,
+    // MPLS labels, top of the stack first
+    pub mpls_labels: Vec<u32>,
+    // Type of the payload after the VLAN tags, MPLS if there are labels
+    pub ether_type: u16,
+    // Offset of the network header, after the bottom of the MPLS stack
+    pub network_offset: usize,
+}
+
+impl LinkTags{
+    // etherparse doesn't decode MPLS, the network header has to be read from `network_offset`
+    pub fn is_mpls(&self) -> bool{
+        !self.mpls_labels.is_empty()
+    }
+}
+
+pub fn parse_link_tags(frame: &[u8]) -> Option<LinkTags>{
+    let bytes = frame.get(12..ETHERNET_HEADER_LEN)?;
+    let mut tags = LinkTags{
+        ether_type: u16::from_be_bytes([bytes[0], bytes[1]]),
+        network_offset: ETHERNET_HEADER_LEN,
+        ..LinkTags::default()
+    };
+    while tags.ether_type == ETHER_TYPE_VLAN || tags.ether_type == ETHER_TYPE_QINQ{
+        let pos = tags.network_offset;
+        let tag = frame.get(pos..pos + VLAN_TAG_LEN)?;
+        tags.vlans.push(u16::from_be_bytes([tag[0], tag[1]]) & VLAN_ID_MASK);
+        tags.ether_type = u16::from_be_bytes([tag[2], tag[3]]);
+        tags.network_offset += VLAN_TAG_LEN;
+    }
+    if tags.ether_type == ETHER_TYPE_MPLS || tags.ether_type == ETHER_TYPE_MPLS_MULTICAST{
+        // A truncated stack keeps the labels read so far
+        while let Some(entry) = frame.get(tags.network_offset..tags.network_offset + MPLS_LABEL_LEN){
+            let entry = u32::from_be_bytes(entry.try_into().unwrap());
+            tags.mpls_labels.push(entry >> 12);
+            tags.network_offset += MPLS_LABEL_LEN;
+            if entry & MPLS_BOTTOM_OF_STACK != 0{
+                break;
+            }
+        }
+    }
+    Some(tags)
+}
+
+// `length` is the length on the wire, the capture may have truncated the frame
+pub fn parse_ethernet(frame: &[u8], length: u32, timestamp: SystemTime) -> Option<FrameInfo>{
+    let header = frame.get(..ETHERNET_HEADER_LEN)?;
+    let dest: [u8; 6] = header[0..6].try_into().ok()?;
+    let source: [u8; 6] = header[6..12].try_into().ok()?;
+    let ether_type = parse_link_tags(frame)?.ether_type;
+    Some(FrameInfo{
+        source: MacAddress(source),
+        dest: MacAddress(dest),
+        ether_type: Some(ether_type).filter(|ether_type| *ether_type >= MIN_ETHER_TYPE),
+        length,
+        timestamp,
+    })
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde::{Deserialize, Serialize};
+use crate::data::AddressClass;
+
+// Location and network of a public address, from the MaxMind databases
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeoInfo{
+    // ISO 3166 code, "FR"
+    pub country: Option<String>,
+    pub country_name: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub asn: Option<u32>,
+    pub as_organization: Option<String>,
+}
+
+impl GeoInfo{
+    // "Paris, France"
+    pub fn location(&self) -> Option<String>{
+        let country = self.country_name.as_ref().or(self.country.as_ref());
+        match (&self.city, country) {
+            (Some(city), Some(country)) => Some(format!("{}, {}", city, country)),
+            (city, country) => city.as_ref().or(country).cloned(),
+        }
+    }
+
+    // "AS15169 Google LLC"
+    pub fn as_label(&self) -> Option<String>{
+        let asn = self.asn?;
+        Some(match &self.as_organization {
+            Some(organization) => format!("AS{} {}", asn, organization),
+            None => format!("AS{}", asn),
+        })
+    }
+}
+
+// GeoLite2/GeoIP2 City and ASN databases, both optional. The files are read in memory
+#[derive(Default)]
+pub struct GeoIpDatabase{
+    city: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpDatabase{
+    pub fn new() -> GeoIpDatabase{
+        GeoIpDatabase{city: None, asn: None}
+    }
+
+    pub fn load_city(&mut self, path: &Path) -> Result<(), MaxMindDBError>{
+        self.set_city_source(fs::read(path)?)
+    }
+
+    pub fn load_asn(&mut self, path: &Path) -> Result<(), MaxMindDBError>{
+        self.set_asn_source(fs::read(path)?)
+    }
+
+    // Database already in memory
+    pub fn set_city_source(&mut self, data: Vec<u8>) -> Result<(), MaxMindDBError>{
+        self.city = Some(Reader::from_source(data)?);
+        Ok(())
+    }
+
+    pub fn set_asn_source(&mut self, data: Vec<u8>) -> Result<(), MaxMindDBError>{
+        self.asn = Some(Reader::from_source(data)?);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.city.is_none() && self.asn.is_none()
+    }
+
+    // Information on a public address, None for local addresses and the ones missing from the databases
+    pub fn lookup(&self, ip: &IpAddr) -> Option<GeoInfo>{
+        if AddressClass::of(ip) != AddressClass::Public{
+            return None;
+        }
+        let mut info = GeoInfo::default();
+        if let Some(city) = self.city.as_ref().and_then(|reader| reader.lookup::<geoip2::City>(*ip).ok()){
+            let english = |names: Option<BTreeMap<&str, &str>>| names.and_then(|names| names.get("en").map(|name| name.to_string()));
+            if let Some(country) = city.country{
+                info.country = country.iso_code.map(|code| code.to_string());
+                info.country_name = english(country.names);
+            }
+            info.city = city.city.and_then(|city| english(city.names));
+            if let Some(location) = city.location{
+                info.latitude = location.latitude;
+                info.longitude = location.longitude;
+            }
+        }
+        if let Some(asn) = self.asn.as_ref().and_then(|reader| reader.lookup::<geoip2::Asn>(*ip).ok()){
+            info.asn = asn.autonomous_system_number;
+            info.as_organization = asn.autonomous_system_organization.map(|organization| organization.to_string());
+        }
+        Some(info).filter(|info| *info != GeoInfo::default())
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::data::{IPPacketInfo, NodeData, NodeKey};
+
+// One hour of per second buckets
+pub const DEFAULT_HISTORY_SECONDS: u64 = 3600;
+
+// Part of the capture the statistics are computed on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeWindow{
+    All,
+    // Relative to the last packet and not to now, so it also works on pcap files
+    Last(Duration),
+    Range(SystemTime, SystemTime),
+}
+
+#[derive(Default)]
+struct PairBucket{
+    packets: u32,
+    bytes: u64,
+}
+
+// Packets and bytes per (source, dest) pair for every second of the capture,
+// buckets older than the retention are dropped to bound the memory
+pub struct StatsHistory{
+    buckets: BTreeMap<u64, HashMap<(NodeKey, NodeKey), PairBucket>>,
+    retention: u64,
+    per_vlan: bool,
+}
+
+fn to_seconds(time: SystemTime) -> u64{
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl StatsHistory{
+    pub fn new(retention: u64) -> StatsHistory{
+        StatsHistory{
+            buckets: BTreeMap::new(),
+            retention: retention.max(1),
+            per_vlan: false,
+        }
+    }
+
+    // Keeps the pairs of each VLAN apart, see IPPacketInfo::node_keys
+    pub fn set_per_vlan(&mut self, per_vlan: bool){
+        self.per_vlan = per_vlan;
+    }
+
+    pub fn add_packet(&mut self, packet: &IPPacketInfo){
+        let second = to_seconds(packet.timestamp);
+        let bucket = self.buckets.entry(second).or_default()
+            .entry(packet.node_keys(self.per_vlan)).or_default();
+        bucket.packets += 1;
+        bucket.bytes += packet.payload_len as u64;
+
+        let last = *self.buckets.keys().next_back().unwrap();
+        let oldest = last.saturating_sub(self.retention - 1);
+        while self.buckets.keys().next().is_some_and(|second| *second < oldest){
+            self.buckets.pop_first();
+        }
+    }
+
+    // Range of the retained buckets, both ends included
+    pub fn bounds(&self) -> Option<(SystemTime, SystemTime)>{
+        let first = *self.buckets.keys().next()?;
+        let last = *self.buckets.keys().next_back()?;
+        Some((UNIX_EPOCH + Duration::from_secs(first), UNIX_EPOCH + Duration::from_secs(last)))
+    }
+
+    pub fn clear(&mut self){
+        self.buckets.clear();
+    }
+
+    // Seconds of the window, both ends included, None when no bucket is retained
+    fn range(&self, window: TimeWindow) -> Option<(u64, u64)>{
+        let (first, last) = self.bounds()?;
+        let (from, to) = match window {
+            TimeWindow::All => (to_seconds(first), to_seconds(last)),
+            TimeWindow::Last(duration) => {
+                let last = to_seconds(last);
+                (last.saturating_sub(duration.as_secs().saturating_sub(1)), last)
+            }
+            TimeWindow::Range(from, to) => (to_seconds(from), to_seconds(to)),
+        };
+        Some((from, to)).filter(|(from, to)| from <= to)
+    }
+
+    // Per node statistics of the packets inside the window, limited to the retained buckets
+    pub fn nodes(&self, window: TimeWindow) -> Vec<NodeData>{
+        let Some((from, to)) = self.range(window) else {
+            return Vec::new();
+        };
+        let mut nodes: HashMap<NodeKey, NodeData> = HashMap::new();
+        for pairs in self.buckets.range(from..=to).map(|(_, pairs)| pairs){
+            for ((source, dest), bucket) in pairs{
+                nodes.entry(*source).or_insert_with(|| NodeData::from_key(*source))
+                    .add_sent(dest.ip, bucket.packets, bucket.bytes);
+                nodes.entry(*dest).or_insert_with(|| NodeData::from_key(*dest))
+                    .add_recv(source.ip, bucket.packets, bucket.bytes);
+            }
+        }
+        nodes.into_values().collect()
+    }
+
+    // Bytes sent from each source to each destination inside the window
+    pub fn pair_bytes(&self, window: TimeWindow) -> HashMap<(NodeKey, NodeKey), u64>{
+        let mut pairs: HashMap<(NodeKey, NodeKey), u64> = HashMap::new();
+        let Some((from, to)) = self.range(window) else {
+            return pairs;
+        };
+        for buckets in self.buckets.range(from..=to).map(|(_, pairs)| pairs){
+            for (pair, bucket) in buckets{
+                *pairs.entry(*pair).or_default() += bucket.bytes;
+            }
+        }
+        pairs
+    }
+}
+
+impl Default for StatsHistory{
+    fn default() -> Self {
+        StatsHistory::new(DEFAULT_HISTORY_SECONDS)
+    }
+}
@@ -1,13 +1,101 @@
 use std::net::IpAddr;
+use std::time::SystemTime;
+mod address_class;
+mod alert;
+mod direction;
+mod discovery;
+mod dns;
+mod flow;
+mod fragment;
+mod frame;
+mod geoip;
+mod history;
+mod names;
 mod node_data;
+mod oui;
 mod packet_data;   
+mod rules;
+mod scan;
+mod search;
+mod server_name;
+mod traffic_graph;
+mod tunnel;
 
+pub use address_class::AddressClass;
+pub use alert::{Alert, AlertKind};
+pub use direction::{DirectionStats, DirectionTotals, PacketDirection};
+pub use discovery::{DhcpInfo, DhcpMessageType, DiscoverySource, HostDiscovery, MacAddress, parse_arp, parse_dhcp, DHCP_CLIENT_PORT, DHCP_SERVER_PORT};
+pub use dns::{DnsRecord, build_ptr_query, is_dns_port, message_id, parse_response, parse_tcp_response, reverse_name, DNS_PORT, MDNS_PORT};
+pub use flow::{FlowKey, FlowStats, FlowTable, FLOW_IDLE_TIMEOUT, MAX_FLOWS};
+pub use fragment::{parse_fragment, FragmentKey, IpFragment, Reassembler, FRAGMENT_TIMEOUT, MAX_FRAGMENT_BYTES, MAX_PENDING_DATAGRAMS};
+pub use frame::{parse_ethernet, parse_link_tags, FrameInfo, LinkTags};
+pub use geoip::{GeoInfo, GeoIpDatabase};
+pub use history::{StatsHistory, TimeWindow, DEFAULT_HISTORY_SECONDS};
 pub use packet_data::PacketData;
-pub use node_data::NodeData;
+pub use names::NameTable;
+pub use node_data::{MacChange, MacEntry, NodeData, NodeKey, Statistics};
+pub use oui::OuiTable;
+pub use rules::{AddressFilter, Rule, RuleAction, RuleSet, RuleTrigger, Webhook, RULE_COOLDOWN};
+pub use scan::{ScanConfig, ScanDetector, DEFAULT_HOST_THRESHOLD, DEFAULT_PORT_THRESHOLD, DEFAULT_SCAN_WINDOW};
+pub use search::SearchQuery;
+pub use server_name::{parse_http_host, parse_server_name, parse_tls_sni};
+pub use traffic_graph::TrafficGraph;
+pub use tunnel::{parse_encapsulation, Encapsulation, Tunnel, TunnelEndpoints, TunnelKind, GENEVE_PORT, VXLAN_PORT};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct IPPacketInfo {
     pub source: IpAddr,
     pub dest: IpAddr,
-    pub payload_len: u16
+    pub payload_len: u16,
+    // IP protocol number of the transport layer, 6 for TCP and 17 for UDP
+    pub protocol: u8,
+    pub source_port: Option<u16>,
+    pub dest_port: Option<u16>,
+    pub timestamp: SystemTime,
+    // Names learned from the packet when it is a DNS response
+    pub dns_records: Vec<DnsRecord>,
+    // Server asked for by the client in a TLS ClientHello or an HTTP request
+    pub server_name: Option<String>,
+    // 802.1Q tags, outer first, two for QinQ
+    pub vlans: Vec<u16>,
+    // MPLS label stack, top first
+    pub mpls_labels: Vec<u32>,
+    // Set when the packet was taken out of a tunnel
+    pub tunnel: Option<Box<Tunnel>>,
+}
+
+impl IPPacketInfo {
+    // Packet without transport information captured now
+    pub fn new(source: IpAddr, dest: IpAddr, payload_len: u16) -> IPPacketInfo {
+        IPPacketInfo {
+            source,
+            dest,
+            payload_len,
+            protocol: 0,
+            source_port: None,
+            dest_port: None,
+            timestamp: SystemTime::now(),
+            dns_records: Vec::new(),
+            server_name: None,
+            vlans: Vec::new(),
+            mpls_labels: Vec::new(),
+            tunnel: None,
+        }
+    }
+
+    // Packet as seen on the wire, the tunnel packet of a decapsulated one
+    pub fn outer(&self) -> &IPPacketInfo {
+        self.tunnel.as_ref().map_or(self, |tunnel| &tunnel.outer)
+    }
+
+    // The inner tag is the VLAN of the hosts, the outer one of QinQ is added by the provider
+    pub fn vlan(&self) -> Option<u16> {
+        self.vlans.last().copied()
+    }
+
+    // Keys of the source and destination nodes, with the VLAN when nodes are unique per VLAN
+    pub fn node_keys(&self, per_vlan: bool) -> (NodeKey, NodeKey) {
+        let vlan = self.vlan().filter(|_| per_vlan);
+        (NodeKey::new(self.source, vlan), NodeKey::new(self.dest, vlan))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+use crate::data::DnsRecord;
+
+// Expired names are still shown, after a while they are forgotten
+const EXPIRED_GRACE: Duration = Duration::from_secs(3600);
+// CDN addresses can have many names, only the most recent ones are kept
+const MAX_NAMES_PER_IP: usize = 8;
+
+struct NameEntry{
+    name: String,
+    // Names from reverse lookups are often generic, they come after the ones seen in responses
+    reverse: bool,
+    expires: SystemTime,
+    last_seen: SystemTime,
+}
+
+// Names of the addresses seen in DNS responses. Times are the ones of the packets,
+// so that pcap files expire names like a live capture would
+#[derive(Default)]
+pub struct NameTable{
+    names: HashMap<IpAddr, Vec<NameEntry>>,
+}
+
+impl NameTable{
+    pub fn new() -> NameTable{
+        NameTable{names: HashMap::new()}
+    }
+
+    pub fn add(&mut self, record: &DnsRecord, time: SystemTime){
+        self.insert(record, time, false);
+    }
+
+    // Name given by a reverse lookup made by the program
+    pub fn add_reverse(&mut self, record: &DnsRecord, time: SystemTime){
+        self.insert(record, time, true);
+    }
+
+    fn insert(&mut self, record: &DnsRecord, time: SystemTime, reverse: bool){
+        let entries = self.names.entry(record.ip).or_default();
+        entries.retain(|entry| entry.expires + EXPIRED_GRACE >= time);
+        let expires = time + Duration::from_secs(record.ttl as u64);
+        match entries.iter_mut().find(|entry| entry.name == record.name) {
+            Some(entry) => {
+                entry.expires = expires;
+                entry.last_seen = time;
+                entry.reverse &= reverse;
+            }
+            None => entries.push(NameEntry{name: record.name.clone(), reverse, expires, last_seen: time}),
+        }
+        if entries.len() > MAX_NAMES_PER_IP{
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_seen));
+            entries.truncate(MAX_NAMES_PER_IP);
+        }
+    }
+
+    // Names of the address, best first: the ones seen in responses, the ones still valid,
+    // then the most recently seen
+    pub fn names(&self, ip: &IpAddr, time: SystemTime) -> Vec<String>{
+        let Some(entries) = self.names.get(ip) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<&NameEntry> = entries.iter().collect();
+        entries.sort_by_key(|entry| (entry.reverse, entry.expires < time, std::cmp::Reverse(entry.last_seen)));
+        entries.into_iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    pub fn best_name(&self, ip: &IpAddr, time: SystemTime) -> Option<String>{
+        self.names(ip, time).into_iter().next()
+    }
+
+    pub fn len(&self) -> usize{
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.names.is_empty()
+    }
+
+    pub fn clear(&mut self){
+        self.names.clear();
+    }
+}
@@ -1,7 +1,45 @@
 use std::net::IpAddr;
 use std::collections::HashMap;
-use crate::data::IPPacketInfo;
+use std::fmt;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::data::{AddressClass, DhcpInfo, GeoInfo, HostDiscovery, IPPacketInfo, MacAddress, OuiTable, TunnelEndpoints};
 
+// A CDN address can serve many names, only the first ones are kept
+const MAX_SERVER_NAMES: usize = 32;
+const MAX_MACS: usize = 16;
+const MAX_MPLS_LABELS: usize = 16;
+const MAX_TUNNELS: usize = 16;
+
+// Identity of a node, the VLAN is only set when nodes are made unique per VLAN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeKey{
+    pub ip: IpAddr,
+    pub vlan: Option<u16>,
+}
+
+impl NodeKey{
+    pub fn new(ip: IpAddr, vlan: Option<u16>) -> NodeKey{
+        NodeKey{ip, vlan}
+    }
+}
+
+impl From<IpAddr> for NodeKey{
+    fn from(ip: IpAddr) -> NodeKey{
+        NodeKey::new(ip, None)
+    }
+}
+
+impl fmt::Display for NodeKey{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self.vlan {
+            Some(vlan) => write!(f, "{} (VLAN {})", self.ip, vlan),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Statistics{
     pub packet_count_recv: u32,
     pub packet_count_sent: u32,
@@ -18,51 +56,234 @@ impl Statistics{
             packet_bytes_recv: 0,
         }
     }
+}
 
-    fn add_packet(&mut self, packet: &IPPacketInfo, source_ip: IpAddr){
-        if packet.source == source_ip{
-            self.packet_count_sent += 1;
-            self.packet_bytes_sent += packet.payload_len as u64;
-        }
-        else if packet.dest == source_ip{
-            self.packet_count_recv += 1;
-            self.packet_bytes_recv += packet.payload_len as u64;
-        }
-    }
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MacEntry{
+    pub mac: MacAddress,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+// The address moved to another MAC, a new device or ARP spoofing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacChange{
+    pub ip: IpAddr,
+    pub old: MacAddress,
+    pub new: MacAddress,
+    pub timestamp: SystemTime,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NodeData{
     pub ip: IpAddr,
+    // VLAN the node belongs to when nodes are unique per VLAN, see NodeKey
+    #[serde(default)]
+    pub vlan: Option<u16>,
     pub stats: Statistics,
     pub recv_from: HashMap<IpAddr, Statistics>,
     pub sent_to: HashMap<IpAddr, Statistics>,
+    // Names clients asked this node for, in order of arrival
+    #[serde(default)]
+    pub server_names: Vec<String>,
+    // MAC addresses seen with this IP in ARP and DHCP messages
+    #[serde(default)]
+    pub macs: Vec<MacEntry>,
+    #[serde(default)]
+    pub dhcp: Option<DhcpInfo>,
+    // Time of the last DHCP acknowledgement, start of the lease
+    #[serde(default)]
+    pub lease_start: Option<SystemTime>,
+    // Hardware vendor of the current MAC address
+    #[serde(default)]
+    pub vendor: Option<String>,
+    // Country and AS of public addresses
+    #[serde(default)]
+    pub geo: Option<GeoInfo>,
+    // VLAN IDs and MPLS labels of the packets of the node, sorted
+    #[serde(default)]
+    pub vlans: Vec<u16>,
+    #[serde(default)]
+    pub mpls_labels: Vec<u32>,
+    // Tunnels the packets of the node were decapsulated from
+    #[serde(default)]
+    pub tunnels: Vec<TunnelEndpoints>,
+    // Address of the capture interface, the host running the capture
+    #[serde(default)]
+    pub local: bool,
+    // Time of the first packet or discovery of the node
+    #[serde(default)]
+    pub first_seen: Option<SystemTime>,
+    // Node of the MAC view, its IP is only derived from the MAC address, see MacAddress::to_node_ip
+    #[serde(default)]
+    pub mac_node: bool,
 }
 
 impl NodeData{
     pub fn new(ip: IpAddr) -> NodeData{
+        NodeData::from_key(ip.into())
+    }
+
+    pub fn from_key(key: NodeKey) -> NodeData{
         NodeData{
-            ip: ip,
+            ip: key.ip,
+            vlan: key.vlan,
             stats: Statistics::new(),
             recv_from: HashMap::new(),
             sent_to: HashMap::new(),
+            server_names: Vec::new(),
+            macs: Vec::new(),
+            dhcp: None,
+            lease_start: None,
+            vendor: None,
+            geo: None,
+            vlans: Vec::new(),
+            mpls_labels: Vec::new(),
+            tunnels: Vec::new(),
+            local: false,
+            first_seen: None,
+            mac_node: false,
+        }
+    }
+
+    pub fn key(&self) -> NodeKey{
+        NodeKey::new(self.ip, self.vlan)
+    }
+
+    // Address of the node or of one of its peers in the exports, the MAC address in the MAC view
+    pub fn address(&self, ip: &IpAddr) -> String{
+        match MacAddress::from_node_ip(ip).filter(|_| self.mac_node) {
+            Some(mac) => mac.to_string(),
+            None => ip.to_string(),
         }
     }
 
+    // Address with the VLAN when nodes are unique per VLAN, see NodeKey. Peers are on the VLAN of the node
+    pub fn node_id(&self, ip: &IpAddr) -> String{
+        match self.vlan {
+            Some(vlan) => format!("{} (VLAN {})", self.address(ip), vlan),
+            None => self.address(ip),
+        }
+    }
+
+    pub fn class(&self) -> AddressClass{
+        AddressClass::of(&self.ip)
+    }
+
+    // "VLAN 10" or "VLAN 10, 20" for a node seen on several VLANs
+    pub fn vlan_label(&self) -> Option<String>{
+        if self.vlans.is_empty(){
+            return None;
+        }
+        let vlans: Vec<String> = self.vlans.iter().map(|vlan| vlan.to_string()).collect();
+        Some(format!("VLAN {}", vlans.join(", ")))
+    }
+
+    // Packets may be read out of order, the earliest time is kept
+    fn add_time(&mut self, time: SystemTime){
+        self.first_seen = Some(self.first_seen.map_or(time, |first_seen| first_seen.min(time)));
+    }
+
     pub fn add_packet(&mut self, packet: &IPPacketInfo){
+        self.add_time(packet.timestamp);
         if packet.source == self.ip{
-            self.stats.packet_count_sent += 1;
-            self.stats.packet_bytes_sent += packet.payload_len as u64;
-            let stats = self.sent_to.entry(packet.dest).or_insert(Statistics::new());
-            stats.add_packet(&packet, self.ip);
+            self.add_sent(packet.dest, 1, packet.payload_len as u64);
         }
         else if packet.dest == self.ip {
-            self.stats.packet_count_recv += 1;
-            self.stats.packet_bytes_recv += packet.payload_len as u64;
-            let stats = self.recv_from.entry(packet.source).or_insert(Statistics::new());
-            stats.add_packet(&packet, self.ip);
+            self.add_recv(packet.source, 1, packet.payload_len as u64);
+            if let Some(name) = &packet.server_name{
+                self.add_server_name(name);
+            }
         }else{
             panic!("Packet does not belong to this node");
         }
+        for vlan in &packet.vlans{
+            if let Err(idx) = self.vlans.binary_search(vlan){
+                self.vlans.insert(idx, *vlan);
+            }
+        }
+        for label in &packet.mpls_labels{
+            if let (Err(idx), true) = (self.mpls_labels.binary_search(label), self.mpls_labels.len() < MAX_MPLS_LABELS){
+                self.mpls_labels.insert(idx, *label);
+            }
+        }
+        if let Some(tunnel) = &packet.tunnel{
+            let endpoints = tunnel.endpoints();
+            if self.tunnels.len() < MAX_TUNNELS && !self.tunnels.contains(&endpoints){
+                self.tunnels.push(endpoints);
+            }
+        }
+    }
+
+    pub fn add_server_name(&mut self, name: &str){
+        if self.server_names.len() < MAX_SERVER_NAMES && !self.server_names.iter().any(|known| known == name){
+            self.server_names.push(name.to_string());
+        }
+    }
+
+    // MAC address seen the most recently
+    pub fn current_mac(&self) -> Option<MacAddress>{
+        self.macs.iter().max_by_key(|entry| entry.last_seen).map(|entry| entry.mac)
+    }
+
+    // Looks up the vendor of the current MAC, returns true if it changed
+    pub fn refresh_vendor(&mut self, oui: &OuiTable) -> bool{
+        let vendor = self.current_mac().and_then(|mac| oui.lookup(&mac)).map(|vendor| vendor.to_string());
+        let changed = vendor != self.vendor;
+        self.vendor = vendor;
+        changed
+    }
+
+    // Records the MAC and DHCP information, returns the change when the MAC is not the current one
+    pub fn add_discovery(&mut self, discovery: &HostDiscovery) -> Option<MacChange>{
+        self.add_time(discovery.timestamp);
+        let change = self.current_mac().filter(|mac| *mac != discovery.mac).map(|old| MacChange{
+            ip: self.ip,
+            old,
+            new: discovery.mac,
+            timestamp: discovery.timestamp,
+        });
+        match self.macs.iter_mut().find(|entry| entry.mac == discovery.mac) {
+            Some(entry) => entry.last_seen = entry.last_seen.max(discovery.timestamp),
+            None => {
+                if self.macs.len() >= MAX_MACS{
+                    let oldest = self.macs.iter().enumerate().min_by_key(|(_, entry)| entry.last_seen).map(|(idx, _)| idx);
+                    self.macs.remove(oldest.unwrap());
+                }
+                self.macs.push(MacEntry{mac: discovery.mac, first_seen: discovery.timestamp, last_seen: discovery.timestamp});
+            }
+        }
+        if let Some(info) = &discovery.dhcp{
+            let dhcp = self.dhcp.get_or_insert_with(DhcpInfo::default);
+            if info.hostname.is_some(){
+                dhcp.hostname = info.hostname.clone();
+            }
+            if info.vendor_class.is_some(){
+                dhcp.vendor_class = info.vendor_class.clone();
+            }
+            if info.lease_time.is_some(){
+                dhcp.lease_time = info.lease_time;
+                dhcp.server = info.server.or(dhcp.server);
+                self.lease_start = Some(discovery.timestamp);
+            }
+        }
+        change
+    }
+
+    // Counts several packets at once, used when statistics are rebuilt from aggregates
+    pub fn add_sent(&mut self, dest: IpAddr, packets: u32, bytes: u64){
+        self.stats.packet_count_sent += packets;
+        self.stats.packet_bytes_sent += bytes;
+        let stats = self.sent_to.entry(dest).or_insert(Statistics::new());
+        stats.packet_count_sent += packets;
+        stats.packet_bytes_sent += bytes;
+    }
+
+    pub fn add_recv(&mut self, source: IpAddr, packets: u32, bytes: u64){
+        self.stats.packet_count_recv += packets;
+        self.stats.packet_bytes_recv += bytes;
+        let stats = self.recv_from.entry(source).or_insert(Statistics::new());
+        stats.packet_count_recv += packets;
+        stats.packet_bytes_recv += bytes;
     }
-    
 }
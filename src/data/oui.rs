@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::data::MacAddress;
+
+// Small subset of the IEEE registry with common hardware and virtual machine vendors,
+// the full list can be downloaded from https://standards-oui.ieee.org/oui/oui.txt
+const EMBEDDED_OUI: &str = include_str!("oui.txt");
+// Set in randomized addresses, they don't belong to any vendor
+const LOCALLY_ADMINISTERED: u8 = 0x02;
+
+// Vendor names by the first three bytes of the MAC addresses they assigned
+#[derive(Debug, Clone, Default)]
+pub struct OuiTable{
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl OuiTable{
+    pub fn new() -> OuiTable{
+        OuiTable{vendors: HashMap::new()}
+    }
+
+    pub fn embedded() -> OuiTable{
+        OuiTable::parse(EMBEDDED_OUI)
+    }
+
+    pub fn load(path: &Path) -> io::Result<OuiTable>{
+        let table = OuiTable::parse(&fs::read_to_string(path)?);
+        if table.is_empty(){
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no OUI found"));
+        }
+        Ok(table)
+    }
+
+    // Reads the IEEE oui.txt and oui.csv formats, and lines starting with
+    // the prefix followed by the name, "00:11:22 Vendor". Other lines are skipped
+    pub fn parse(text: &str) -> OuiTable{
+        let mut table = OuiTable::new();
+        for line in text.lines(){
+            if let Some((oui, vendor)) = parse_line(line){
+                table.vendors.entry(oui).or_insert(vendor);
+            }
+        }
+        table
+    }
+
+    pub fn lookup(&self, mac: &MacAddress) -> Option<&str>{
+        let [a, b, c, ..] = mac.0;
+        if a & LOCALLY_ADMINISTERED != 0{
+            return None;
+        }
+        self.vendors.get(&[a, b, c]).map(|vendor| vendor.as_str())
+    }
+
+    pub fn len(&self) -> usize{
+        self.vendors.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.vendors.is_empty()
+    }
+}
+
+fn parse_line(line: &str) -> Option<([u8; 3], String)>{
+    let line = line.trim();
+    // oui.csv: "MA-L,001122,Vendor,Address", vendors with a comma are quoted
+    if let Some(rest) = line.strip_prefix("MA-L,"){
+        let (oui, vendor) = rest.split_once(',')?;
+        let vendor = match vendor.strip_prefix('"') {
+            Some(quoted) => quoted.split('"').next()?,
+            None => vendor.split(',').next()?,
+        };
+        return Some((parse_oui(oui)?, to_vendor(vendor)?));
+    }
+    // oui.txt: "00-11-22   (hex)\t\tVendor" then "001122     (base 16)\t\tVendor". The digits
+    // alone are only taken before "(base 16)", address lines can start with a postal code like "100080"
+    let (oui, vendor, len) = if let Some((oui, vendor)) = line.split_once("(hex)"){
+        (oui.trim(), vendor, 8)
+    }else if let Some((oui, vendor)) = line.split_once("(base 16)"){
+        (oui.trim(), vendor, 6)
+    }else{
+        let (oui, vendor) = line.split_once(char::is_whitespace)?;
+        (oui, vendor, 8)
+    };
+    if oui.len() != len{
+        return None;
+    }
+    Some((parse_oui(oui)?, to_vendor(vendor)?))
+}
+
+// Exactly "00-11-22", "00:11:22" or "001122", so that address lines like "D-80339 Munich" are not taken for one
+fn parse_oui(text: &str) -> Option<[u8; 3]>{
+    let digits: String = match text.len() {
+        6 => text.to_string(),
+        8 => {
+            let separator = text.as_bytes()[2];
+            if (separator != b'-' && separator != b':') || text.as_bytes()[5] != separator{
+                return None;
+            }
+            text.split(separator as char).collect()
+        }
+        _ => return None,
+    };
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()){
+        return None;
+    }
+    let value = u32::from_str_radix(&digits, 16).ok()?;
+    let [_, a, b, c] = value.to_be_bytes();
+    Some([a, b, c])
+}
+
+fn to_vendor(text: &str) -> Option<String>{
+    Some(text.trim().to_string()).filter(|vendor| !vendor.is_empty())
+}
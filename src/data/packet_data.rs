@@ -1,16 +1,19 @@
 use std::net::IpAddr;
-use crate::data::IPPacketInfo;
+use crate::data::{IPPacketInfo, NodeKey, PacketDirection};
 
 pub struct PacketData{
-    pub source: IpAddr,
-    pub dest: IpAddr,
+    pub source: NodeKey,
+    pub dest: NodeKey,
+    pub direction: PacketDirection,
 }
 
 impl PacketData{
-    pub fn new(packet: &IPPacketInfo) -> PacketData{
+    pub fn new(packet: &IPPacketInfo, per_vlan: bool, local_addresses: &[IpAddr]) -> PacketData{
+        let (source, dest) = packet.node_keys(per_vlan);
         PacketData{
-            source: packet.source,
-            dest: packet.dest,
+            source,
+            dest,
+            direction: PacketDirection::of(packet, local_addresses),
         }
     }
-}
\ No newline at end of file
+}
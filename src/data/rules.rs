@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Deserialize;
+use crate::data::search::cidr_contains;
+use crate::data::{AddressClass, Alert, AlertKind, IPPacketInfo, NodeKey, StatsHistory, TimeWindow, DEFAULT_HISTORY_SECONDS};
+
+// Event rules are raised at most once per offender in this time
+pub const RULE_COOLDOWN: Duration = Duration::from_secs(60);
+const DEFAULT_WEBHOOK_PORT: u16 = 80;
+
+// Addresses a rule applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressFilter{
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+    Class(AddressClass),
+}
+
+impl AddressFilter{
+    pub fn matches(&self, ip: &IpAddr) -> bool{
+        match self {
+            AddressFilter::Ip(address) => address == ip,
+            AddressFilter::Cidr(network, prefix) => cidr_contains(network, *prefix, ip),
+            AddressFilter::Class(class) => AddressClass::of(ip) == *class,
+        }
+    }
+}
+
+impl FromStr for AddressFilter{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(ip) = s.parse::<IpAddr>(){
+            return Ok(AddressFilter::Ip(ip));
+        }
+        if let Some((network, prefix)) = s.split_once('/'){
+            if let (Ok(network), Ok(prefix)) = (network.parse::<IpAddr>(), prefix.parse::<u8>()){
+                let max_prefix = if network.is_ipv4() {32} else {128};
+                if prefix <= max_prefix{
+                    return Ok(AddressFilter::Cidr(network, prefix));
+                }
+            }
+        }
+        s.parse::<AddressClass>().map(AddressFilter::Class)
+            .map_err(|_| format!("Unknown address '{}', expected an IP, a CIDR prefix or an address class", s))
+    }
+}
+
+impl fmt::Display for AddressFilter{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressFilter::Ip(ip) => write!(f, "{}", ip),
+            AddressFilter::Cidr(network, prefix) => write!(f, "{}/{}", network, prefix),
+            AddressFilter::Class(class) => write!(f, "{}", class),
+        }
+    }
+}
+
+// What raises the alert of a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleTrigger{
+    // A node seen for the first time, "new"
+    NewNode,
+    // Any packet from or to the port, "port:23"
+    Port(u16),
+    // A node sending more than `bytes` within `window`, "sent>50MB/1m",
+    // computed from the statistics history
+    Sent{bytes: u64, window: Duration},
+}
+
+// Local HTTP endpoint the alerts are posted to as JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook{
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl FromStr for Webhook{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("http://").ok_or_else(|| format!("Unsupported webhook '{}', expected an http:// URL", s))?;
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            // Not the colons of a bracketed IPv6 address
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse::<u16>().map_err(|_| format!("Invalid port in webhook '{}'", s))?)
+            }
+            _ => (authority, DEFAULT_WEBHOOK_PORT),
+        };
+        if host.is_empty(){
+            return Err(format!("Missing host in webhook '{}'", s));
+        }
+        Ok(Webhook{host: host.to_string(), port, path: path.to_string()})
+    }
+}
+
+impl fmt::Display for Webhook{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleAction{
+    // Flash the node of the alert
+    Highlight,
+    // Append the alert to the file
+    Log(PathBuf),
+    // Play a beep through the speakers of the window, ring the terminal bell in headless mode
+    Sound,
+    Webhook(Webhook),
+}
+
+impl FromStr for RuleAction{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(path) = s.strip_prefix("log:"){
+            return Ok(RuleAction::Log(PathBuf::from(path.trim())));
+        }
+        if let Some(url) = s.strip_prefix("webhook:"){
+            return url.trim().parse().map(RuleAction::Webhook);
+        }
+        match s.to_lowercase().as_str() {
+            "highlight" => Ok(RuleAction::Highlight),
+            "sound" => Ok(RuleAction::Sound),
+            _ => Err(format!("Unknown action '{}', expected 'highlight', 'sound', 'log:<file>' or 'webhook:<url>'", s)),
+        }
+    }
+}
+
+impl fmt::Display for RuleAction{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleAction::Highlight => write!(f, "highlight"),
+            RuleAction::Log(path) => write!(f, "log:{}", path.display()),
+            RuleAction::Sound => write!(f, "sound"),
+            RuleAction::Webhook(webhook) => write!(f, "webhook:{}", webhook),
+        }
+    }
+}
+
+// "<trigger> [src:<address>] [dst:<address>] [=> <action>,<action>]",
+// e.g. "sent>50MB/1m dst:public => highlight,log:alerts.log", nodes are highlighted by default
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rule{
+    pub trigger: RuleTrigger,
+    pub source: Option<AddressFilter>,
+    pub dest: Option<AddressFilter>,
+    pub actions: Vec<RuleAction>,
+}
+
+// "50MB", "1.5 GB", units are powers of 1024 like the byte counts shown
+fn parse_size(text: &str) -> Option<u64>{
+    let text = text.trim().to_uppercase();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let power = match unit.trim() {
+        "" | "B" => 0,
+        "KB" => 1,
+        "MB" => 2,
+        "GB" => 3,
+        "TB" => 4,
+        _ => return None,
+    };
+    let number = number.parse::<f64>().ok().filter(|number| *number >= 0.0)?;
+    Some((number * 1024_f64.powi(power)) as u64)
+}
+
+// "60s", "1m", "min", "2h", a number alone is in seconds
+fn parse_window(text: &str) -> Option<Duration>{
+    let text = text.trim().to_lowercase();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number = if number.is_empty() {1} else {number.parse::<u64>().ok()?};
+    let seconds = match unit {
+        "" | "s" | "sec" => number,
+        "m" | "min" => number.checked_mul(60)?,
+        "h" => number.checked_mul(3600)?,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds)).filter(|window| !window.is_zero())
+}
+
+fn format_size(bytes: u64) -> String{
+    let units = [(1 << 40, "TB"), (1 << 30, "GB"), (1 << 20, "MB"), (1 << 10, "KB")];
+    match units.iter().find(|(size, _)| bytes >= *size && bytes.is_multiple_of(*size)) {
+        Some((size, unit)) => format!("{}{}", bytes / size, unit),
+        None => format!("{}B", bytes),
+    }
+}
+
+impl Rule{
+    // Text of the rule without its actions, names the rule in the alerts
+    pub fn condition(&self) -> String{
+        let mut terms = vec![match &self.trigger {
+            RuleTrigger::NewNode => "new".to_string(),
+            RuleTrigger::Port(port) => format!("port:{}", port),
+            RuleTrigger::Sent{bytes, window} => format!("sent>{}/{}s", format_size(*bytes), window.as_secs()),
+        }];
+        if let Some(source) = &self.source{
+            terms.push(format!("src:{}", source));
+        }
+        if let Some(dest) = &self.dest{
+            terms.push(format!("dst:{}", dest));
+        }
+        terms.join(" ")
+    }
+
+    pub fn has_action(&self, action: &RuleAction) -> bool{
+        self.actions.contains(action)
+    }
+
+    fn source_matches(&self, ip: &IpAddr) -> bool{
+        self.source.as_ref().is_none_or(|filter| filter.matches(ip))
+    }
+
+    fn dest_matches(&self, ip: &IpAddr) -> bool{
+        self.dest.as_ref().is_none_or(|filter| filter.matches(ip))
+    }
+
+    fn alert(&self, timestamp: SystemTime, source: NodeKey) -> Alert{
+        Alert{
+            timestamp,
+            kind: AlertKind::Rule,
+            source,
+            target: None,
+            port: None,
+            protocol: 0,
+            count: 0,
+            rule: Some(self.condition()),
+            actions: self.actions.clone(),
+        }
+    }
+}
+
+impl FromStr for Rule{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, actions) = match s.split_once("=>") {
+            Some((condition, actions)) => (condition, actions.split(',').map(|action| action.parse()).collect::<Result<Vec<RuleAction>, String>>()?),
+            None => (s, vec![RuleAction::Highlight]),
+        };
+        let mut trigger = None;
+        let mut source = None;
+        let mut dest = None;
+        for term in condition.split_whitespace(){
+            let lower = term.to_lowercase();
+            let parsed = if lower == "new"{
+                Some(RuleTrigger::NewNode)
+            }else if let Some(port) = lower.strip_prefix("port:"){
+                Some(RuleTrigger::Port(port.parse().map_err(|_| format!("Invalid port '{}'", port))?))
+            }else if let Some(rate) = lower.strip_prefix("sent>"){
+                let (size, window) = rate.split_once('/').unwrap_or((rate, "1m"));
+                let bytes = parse_size(size).ok_or_else(|| format!("Invalid size '{}', expected e.g. 50MB", size))?;
+                let window = parse_window(window).ok_or_else(|| format!("Invalid window '{}', expected e.g. 60s, 1m or 1h", window))?;
+                if window.as_secs() > DEFAULT_HISTORY_SECONDS{
+                    return Err(format!("Window '{}' is longer than the one hour of statistics history", rate));
+                }
+                Some(RuleTrigger::Sent{bytes, window})
+            }else if let Some(address) = lower.strip_prefix("src:"){
+                source = Some(address.parse::<AddressFilter>()?);
+                None
+            }else if let Some(address) = lower.strip_prefix("dst:"){
+                dest = Some(address.parse::<AddressFilter>()?);
+                None
+            }else{
+                return Err(format!("Unknown term '{}', expected 'new', 'port:<port>', 'sent><size>/<window>', 'src:<address>' or 'dst:<address>'", term));
+            };
+            if let Some(parsed) = parsed{
+                if trigger.replace(parsed).is_some(){
+                    return Err(format!("Rule '{}' has more than one of 'new', 'port:' and 'sent>'", s.trim()));
+                }
+            }
+        }
+        let trigger = trigger.ok_or_else(|| format!("Rule '{}' needs one of 'new', 'port:<port>' or 'sent><size>/<window>'", s.trim()))?;
+        if trigger == RuleTrigger::NewNode && dest.is_some(){
+            return Err("New node rules only take a 'src:' address".to_string());
+        }
+        Ok(Rule{trigger, source, dest, actions})
+    }
+}
+
+impl TryFrom<String> for Rule{
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Rule{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actions: Vec<String> = self.actions.iter().map(|action| action.to_string()).collect();
+        write!(f, "{} => {}", self.condition(), actions.join(","))
+    }
+}
+
+// Rule index, offender and destination of an alert, it isn't raised again during the cooldown
+type RuleAlertKey = (usize, NodeKey, Option<IpAddr>);
+
+// Rules checked on every packet and, for the rate rules, once per second of capture on the history
+#[derive(Default)]
+pub struct RuleSet{
+    rules: Vec<Rule>,
+    per_vlan: bool,
+    alerted: HashMap<RuleAlertKey, SystemTime>,
+    // Second of capture the rate rules were last checked at
+    checked: Option<u64>,
+}
+
+impl RuleSet{
+    pub fn new(rules: Vec<Rule>) -> RuleSet{
+        RuleSet{
+            rules,
+            ..RuleSet::default()
+        }
+    }
+
+    pub fn get_rules(&self) -> &Vec<Rule>{
+        &self.rules
+    }
+
+    pub fn add_rule(&mut self, rule: Rule){
+        self.rules.push(rule);
+    }
+
+    // The alerts of the other rules may be raised again
+    pub fn remove_rule(&mut self, index: usize){
+        if index < self.rules.len(){
+            self.rules.remove(index);
+            self.alerted.clear();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.rules.is_empty()
+    }
+
+    // Offenders are nodes of their VLAN, see IPPacketInfo::node_keys
+    pub fn set_per_vlan(&mut self, per_vlan: bool){
+        self.per_vlan = per_vlan;
+    }
+
+    pub fn clear(&mut self){
+        self.alerted.clear();
+        self.checked = None;
+    }
+
+    // Returns false if the alert was already raised during the cooldown
+    fn cooldown(&mut self, key: RuleAlertKey, time: SystemTime, period: Duration) -> bool{
+        let since = time.checked_sub(period).unwrap_or(UNIX_EPOCH);
+        if self.alerted.get(&key).is_some_and(|last| *last > since){
+            return false;
+        }
+        self.alerted.insert(key, time);
+        true
+    }
+
+    // Alerts of the new node rules for a node seen for the first time
+    pub fn add_node(&mut self, key: &NodeKey, time: SystemTime) -> Vec<Alert>{
+        self.rules.iter()
+            .filter(|rule| rule.trigger == RuleTrigger::NewNode && rule.source_matches(&key.ip))
+            .map(|rule| rule.alert(time, *key))
+            .collect()
+    }
+
+    // `history` must already contain the packet, returns the alerts it raised
+    pub fn add_packet(&mut self, packet: &IPPacketInfo, history: &StatsHistory) -> Vec<Alert>{
+        if self.rules.is_empty(){
+            return Vec::new();
+        }
+        let (source, _) = packet.node_keys(self.per_vlan);
+        let time = packet.timestamp;
+        let mut alerts = Vec::new();
+        for index in 0..self.rules.len(){
+            let rule = &self.rules[index];
+            let RuleTrigger::Port(port) = rule.trigger else {
+                continue;
+            };
+            let port_matches = packet.source_port == Some(port) || packet.dest_port == Some(port);
+            if !port_matches || !rule.source_matches(&packet.source) || !rule.dest_matches(&packet.dest){
+                continue;
+            }
+            let mut alert = rule.alert(time, source);
+            alert.target = Some(packet.dest);
+            alert.port = Some(port);
+            alert.protocol = packet.protocol;
+            if self.cooldown((index, source, Some(packet.dest)), time, RULE_COOLDOWN){
+                alerts.push(alert);
+            }
+        }
+        let second = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        if self.checked.is_none_or(|checked| second > checked){
+            self.checked = Some(second);
+            alerts.extend(self.check_rates(time, history));
+        }
+        alerts
+    }
+
+    // Sources sending more than the rate rules allow over their window
+    fn check_rates(&mut self, time: SystemTime, history: &StatsHistory) -> Vec<Alert>{
+        let mut alerts = Vec::new();
+        for index in 0..self.rules.len(){
+            let rule = &self.rules[index];
+            let RuleTrigger::Sent{bytes, window} = rule.trigger else {
+                continue;
+            };
+            let mut sent: HashMap<NodeKey, u64> = HashMap::new();
+            for ((source, dest), pair_bytes) in history.pair_bytes(TimeWindow::Last(window)){
+                if rule.source_matches(&source.ip) && rule.dest_matches(&dest.ip){
+                    *sent.entry(source).or_default() += pair_bytes;
+                }
+            }
+            let mut offenders: Vec<(NodeKey, u64)> = sent.into_iter().filter(|(_, sent)| *sent > bytes).collect();
+            offenders.sort();
+            let rule_alerts: Vec<Alert> = offenders.into_iter().map(|(source, sent)| {
+                let mut alert = rule.alert(time, source);
+                alert.count = sent as usize;
+                alert
+            }).collect();
+            for alert in rule_alerts{
+                if self.cooldown((index, alert.source, None), time, window){
+                    alerts.push(alert);
+                }
+            }
+        }
+        let oldest = self.rules.iter().filter_map(|rule| match rule.trigger {
+            RuleTrigger::Sent{window, ..} => Some(window),
+            _ => None,
+        }).fold(RULE_COOLDOWN, Duration::max);
+        let since = time.checked_sub(oldest).unwrap_or(UNIX_EPOCH);
+        self.alerted.retain(|_, last| *last > since);
+        alerts
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::data::{AddressClass, Alert, AlertKind, FlowKey, FlowTable, IPPacketInfo, NodeKey, RuleAction};
+
+pub const DEFAULT_SCAN_WINDOW: Duration = Duration::from_secs(60);
+pub const DEFAULT_PORT_THRESHOLD: usize = 20;
+pub const DEFAULT_HOST_THRESHOLD: usize = 50;
+// Contacts older than the window are forgotten at most this late
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig{
+    pub window: Duration,
+    // Distinct ports of one host, 0 disables the port scan alerts
+    pub port_threshold: usize,
+    // Distinct hosts on one port, 0 disables the host sweep alerts
+    pub host_threshold: usize,
+}
+
+impl Default for ScanConfig{
+    fn default() -> Self {
+        ScanConfig{
+            window: DEFAULT_SCAN_WINDOW,
+            port_threshold: DEFAULT_PORT_THRESHOLD,
+            host_threshold: DEFAULT_HOST_THRESHOLD,
+        }
+    }
+}
+
+// Offender of an alert, it is raised again once a window has passed
+type AlertKey = (AlertKind, NodeKey, Option<IpAddr>, Option<u16>);
+
+// Flags sources contacting many ports of one host or many hosts on one port inside the window.
+// Packets of a flow answering an earlier one are left out, so a server replying to
+// the ephemeral ports of its clients isn't a scan
+#[derive(Default)]
+pub struct ScanDetector{
+    config: ScanConfig,
+    per_vlan: bool,
+    // Last contact of each port, by source and host
+    ports: HashMap<(NodeKey, IpAddr), HashMap<u16, SystemTime>>,
+    // Last contact of each host, by source, protocol and port
+    hosts: HashMap<(NodeKey, u8, u16), HashMap<IpAddr, SystemTime>>,
+    alerted: HashMap<AlertKey, SystemTime>,
+    last_prune: Option<SystemTime>,
+}
+
+fn is_reply(packet: &IPPacketInfo, flows: &FlowTable) -> bool{
+    let reverse = FlowKey{
+        source: packet.dest,
+        dest: packet.source,
+        protocol: packet.protocol,
+        source_port: packet.dest_port,
+        dest_port: packet.source_port,
+    };
+    flows.get(&reverse).is_some()
+}
+
+impl ScanDetector{
+    pub fn new(config: ScanConfig) -> ScanDetector{
+        ScanDetector{
+            config,
+            ..ScanDetector::default()
+        }
+    }
+
+    pub fn get_config(&self) -> ScanConfig{
+        self.config
+    }
+
+    // Contacts already counted are kept, they are checked against the new thresholds from the next packet
+    pub fn set_config(&mut self, config: ScanConfig){
+        self.config = config;
+    }
+
+    // Sources are nodes of their VLAN, see IPPacketInfo::node_keys
+    pub fn set_per_vlan(&mut self, per_vlan: bool){
+        self.per_vlan = per_vlan;
+    }
+
+    pub fn clear(&mut self){
+        self.ports.clear();
+        self.hosts.clear();
+        self.alerted.clear();
+        self.last_prune = None;
+    }
+
+    // `flows` must already contain the packet, returns the alerts it raised
+    pub fn add_packet(&mut self, packet: &IPPacketInfo, flows: &FlowTable) -> Vec<Alert>{
+        let Some(port) = packet.dest_port else {
+            return Vec::new();
+        };
+        if AddressClass::of(&packet.dest).is_group() || is_reply(packet, flows){
+            return Vec::new();
+        }
+        let time = packet.timestamp;
+        let since = time.checked_sub(self.config.window).unwrap_or(UNIX_EPOCH);
+        if self.last_prune.is_none_or(|last| time >= last + PRUNE_INTERVAL){
+            self.prune(since);
+            self.last_prune = Some(time);
+        }
+        let (source, _) = packet.node_keys(self.per_vlan);
+        let mut alerts = Vec::new();
+
+        let ports = self.ports.entry((source, packet.dest)).or_default();
+        ports.insert(port, time);
+        if self.config.port_threshold > 0 && ports.len() >= self.config.port_threshold{
+            alerts.push(Alert{
+                timestamp: time,
+                kind: AlertKind::PortScan,
+                source,
+                target: Some(packet.dest),
+                port: None,
+                protocol: packet.protocol,
+                count: ports.len(),
+                rule: None,
+                actions: vec![RuleAction::Highlight],
+            });
+        }
+        let hosts = self.hosts.entry((source, packet.protocol, port)).or_default();
+        hosts.insert(packet.dest, time);
+        if self.config.host_threshold > 0 && hosts.len() >= self.config.host_threshold{
+            alerts.push(Alert{
+                timestamp: time,
+                kind: AlertKind::HostSweep,
+                source,
+                target: None,
+                port: Some(port),
+                protocol: packet.protocol,
+                count: hosts.len(),
+                rule: None,
+                actions: vec![RuleAction::Highlight],
+            });
+        }
+        alerts.retain(|alert| {
+            let key = (alert.kind, alert.source, alert.target, alert.port);
+            if self.alerted.get(&key).is_some_and(|last| *last >= since){
+                return false;
+            }
+            self.alerted.insert(key, time);
+            true
+        });
+        alerts
+    }
+
+    // Forgets the contacts and alerts older than `since`
+    fn prune(&mut self, since: SystemTime){
+        for contacts in self.ports.values_mut(){
+            contacts.retain(|_, seen| *seen >= since);
+        }
+        self.ports.retain(|_, contacts| !contacts.is_empty());
+        for contacts in self.hosts.values_mut(){
+            contacts.retain(|_, seen| *seen >= since);
+        }
+        self.hosts.retain(|_, contacts| !contacts.is_empty());
+        self.alerted.retain(|_, last| *last >= since);
+    }
+}
@@ -0,0 +1,88 @@
+use std::net::IpAddr;
+use crate::data::{AddressClass, NodeData};
+
+// Parsed representation of the text typed in the search box
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchQuery{
+    Empty,
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+    Text(String),
+    // "vendor:apple", vendors of the MAC addresses containing the text
+    Vendor(String),
+    // "vlan:10", nodes seen on the VLAN
+    Vlan(u16),
+    // "class:multicast", nodes of the address class
+    Class(AddressClass),
+}
+
+impl SearchQuery{
+    pub fn parse(text: &str) -> SearchQuery{
+        let text = text.trim();
+        if text.is_empty(){
+            return SearchQuery::Empty;
+        }
+        if let Some(vendor) = strip_prefix_ignore_case(text, "vendor:"){
+            return SearchQuery::Vendor(vendor.trim().to_lowercase());
+        }
+        if let Some(vlan) = strip_prefix_ignore_case(text, "vlan:").and_then(|vlan| vlan.trim().parse::<u16>().ok()){
+            return SearchQuery::Vlan(vlan);
+        }
+        if let Some(class) = strip_prefix_ignore_case(text, "class:").and_then(|class| class.trim().parse::<AddressClass>().ok()){
+            return SearchQuery::Class(class);
+        }
+        if let Ok(ip) = text.parse::<IpAddr>(){
+            return SearchQuery::Ip(ip);
+        }
+        if let Some((network, prefix)) = text.split_once('/'){
+            if let (Ok(network), Ok(prefix)) = (network.parse::<IpAddr>(), prefix.parse::<u8>()){
+                let max_prefix = if network.is_ipv4() {32} else {128};
+                if prefix <= max_prefix{
+                    return SearchQuery::Cidr(network, prefix);
+                }
+            }
+        }
+        SearchQuery::Text(text.to_lowercase())
+    }
+
+    pub fn is_empty(&self) -> bool{
+        *self == SearchQuery::Empty
+    }
+
+    // `names` are the hostnames known for the node, server names are matched too
+    pub fn matches(&self, data: &NodeData, names: &[String]) -> bool{
+        match self {
+            SearchQuery::Empty => false,
+            // The IP of the nodes of the MAC view is not a real one, they are found by their MAC in the names
+            SearchQuery::Ip(_) | SearchQuery::Cidr(..) | SearchQuery::Class(_) if data.mac_node => false,
+            SearchQuery::Ip(ip) => data.ip == *ip,
+            SearchQuery::Cidr(network, prefix) => cidr_contains(network, *prefix, &data.ip),
+            SearchQuery::Text(text) => {
+                (!data.mac_node && data.ip.to_string().contains(text.as_str()))
+                    || names.iter().chain(&data.server_names).any(|name| name.contains(text.as_str()))
+            }
+            SearchQuery::Vendor(text) => data.vendor.as_ref().is_some_and(|vendor| vendor.to_lowercase().contains(text.as_str())),
+            SearchQuery::Vlan(vlan) => data.vlan == Some(*vlan) || data.vlans.contains(vlan),
+            SearchQuery::Class(class) => data.class() == *class,
+        }
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str>{
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then(|| &text[prefix.len()..])
+}
+
+pub(crate) fn cidr_contains(network: &IpAddr, prefix: u8, ip: &IpAddr) -> bool{
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(*network) & mask == u32::from(*ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(*network) & mask == u128::from(*ip) & mask
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,122 @@
+// Server names sent in clear by the clients: the SNI of TLS ClientHello messages and
+// the Host header of HTTP/1.x requests. QUIC encrypts its ClientHello with keys derived
+// from the connection ID, reading it would need AES-GCM and HKDF so it is not parsed
+
+const TLS_HANDSHAKE: u8 = 0x16;
+const TLS_CLIENT_HELLO: u8 = 0x01;
+const TLS_EXTENSION_SERVER_NAME: u16 = 0;
+const SERVER_NAME_HOST: u8 = 0;
+const HTTP_METHODS: [&str; 9] = ["GET ", "POST ", "HEAD ", "PUT ", "DELETE ", "OPTIONS ", "PATCH ", "CONNECT ", "TRACE "];
+// Headers after this are not looked at
+const MAX_HTTP_HEADER_LEN: usize = 8192;
+const MAX_NAME_LEN: usize = 253;
+
+// Name of the server the payload of a TCP segment sent by a client asks for
+pub fn parse_server_name(payload: &[u8]) -> Option<String>{
+    parse_tls_sni(payload).or_else(|| parse_http_host(payload))
+}
+
+struct Cursor<'a>{
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a>{
+    fn new(data: &'a [u8]) -> Cursor<'a>{
+        Cursor{data, pos: 0}
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]>{
+        let bytes = self.data.get(self.pos..self.pos + length)?;
+        self.pos += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8>{
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16>{
+        let bytes = self.bytes(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    // Bytes prefixed by their length on `size` bytes
+    fn vector(&mut self, size: usize) -> Option<&'a [u8]>{
+        let length = self.bytes(size)?.iter().fold(0usize, |length, byte| length << 8 | *byte as usize);
+        self.bytes(length)
+    }
+}
+
+// SNI of a ClientHello starting the payload, the message must be in one segment
+pub fn parse_tls_sni(payload: &[u8]) -> Option<String>{
+    let mut record = Cursor::new(payload);
+    if record.u8()? != TLS_HANDSHAKE || record.u8()? != 0x03{
+        return None;
+    }
+    record.u8()?;
+    let mut handshake = Cursor::new(record.vector(2)?);
+    if handshake.u8()? != TLS_CLIENT_HELLO{
+        return None;
+    }
+    let mut hello = Cursor::new(handshake.vector(3)?);
+    // Version and random
+    hello.bytes(2 + 32)?;
+    // Session id, cipher suites and compression methods
+    hello.vector(1)?;
+    hello.vector(2)?;
+    hello.vector(1)?;
+    let mut extensions = Cursor::new(hello.vector(2)?);
+    while let Some(extension_type) = extensions.u16(){
+        let data = extensions.vector(2)?;
+        if extension_type != TLS_EXTENSION_SERVER_NAME{
+            continue;
+        }
+        let mut names = Cursor::new(Cursor::new(data).vector(2)?);
+        while let Some(name_type) = names.u8(){
+            let name = names.vector(2)?;
+            if name_type == SERVER_NAME_HOST{
+                return to_server_name(name);
+            }
+        }
+    }
+    None
+}
+
+// Host header of an HTTP/1.x request starting the payload, without the port
+pub fn parse_http_host(payload: &[u8]) -> Option<String>{
+    if !HTTP_METHODS.iter().any(|method| payload.starts_with(method.as_bytes())){
+        return None;
+    }
+    let header = &payload[..payload.len().min(MAX_HTTP_HEADER_LEN)];
+    // The request line is skipped, the headers end at the first empty line
+    for line in header.split(|byte| *byte == b'\n').skip(1){
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty(){
+            break;
+        }
+        let Some(colon) = line.iter().position(|byte| *byte == b':') else {
+            continue;
+        };
+        if !line[..colon].eq_ignore_ascii_case(b"host"){
+            continue;
+        }
+        let host = std::str::from_utf8(&line[colon + 1..]).ok()?.trim();
+        let host = match host.strip_prefix('[') {
+            // IPv6 literal, [::1]:8080
+            Some(literal) => literal.split(']').next()?,
+            None => host.split(':').next()?,
+        };
+        return to_server_name(host.as_bytes());
+    }
+    None
+}
+
+fn to_server_name(name: &[u8]) -> Option<String>{
+    let valid = !name.is_empty() && name.len() <= MAX_NAME_LEN && name.iter().all(|byte| byte.is_ascii_graphic());
+    if !valid{
+        return None;
+    }
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    Some(name.trim_end_matches('.').to_string())
+}
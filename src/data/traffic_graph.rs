@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use crate::data::{Alert, DirectionTotals, FlowTable, GeoIpDatabase, HostDiscovery, IPPacketInfo, MacChange, NodeData, NodeKey, OuiTable, PacketDirection, Rule, RuleSet, ScanConfig, ScanDetector, StatsHistory, TimeWindow};
+use crate::export::ExportNode;
+
+// Per node statistics without any position or drawing information
+pub struct TrafficGraph{
+    nodes: HashMap<NodeKey, NodeData>,
+    packet_count: u64,
+    history: StatsHistory,
+    oui: OuiTable,
+    geoip: GeoIpDatabase,
+    per_vlan: bool,
+    local_addresses: Vec<IpAddr>,
+    directions: DirectionTotals,
+    flows: FlowTable,
+    scan: ScanDetector,
+    rules: RuleSet,
+    alerts: Vec<Alert>,
+}
+
+impl TrafficGraph{
+    pub fn new() -> TrafficGraph{
+        TrafficGraph{
+            nodes: HashMap::new(),
+            packet_count: 0,
+            history: StatsHistory::default(),
+            oui: OuiTable::embedded(),
+            geoip: GeoIpDatabase::new(),
+            per_vlan: false,
+            local_addresses: Vec::new(),
+            directions: DirectionTotals::default(),
+            flows: FlowTable::new(),
+            scan: ScanDetector::default(),
+            rules: RuleSet::default(),
+            alerts: Vec::new(),
+        }
+    }
+
+    // Nodes with the same IP on different VLANs are kept apart, set before adding packets
+    pub fn set_per_vlan(&mut self, per_vlan: bool){
+        self.per_vlan = per_vlan;
+        self.history.set_per_vlan(per_vlan);
+        self.scan.set_per_vlan(per_vlan);
+        self.rules.set_per_vlan(per_vlan);
+    }
+
+    // User rules checked on every packet
+    pub fn set_rules(&mut self, rules: Vec<Rule>){
+        self.rules = RuleSet::new(rules);
+        self.rules.set_per_vlan(self.per_vlan);
+    }
+
+    // Thresholds of the port scan and host sweep alerts
+    pub fn set_scan_config(&mut self, config: ScanConfig){
+        self.scan.set_config(config);
+    }
+
+    // Addresses of the capture interface, their nodes are marked as local
+    pub fn set_local_addresses(&mut self, addresses: Vec<IpAddr>){
+        self.local_addresses = addresses;
+        for node in self.nodes.values_mut(){
+            node.local = self.local_addresses.contains(&node.ip);
+        }
+    }
+
+    // Returns the alerts raised by the packet
+    pub fn add_packet(&mut self, packet: &IPPacketInfo) -> Vec<Alert>{
+        self.packet_count += 1;
+        self.flows.add_packet(packet);
+        let mut alerts = self.scan.add_packet(packet, &self.flows);
+        self.history.add_packet(packet);
+        alerts.extend(self.rules.add_packet(packet, &self.history));
+        self.directions.add_packet(PacketDirection::of(packet, &self.local_addresses), packet);
+        let (source, dest) = packet.node_keys(self.per_vlan);
+        for key in [source, dest]{
+            if !self.nodes.contains_key(&key){
+                alerts.extend(self.rules.add_node(&key, packet.timestamp));
+            }
+            self.get_or_insert(key).add_packet(packet);
+        }
+        self.alerts.extend(alerts.iter().cloned());
+        alerts
+    }
+
+    // Alerts in order of detection
+    pub fn get_alerts(&self) -> &Vec<Alert>{
+        &self.alerts
+    }
+
+    fn get_or_insert(&mut self, key: NodeKey) -> &mut NodeData{
+        let local = self.local_addresses.contains(&key.ip);
+        let geoip = &self.geoip;
+        self.nodes.entry(key).or_insert_with(|| NodeData{local, geo: geoip.lookup(&key.ip), ..NodeData::from_key(key)})
+    }
+
+    // Packets and bytes by direction relative to the local addresses
+    pub fn get_directions(&self) -> &DirectionTotals{
+        &self.directions
+    }
+
+    pub fn has_local_addresses(&self) -> bool{
+        !self.local_addresses.is_empty()
+    }
+
+    // Vendors of the MAC addresses discovered from now on, the embedded table by default
+    pub fn set_oui_table(&mut self, oui: OuiTable){
+        self.oui = oui;
+    }
+
+    // Country and AS of the public nodes
+    pub fn set_geoip(&mut self, geoip: GeoIpDatabase){
+        self.geoip = geoip;
+        for node in self.nodes.values_mut(){
+            node.geo = self.geoip.lookup(&node.ip);
+        }
+    }
+
+    // Returns the change when the host changed MAC, new node alerts are added to the alerts
+    pub fn add_discovery(&mut self, discovery: &HostDiscovery) -> Option<MacChange>{
+        let key = discovery.node_key(self.per_vlan);
+        if !self.nodes.contains_key(&key){
+            self.alerts.extend(self.rules.add_node(&key, discovery.timestamp));
+        }
+        let local = self.local_addresses.contains(&key.ip);
+        let geo = self.geoip.lookup(&key.ip);
+        let node = self.nodes.entry(key).or_insert_with(|| NodeData{local, geo, ..NodeData::from_key(key)});
+        let change = node.add_discovery(discovery);
+        node.refresh_vendor(&self.oui);
+        change
+    }
+
+    pub fn get_node<K: Into<NodeKey> + Copy>(&self, key: &K) -> Option<&NodeData>{
+        self.nodes.get(&(*key).into())
+    }
+
+    pub fn get_nodes(&self) -> impl Iterator<Item = &NodeData>{
+        self.nodes.values()
+    }
+
+    // Nodes without position nor names, the headless mode doesn't place nor resolve them
+    pub fn export_nodes(&self) -> Vec<ExportNode<'_>>{
+        self.nodes.values().map(|data| ExportNode{data, position: None, names: &[]}).collect()
+    }
+
+    // The whole capture uses the totals, a shorter window the retained history
+    pub fn window_nodes(&self, window: TimeWindow) -> Vec<NodeData>{
+        match window {
+            TimeWindow::All => self.nodes.values().cloned().collect(),
+            _ => self.history.nodes(window),
+        }
+    }
+
+    pub fn get_packet_count(&self) -> u64{
+        self.packet_count
+    }
+
+    // Nodes with the most bytes sent and received, busiest first
+    pub fn top_talkers(&self, count: usize) -> Vec<&NodeData>{
+        let mut nodes: Vec<&NodeData> = self.nodes.values().collect();
+        nodes.sort_by_key(|node| std::cmp::Reverse(node.stats.packet_bytes_sent + node.stats.packet_bytes_recv));
+        nodes.truncate(count);
+        nodes
+    }
+}
+
+impl Default for TrafficGraph{
+    fn default() -> Self {
+        TrafficGraph::new()
+    }
+}
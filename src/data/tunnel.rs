@@ -0,0 +1,152 @@
+use std::fmt;
+use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+use crate::data::IPPacketInfo;
+
+pub const VXLAN_PORT: u16 = 4789;
+pub const GENEVE_PORT: u16 = 6081;
+
+const IP_PROTOCOL_IPV4: u8 = 4;
+const IP_PROTOCOL_UDP: u8 = 17;
+const IP_PROTOCOL_IPV6: u8 = 41;
+const IP_PROTOCOL_GRE: u8 = 47;
+
+const ETHER_TYPE_IPV4: u16 = 0x0800;
+const ETHER_TYPE_IPV6: u16 = 0x86dd;
+// Transparent Ethernet bridging, the tunnel carries whole frames
+const ETHER_TYPE_ETHERNET: u16 = 0x6558;
+
+const GRE_CHECKSUM: u16 = 0x8000;
+const GRE_ROUTING: u16 = 0x4000;
+const GRE_KEY: u16 = 0x2000;
+const GRE_SEQUENCE: u16 = 0x1000;
+const GRE_VERSION: u16 = 0x0007;
+const VXLAN_VALID_VNI: u8 = 0x08;
+const VXLAN_HEADER_LEN: usize = 8;
+const GENEVE_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TunnelKind{
+    Gre,
+    Vxlan,
+    Geneve,
+    IpInIp,
+}
+
+impl fmt::Display for TunnelKind{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelKind::Gre => write!(f, "GRE"),
+            TunnelKind::Vxlan => write!(f, "VXLAN"),
+            TunnelKind::Geneve => write!(f, "GENEVE"),
+            TunnelKind::IpInIp => write!(f, "IP-in-IP"),
+        }
+    }
+}
+
+// Outer packet of a decapsulated one, the outermost for nested tunnels
+#[derive(Debug, PartialEq, Eq)]
+pub struct Tunnel{
+    pub kind: TunnelKind,
+    // VNI of VXLAN and GENEVE, key of GRE
+    pub id: Option<u32>,
+    pub outer: IPPacketInfo,
+}
+
+impl Tunnel{
+    pub fn endpoints(&self) -> TunnelEndpoints{
+        TunnelEndpoints{kind: self.kind, id: self.id, source: self.outer.source, dest: self.outer.dest}
+    }
+}
+
+// Tunnel a node was reached through, kept in the node statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TunnelEndpoints{
+    pub kind: TunnelKind,
+    pub id: Option<u32>,
+    pub source: IpAddr,
+    pub dest: IpAddr,
+}
+
+impl fmt::Display for TunnelEndpoints{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(id) = self.id{
+            write!(f, " {}", id)?;
+        }
+        write!(f, " {} -> {}", self.source, self.dest)
+    }
+}
+
+// Where the inner packet starts in the payload of the outer one
+#[derive(Debug, PartialEq, Eq)]
+pub struct Encapsulation{
+    pub kind: TunnelKind,
+    pub id: Option<u32>,
+    pub offset: usize,
+    // The inner packet starts with an Ethernet header, otherwise with an IP header
+    pub ethernet: bool,
+}
+
+// `payload` follows the IP header for GRE and IP-in-IP, the UDP header for VXLAN and GENEVE
+pub fn parse_encapsulation(protocol: u8, dest_port: Option<u16>, payload: &[u8]) -> Option<Encapsulation>{
+    let encapsulation = match (protocol, dest_port) {
+        (IP_PROTOCOL_IPV4 | IP_PROTOCOL_IPV6, _) => Encapsulation{kind: TunnelKind::IpInIp, id: None, offset: 0, ethernet: false},
+        (IP_PROTOCOL_GRE, _) => parse_gre(payload)?,
+        (IP_PROTOCOL_UDP, Some(VXLAN_PORT)) => parse_vxlan(payload)?,
+        (IP_PROTOCOL_UDP, Some(GENEVE_PORT)) => parse_geneve(payload)?,
+        _ => return None,
+    };
+    (encapsulation.offset < payload.len()).then_some(encapsulation)
+}
+
+fn is_ethernet(protocol: u16) -> Option<bool>{
+    match protocol {
+        ETHER_TYPE_ETHERNET => Some(true),
+        ETHER_TYPE_IPV4 | ETHER_TYPE_IPV6 => Some(false),
+        _ => None,
+    }
+}
+
+// Only version 0, version 1 is the PPP of PPTP
+fn parse_gre(payload: &[u8]) -> Option<Encapsulation>{
+    let header = payload.get(0..4)?;
+    let flags = u16::from_be_bytes([header[0], header[1]]);
+    if flags & (GRE_VERSION | GRE_ROUTING) != 0{
+        return None;
+    }
+    let ethernet = is_ethernet(u16::from_be_bytes([header[2], header[3]]))?;
+    let mut offset = 4;
+    if flags & GRE_CHECKSUM != 0{
+        offset += 4;
+    }
+    let mut id = None;
+    if flags & GRE_KEY != 0{
+        id = Some(u32::from_be_bytes(payload.get(offset..offset + 4)?.try_into().ok()?));
+        offset += 4;
+    }
+    if flags & GRE_SEQUENCE != 0{
+        offset += 4;
+    }
+    Some(Encapsulation{kind: TunnelKind::Gre, id, offset, ethernet})
+}
+
+fn parse_vxlan(payload: &[u8]) -> Option<Encapsulation>{
+    let header = payload.get(0..VXLAN_HEADER_LEN)?;
+    if header[0] & VXLAN_VALID_VNI == 0{
+        return None;
+    }
+    let vni = u32::from_be_bytes([0, header[4], header[5], header[6]]);
+    Some(Encapsulation{kind: TunnelKind::Vxlan, id: Some(vni), offset: VXLAN_HEADER_LEN, ethernet: true})
+}
+
+fn parse_geneve(payload: &[u8]) -> Option<Encapsulation>{
+    let header = payload.get(0..GENEVE_HEADER_LEN)?;
+    if header[0] >> 6 != 0{
+        return None;
+    }
+    let options_len = (header[0] & 0x3f) as usize * 4;
+    let ethernet = is_ethernet(u16::from_be_bytes([header[2], header[3]]))?;
+    let vni = u32::from_be_bytes([0, header[4], header[5], header[6]]);
+    Some(Encapsulation{kind: TunnelKind::Geneve, id: Some(vni), offset: GENEVE_HEADER_LEN + options_len, ethernet})
+}
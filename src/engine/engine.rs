@@ -1,38 +1,121 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::vec::Vec;
 use std::net::IpAddr;
-use macroquad::prelude::*;
+use std::time::SystemTime;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use crate::math::Point;
-use crate::data::IPPacketInfo;
-use crate::engine::{EngineSettings, PhysicsEngine, GraphicsEngine};
-use crate::entities::{PacketEntity, Entity, Node};
+use crate::data::{AddressClass, Alert, DirectionTotals, DnsRecord, FlowKey, FlowStats, FlowTable, FrameInfo, GeoInfo, GeoIpDatabase, DiscoverySource, HostDiscovery, IPPacketInfo, MacChange, NameTable, NodeData, NodeKey, OuiTable, PacketData, PacketDirection, Rule, RuleAction, RuleSet, ScanDetector, SearchQuery, StatsHistory, TimeWindow};
+use crate::engine::{category_color, Baseline, Color, ColorMode, EngineSettings, GroupMode, LayoutMode, PhysicsEngine, GraphicsEngine, InputState, Renderer, Session, SessionNode, SESSION_VERSION, WorldMap, GREEN, YELLOW};
+use crate::export::ExportNode;
+use crate::entities::{PacketEntity, Entity, Node, SearchState};
+
+// Angle between consecutive nodes in the spiral layout
+const GOLDEN_ANGLE: f32 = 2.399_963;
+const SPIRAL_SPACING: f32 = 25.0;
+
+// Space between a group of nodes of the same AS and its outline
+const GROUP_MARGIN: f32 = 20.0;
+const GROUP_LABEL_SIZE: f32 = 16.0;
+const GROUP_ALPHA: f32 = 0.5;
+
+// Packets launched at most for a multicast or broadcast packet in burst mode
+const MAX_BURST_PACKETS: usize = 64;
+
+// Oldest MAC changes are forgotten after this many
+const MAX_MAC_CHANGES: usize = 1000;
+// Oldest alerts are forgotten after this many
+const MAX_ALERTS: usize = 1000;
+const NEW_PAIR_THICKNESS: f32 = 2.0;
+
+// Screen size used until the first update
+const DEFAULT_SCREEN_WIDTH: f32 = 800.0;
+const DEFAULT_SCREEN_HEIGHT: f32 = 600.0;
+
+fn node_color(color_by: ColorMode, data: &NodeData) -> Color{
+    color_by.key(data).map(|key| category_color(&key)).unwrap_or(YELLOW)
+}
+
+fn is_bus(group_mode: GroupMode, key: &NodeKey) -> bool{
+    group_mode == GroupMode::Bus && AddressClass::of(&key.ip).is_group()
+}
+
 
 pub struct Engine {
     settings: EngineSettings,
-    node_position_map: HashMap<IpAddr,Node>,
+    node_position_map: HashMap<NodeKey,Node>,
     packet_position_map: Vec<PacketEntity>,
+    flows: FlowTable,
+    history: StatsHistory,
+    names: NameTable,
+    // Time of the last packet, the clock of the name table
+    last_packet_time: SystemTime,
+    // Packets come from a live capture, not a replayed file
+    live: bool,
+    mac_changes: Vec<MacChange>,
+    scan: ScanDetector,
+    rules: RuleSet,
+    alerts: Vec<Alert>,
+    // Alerts with actions left to the caller, until taken
+    pending_alerts: Vec<Alert>,
+    // Nodes glowing until they are older than the highlight duration
+    new_nodes: HashSet<NodeKey>,
+    // Peer pairs seen for the first time, with the smallest key first, and when
+    new_pairs: HashMap<(NodeKey, NodeKey), SystemTime>,
+    // Only what is missing from this saved session is new
+    baseline: Option<Baseline>,
+    oui: OuiTable,
+    // Color mode the node colors were computed with
+    color_by: ColorMode,
+    geoip: GeoIpDatabase,
+    // True once the nodes were moved to their AS group
+    grouped_by_asn: bool,
+    // Layout the nodes were last placed with
+    layout: LayoutMode,
+    world_map: WorldMap,
+    // Nodes with the same IP on different VLANs are kept apart
+    per_vlan: bool,
+    // Fed with frames, the addresses of the nodes are derived from their MAC and are not located
+    mac_view: bool,
+    // Group mode the bus nodes were set with
+    group_mode: GroupMode,
+    // Addresses of the capture interface
+    local_addresses: Vec<IpAddr>,
+    directions: DirectionTotals,
+    // True once the local nodes were pinned to the center
+    pinned_local: bool,
+
+    picked_node: Option<NodeKey>,
+    // Node shown in the detail window, the last one clicked
+    selected_node: Option<NodeKey>,
+    tooltip_node: Option<NodeKey>,
 
-    picked_node: Option<IpAddr>,
-    tooltip_node: Option<IpAddr>,
+    // World position of the top left corner of the screen
+    camera: Point,
+    search: SearchQuery,
+    search_matches: Vec<NodeKey>,
+    focused_node: Option<NodeKey>,
+
+    // Size of the screen on the last update
+    screen_size: (f32, f32),
+    rng: StdRng,
 }
 
 
 impl Engine {
     pub fn new() -> Engine{
-        rand::srand(macroquad::miniquad::date::now() as _);
-        let settings = EngineSettings {
-            update: true,
-            draw_debug: false,
-            max_packets: 10000,
-            
-            angle_launch: 0.1,
-            speed_launch: 1.5,
-            delete_distance: 8.0,
-
-            draw_tooltip: true,
-        };
+        Engine::with_rng(StdRng::from_entropy())
+    }
+
+    // Engine with a reproducible node placement and packet launch angles
+    pub fn with_seed(seed: u64) -> Engine{
+        Engine::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Engine{
+        let settings = EngineSettings::default();
 
-        let mut hashmap: HashMap<IpAddr,Node> = HashMap::new();
+        let mut hashmap: HashMap<NodeKey,Node> = HashMap::new();
         hashmap.reserve(500);
 
         let mut packet_vector: Vec<PacketEntity> = Vec::new();
@@ -42,8 +125,40 @@ impl Engine {
             settings: settings,
             node_position_map: hashmap,
             packet_position_map: packet_vector,
+            flows: FlowTable::new(),
+            history: StatsHistory::default(),
+            names: NameTable::new(),
+            last_packet_time: SystemTime::UNIX_EPOCH,
+            live: false,
+            mac_changes: Vec::new(),
+            scan: ScanDetector::default(),
+            rules: RuleSet::default(),
+            alerts: Vec::new(),
+            pending_alerts: Vec::new(),
+            new_nodes: HashSet::new(),
+            new_pairs: HashMap::new(),
+            baseline: None,
+            oui: OuiTable::embedded(),
+            color_by: ColorMode::None,
+            geoip: GeoIpDatabase::new(),
+            grouped_by_asn: false,
+            layout: LayoutMode::Random,
+            world_map: WorldMap::fit((DEFAULT_SCREEN_WIDTH, DEFAULT_SCREEN_HEIGHT)),
+            per_vlan: false,
+            mac_view: false,
+            group_mode: GroupMode::Bus,
+            local_addresses: Vec::new(),
+            directions: DirectionTotals::default(),
+            pinned_local: true,
             picked_node: None,
+            selected_node: None,
             tooltip_node: None,
+            camera: Point{x: 0.0, y: 0.0},
+            search: SearchQuery::Empty,
+            search_matches: Vec::new(),
+            focused_node: None,
+            screen_size: (DEFAULT_SCREEN_WIDTH, DEFAULT_SCREEN_HEIGHT),
+            rng,
         }
     }
 
@@ -55,18 +170,739 @@ impl Engine {
         return &mut self.settings;
     }
 
-    fn move_node(&mut self, ip: &IpAddr, point: &Point){
-        self.node_position_map.get_mut(ip).unwrap().move_to(point);
-        self.packet_position_map.iter_mut().filter(|packet| packet.get_ip_dest() == ip).for_each(|packet| packet.set_destination(point));
+    pub fn export_nodes(&self) -> Vec<ExportNode<'_>>{
+        self.node_position_map.values().map(|node| ExportNode{
+            data: node.get_data(),
+            position: Some(*node.get_position()),
+            names: node.get_names(),
+        }).collect()
+    }
+
+    // The whole capture uses the totals, a shorter window the retained history
+    pub fn window_nodes(&self, window: TimeWindow) -> Vec<NodeData>{
+        match window {
+            TimeWindow::All => self.node_position_map.values().map(|node| node.get_data().clone()).collect(),
+            _ => self.history.nodes(window),
+        }
+    }
+
+    pub fn get_flows(&self) -> &FlowTable{
+        &self.flows
+    }
+
+    // Flows from or to the node, biggest first
+    pub fn get_node_flows(&self, ip: &IpAddr) -> Vec<(&FlowKey, &FlowStats)>{
+        let mut flows: Vec<(&FlowKey, &FlowStats)> = self.flows.iter()
+            .filter(|(key, _)| key.source == *ip || key.dest == *ip)
+            .collect();
+        flows.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.packet_bytes));
+        flows
+    }
+
+    pub fn get_selected_node(&self) -> Option<&Node>{
+        self.selected_node.and_then(|key| self.node_position_map.get(&key))
+    }
+
+    pub fn select_node(&mut self, key: Option<NodeKey>){
+        self.selected_node = key;
+    }
+
+    // Makes nodes unique per (VLAN, IP), set before adding packets
+    pub fn set_per_vlan(&mut self, per_vlan: bool){
+        self.per_vlan = per_vlan;
+        self.history.set_per_vlan(per_vlan);
+        self.scan.set_per_vlan(per_vlan);
+        self.rules.set_per_vlan(per_vlan);
+    }
+
+    pub fn to_session(&self) -> Session{
+        Session{
+            version: SESSION_VERSION,
+            settings: self.settings.clone(),
+            camera: self.camera,
+            per_vlan: self.per_vlan,
+            nodes: self.node_position_map.values().map(|node| SessionNode{
+                data: node.get_data().clone(),
+                position: *node.get_position(),
+                pinned: node.is_pinned(),
+                names: node.get_names().clone(),
+            }).collect(),
+            flows: self.flows.entries(),
+        }
+    }
+
+    // Replaces the current graph with the one saved in the session
+    pub fn load_session(&mut self, session: Session){
+        self.settings = session.settings;
+        self.camera = session.camera;
+        // New packets must give the keys of the saved nodes
+        self.set_per_vlan(session.per_vlan);
+        self.node_position_map = session.nodes.into_iter().map(|node| {
+            let mut entity = Node::from_data(node.data, &node.position, node.pinned);
+            entity.set_names(node.names);
+            (entity.get_data().key(), entity)
+        }).collect();
+        self.packet_position_map.clear();
+        self.flows = FlowTable::from_entries(session.flows);
+        // The history is not saved, windows only cover packets captured after opening
+        self.history.clear();
+        self.directions.clear();
+        self.names.clear();
+        self.mac_changes.clear();
+        self.scan.clear();
+        self.rules.clear();
+        self.alerts.clear();
+        self.pending_alerts.clear();
+        // Nodes of the session are not new
+        self.new_nodes.clear();
+        self.new_pairs.clear();
+        self.picked_node = None;
+        self.selected_node = None;
+        self.tooltip_node = None;
+        self.focused_node = None;
+        if self.geoip.is_empty(){
+            self.refresh_colors();
+        }else{
+            self.refresh_geo();
+        }
+        // Saved positions are kept
+        self.grouped_by_asn = self.settings.group_by_asn;
+        self.layout = self.settings.layout;
+        self.pinned_local = self.settings.pin_local;
+        self.refresh_group_mode();
+        self.refresh_search_state();
+    }
+
+    fn move_node(&mut self, key: &NodeKey, point: &Point){
+        self.node_position_map.get_mut(key).unwrap().move_to(point);
+        self.packet_position_map.iter_mut().filter(|packet| packet.get_dest_node() == key).for_each(|packet| packet.set_destination(point));
+    }
+
+    pub fn get_node<K: Into<NodeKey> + Copy>(&self, key: &K) -> Option<&Node>{
+        self.node_position_map.get(&(*key).into())
+    }
+
+    // Names found by reverse lookups, they only name nodes without a better one
+    pub fn add_reverse_names(&mut self, records: &[DnsRecord]){
+        let mut renamed = false;
+        for record in records{
+            self.names.add_reverse(record, self.last_packet_time);
+            renamed |= self.refresh_names(&record.ip, self.last_packet_time);
+        }
+        if renamed && !self.search.is_empty(){
+            self.refresh_search_state();
+        }
+    }
+
+    // Records the MAC and DHCP information of the host, hosts that didn't send
+    // any IP packet yet get a node. Returns the change when the host changed MAC
+    pub fn add_discovery(&mut self, discovery: &HostDiscovery) -> Option<MacChange>{
+        let key = discovery.node_key(self.per_vlan);
+        if !self.node_position_map.contains_key(&key){
+            self.insert_new_node(key, discovery.timestamp);
+        }
+        let node = self.node_position_map.get_mut(&key).unwrap();
+        let change = node.add_discovery(discovery);
+        if node.refresh_vendor(&self.oui){
+            self.refresh_color(&key);
+            // The vendor may make the node match the search
+            if !self.search.is_empty(){
+                self.refresh_search_state();
+            }
+        }
+        let change = change?;
+        if self.mac_changes.len() >= MAX_MAC_CHANGES{
+            self.mac_changes.remove(0);
+        }
+        self.mac_changes.push(change.clone());
+        Some(change)
+    }
+
+    // Vendors of the MAC addresses, the embedded table by default
+    pub fn set_oui_table(&mut self, oui: OuiTable){
+        self.oui = oui;
+        let mut changed = false;
+        for node in self.node_position_map.values_mut(){
+            changed |= node.refresh_vendor(&self.oui);
+        }
+        if changed{
+            self.refresh_colors();
+            self.refresh_search_state();
+        }
+    }
+
+    // Country and AS databases of the public addresses, looked up once per node
+    pub fn set_geoip(&mut self, geoip: GeoIpDatabase){
+        self.geoip = geoip;
+        self.refresh_geo();
+    }
+
+    fn lookup_geo(&self, ip: &IpAddr) -> Option<GeoInfo>{
+        if self.mac_view {None} else {self.geoip.lookup(ip)}
+    }
+
+    fn refresh_geo(&mut self){
+        let geos: Vec<(NodeKey, Option<GeoInfo>)> = self.node_position_map.keys().map(|key| (*key, self.lookup_geo(&key.ip))).collect();
+        for (key, geo) in geos{
+            self.node_position_map.get_mut(&key).unwrap().set_geo(geo);
+        }
+        self.refresh_colors();
+        self.grouped_by_asn = false;
+    }
+
+    // Nodes by AS number, for the public nodes found in the ASN database
+    fn get_asn_groups(&self) -> BTreeMap<u32, Vec<&Node>>{
+        let mut groups: BTreeMap<u32, Vec<&Node>> = BTreeMap::new();
+        for node in self.node_position_map.values(){
+            if let Some(asn) = node.get_data().geo.as_ref().and_then(|geo| geo.asn){
+                groups.entry(asn).or_default().push(node);
+            }
+        }
+        groups.values_mut().for_each(|nodes| nodes.sort_by_key(|node| node.get_data().key()));
+        groups
+    }
+
+    // Position of the `index`th node of a group, on a spiral around the first one
+    fn get_group_position(anchor: &Point, index: usize) -> Point{
+        let index = index as f32;
+        let angle = index * GOLDEN_ANGLE;
+        let radius = SPIRAL_SPACING * index.sqrt();
+        Point{x: anchor.x + radius*angle.cos(), y: anchor.y + radius*angle.sin()}
+    }
+
+    // Coordinates of the node on the world map, None for the nodes of the local network inset
+    fn get_map_key(geo: Option<&GeoInfo>) -> Option<(u64, u64)>{
+        let geo = geo?;
+        Some((geo.latitude?.to_bits(), geo.longitude?.to_bits()))
+    }
+
+    fn get_map_anchor(&self, geo: Option<&GeoInfo>) -> Point{
+        match geo.and_then(|geo| Some((geo.latitude?, geo.longitude?))) {
+            Some((latitude, longitude)) => self.world_map.project(latitude, longitude),
+            None => self.world_map.inset_center(),
+        }
+    }
+
+    // Nodes at the same place are spread on a spiral around it
+    fn get_world_position(&self, geo: Option<&GeoInfo>) -> Point{
+        let key = Engine::get_map_key(geo);
+        let index = self.node_position_map.values()
+            .filter(|node| Engine::get_map_key(node.get_data().geo.as_ref()) == key)
+            .count();
+        Engine::get_group_position(&self.get_map_anchor(geo), index)
+    }
+
+    // Moves every node to its place on the world map, pinned nodes stay in place
+    fn place_on_map(&mut self){
+        let mut keys: Vec<NodeKey> = self.node_position_map.keys().copied().collect();
+        keys.sort();
+        let mut counts: HashMap<Option<(u64, u64)>, usize> = HashMap::new();
+        let mut moves = Vec::new();
+        for key in keys{
+            let node = &self.node_position_map[&key];
+            let geo = node.get_data().geo.as_ref();
+            let index = counts.entry(Engine::get_map_key(geo)).or_default();
+            if !node.is_pinned(){
+                moves.push((key, Engine::get_group_position(&self.get_map_anchor(geo), *index)));
+            }
+            *index += 1;
+        }
+        for (key, point) in moves{
+            self.move_node(&key, &point);
+        }
+    }
+
+    // Moves the nodes of each AS around the first one of the group, pinned nodes stay in place
+    fn group_by_asn(&mut self){
+        let moves: Vec<(NodeKey, Point)> = self.get_asn_groups().values()
+            .flat_map(|nodes| {
+                let anchor = *nodes[0].get_position();
+                nodes.iter().enumerate().skip(1)
+                    .filter(|(_, node)| !node.is_pinned())
+                    .map(move |(index, node)| (node.get_data().key(), Engine::get_group_position(&anchor, index)))
+            })
+            .collect();
+        for (key, point) in moves{
+            self.move_node(&key, &point);
+        }
+        self.grouped_by_asn = true;
+    }
+
+    // Outline and AS name around the groups of more than one node
+    fn draw_asn_groups(&self, renderer: &mut dyn Renderer){
+        for nodes in self.get_asn_groups().values().filter(|nodes| nodes.len() > 1){
+            let count = nodes.len() as f32;
+            let center = Point{
+                x: nodes.iter().map(|node| node.get_position().x).sum::<f32>() / count,
+                y: nodes.iter().map(|node| node.get_position().y).sum::<f32>() / count,
+            };
+            let radius = nodes.iter().map(|node| node.get_position().distance(&center)).fold(0.0, f32::max) + GROUP_MARGIN;
+            let label = nodes[0].get_data().geo.as_ref().and_then(|geo| geo.as_label()).unwrap_or_default();
+            let color = category_color(&label).with_alpha(GROUP_ALPHA);
+            let center = center.relative_to(&self.camera);
+            renderer.draw_circle_lines(&center, radius, 1.0, color);
+            let label_pos = Point{x: center.x, y: center.y - radius - GROUP_LABEL_SIZE};
+            renderer.draw_text(&label, &label_pos, GROUP_LABEL_SIZE, color);
+        }
+    }
+
+    // Values of the color mode attribute with their color and number of nodes, most common first
+    pub fn get_color_legend(&self) -> Vec<(String, Color, usize)>{
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for node in self.node_position_map.values(){
+            if let Some(key) = self.color_by.key(node.get_data()){
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+        let mut legend: Vec<(String, Color, usize)> = counts.into_iter()
+            .map(|(key, count)| {
+                let color = category_color(&key);
+                (key, color, count)
+            })
+            .collect();
+        legend.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        legend
+    }
+
+    fn refresh_color(&mut self, key: &NodeKey){
+        if let Some(node) = self.node_position_map.get_mut(key){
+            node.set_color(node_color(self.color_by, node.get_data()));
+        }
+    }
+
+    fn refresh_colors(&mut self){
+        self.color_by = self.settings.color_by;
+        for node in self.node_position_map.values_mut(){
+            node.set_color(node_color(self.color_by, node.get_data()));
+        }
+    }
+
+    // Addresses of the capture interface, their nodes are marked as local
+    pub fn set_local_addresses(&mut self, addresses: Vec<IpAddr>){
+        self.local_addresses = addresses;
+        for node in self.node_position_map.values_mut(){
+            node.set_local(self.local_addresses.contains(&node.get_data().ip));
+        }
+        self.place_local_nodes();
+    }
+
+    pub fn get_local_addresses(&self) -> &Vec<IpAddr>{
+        &self.local_addresses
+    }
+
+    // Packets and bytes by direction relative to the local addresses
+    pub fn get_directions(&self) -> &DirectionTotals{
+        &self.directions
+    }
+
+    // Position of the `index`th local node, around the center of the screen
+    fn get_local_position(&self, index: usize) -> Point{
+        let center = Point{x: self.camera.x + self.screen_size.0/2.0, y: self.camera.y + self.screen_size.1/2.0};
+        Engine::get_group_position(&center, index)
+    }
+
+    fn get_local_keys(&self) -> Vec<NodeKey>{
+        let mut keys: Vec<NodeKey> = self.node_position_map.iter()
+            .filter(|(_, node)| node.get_data().local)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    // Pins the local nodes to the center or unpins them, the world map places them in its inset
+    fn place_local_nodes(&mut self){
+        self.pinned_local = self.settings.pin_local;
+        if self.settings.layout == LayoutMode::World{
+            return;
+        }
+        for (index, key) in self.get_local_keys().into_iter().enumerate(){
+            if self.pinned_local{
+                let point = self.get_local_position(index);
+                self.move_node(&key, &point);
+            }
+            self.node_position_map.get_mut(&key).unwrap().set_pinned(self.pinned_local);
+        }
+    }
+
+    fn refresh_group_mode(&mut self){
+        self.group_mode = self.settings.group_mode;
+        for (key, node) in self.node_position_map.iter_mut(){
+            node.set_bus(is_bus(self.group_mode, key));
+        }
+    }
+
+    pub fn is_node_shown(&self, key: &NodeKey) -> bool{
+        self.is_shown(key)
+    }
+
+    // Nodes of hidden classes are not drawn, neither are group nodes in burst mode.
+    // The addresses of the MAC view have no class, its nodes are always drawn
+    fn is_shown(&self, key: &NodeKey) -> bool{
+        if self.mac_view{
+            return true;
+        }
+        let class = AddressClass::of(&key.ip);
+        let burst = class.is_group() && self.settings.group_mode == GroupMode::Burst;
+        !burst && !self.settings.hidden_classes.contains(&class)
+    }
+
+    // Local nodes of the VLAN and IP version of the group, they get a packet of a multicast or broadcast one
+    fn get_burst_members(&self, source: &NodeKey, group: &NodeKey) -> Vec<NodeKey>{
+        let mut members: Vec<NodeKey> = self.node_position_map.keys()
+            .filter(|key| *key != source && key.vlan == group.vlan && key.ip.is_ipv4() == group.ip.is_ipv4())
+            .filter(|key| AddressClass::of(&key.ip).is_local() && self.is_shown(key))
+            .copied()
+            .collect();
+        members.sort();
+        members.truncate(MAX_BURST_PACKETS);
+        members
+    }
+
+    fn launch_packet(&mut self, packet_data: PacketData, source_pos: &Point, dest_pos: &Point){
+        let dimmed = !self.search.is_empty() && !self.is_highlighted(&packet_data.source) && !self.is_highlighted(&packet_data.dest);
+        let mut packet_entity = if self.settings.layout == LayoutMode::World{
+            let control = self.world_map.arc_control(source_pos, dest_pos);
+            PacketEntity::with_arc(packet_data, source_pos, &control, dest_pos, self.settings.speed_launch)
+        }else{
+            let angle = self.rng.gen_range(-self.settings.angle_launch..=self.settings.angle_launch);
+            PacketEntity::new(packet_data, source_pos, dest_pos, self.settings.speed_launch, angle)
+        };
+        packet_entity.set_dimmed(dimmed);
+        self.packet_position_map.push(packet_entity);
+    }
+
+    // Frame of the MAC view, its nodes are keyed by MacAddress::to_node_ip and named by their MAC
+    pub fn add_frame(&mut self, frame: &FrameInfo){
+        self.mac_view = true;
+        let mut added = false;
+        for mac in [frame.source, frame.dest]{
+            let ip = mac.to_node_ip();
+            if self.node_position_map.contains_key(&ip.into()){
+                continue;
+            }
+            self.add_discovery(&HostDiscovery{ip, mac, source: DiscoverySource::Ethernet, dhcp: None, timestamp: frame.timestamp, vlan: None});
+            let node = self.node_position_map.get_mut(&ip.into()).unwrap();
+            node.set_names(vec![mac.to_string()]);
+            node.set_mac_node(true);
+            self.refresh_color(&ip.into());
+            added = true;
+        }
+        // The names may make the new nodes match the search
+        if added && !self.search.is_empty(){
+            self.refresh_search_state();
+        }
+        self.add_packet(&frame.to_packet_info());
     }
 
-    fn get_screen_random_position() -> Point{
+    // MAC changes in order of detection
+    pub fn get_mac_changes(&self) -> &Vec<MacChange>{
+        &self.mac_changes
+    }
+
+    // Scan and rule alerts in order of detection
+    pub fn get_alerts(&self) -> &Vec<Alert>{
+        &self.alerts
+    }
+
+    pub fn clear_alerts(&mut self){
+        self.alerts.clear();
+    }
+
+    // Alerts raised since the last call whose log, sound or webhook actions are to be run
+    pub fn take_new_alerts(&mut self) -> Vec<Alert>{
+        std::mem::take(&mut self.pending_alerts)
+    }
+
+    pub fn get_rules(&self) -> &Vec<Rule>{
+        self.rules.get_rules()
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<Rule>){
+        self.rules = RuleSet::new(rules);
+        self.rules.set_per_vlan(self.per_vlan);
+    }
+
+    pub fn add_rule(&mut self, rule: Rule){
+        self.rules.add_rule(rule);
+    }
+
+    pub fn remove_rule(&mut self, index: usize){
+        self.rules.remove_rule(index);
+    }
+
+    // Flashes the offending node if the alert asks for it
+    fn add_alert(&mut self, alert: Alert){
+        if alert.actions.contains(&RuleAction::Highlight){
+            if let Some(node) = self.node_position_map.get_mut(&alert.source){
+                node.flash();
+            }
+        }
+        if alert.actions.iter().any(|action| *action != RuleAction::Highlight){
+            if self.pending_alerts.len() >= MAX_ALERTS{
+                self.pending_alerts.remove(0);
+            }
+            self.pending_alerts.push(alert.clone());
+        }
+        if self.alerts.len() >= MAX_ALERTS{
+            self.alerts.remove(0);
+        }
+        self.alerts.push(alert);
+    }
+
+    pub fn get_nodes(&self) -> impl Iterator<Item = &Node>{
+        self.node_position_map.values()
+    }
+
+    pub fn get_packets(&self) -> impl Iterator<Item = &PacketEntity>{
+        self.packet_position_map.iter()
+    }
+
+    pub fn get_tooltip_node(&self) -> Option<&Node>{
+        if !self.settings.draw_tooltip{
+            return None;
+        }
+        self.tooltip_node.and_then(|key| self.node_position_map.get(&key))
+    }
+
+    fn get_screen_random_position(&mut self) -> Point{
+        let (width, height) = self.screen_size;
+        Point{
+            x: self.camera.x + self.rng.gen_range(0.0..=width),
+            y: self.camera.y + self.rng.gen_range(0.0..=height)
+        }
+    }
+
+    // Places nodes on a sunflower spiral around the center of the screen, in order of arrival
+    fn get_spiral_position(&self) -> Point{
+        let index = self.node_position_map.len() as f32;
+        let angle = index * GOLDEN_ANGLE;
+        let radius = SPIRAL_SPACING * index.sqrt();
         Point{
-            x:rand::gen_range(0.0,screen_width()),
-            y: rand::gen_range(0.0,screen_height())
+            x: self.camera.x + self.screen_size.0/2.0 + radius*angle.cos(),
+            y: self.camera.y + self.screen_size.1/2.0 + radius*angle.sin(),
+        }
+    }
+
+    // Updates the hostnames of the nodes of the IP from the name table, returns true if they changed.
+    // Nodes keep their names when the table has forgotten them
+    fn refresh_names(&mut self, ip: &IpAddr, time: SystemTime) -> bool{
+        let names = self.names.names(ip, time);
+        if names.is_empty(){
+            return false;
+        }
+        let mut renamed = false;
+        // Names are per IP, shared by the nodes of every VLAN
+        let nodes: Vec<&mut Node> = if self.per_vlan{
+            self.node_position_map.values_mut().filter(|node| node.get_data().ip == *ip).collect()
+        }else{
+            self.node_position_map.get_mut(&(*ip).into()).into_iter().collect()
+        };
+        for node in nodes{
+            if *node.get_names() != names{
+                node.set_names(names.clone());
+                renamed = true;
+            }
+        }
+        renamed
+    }
+
+    // Node seen for the first time in the capture, may raise new node alerts
+    fn insert_new_node(&mut self, key: NodeKey, time: SystemTime){
+        self.insert_node(key, time);
+        let baseline = self.baseline.as_ref().is_some_and(|baseline| baseline.contains_node(&key));
+        if !self.settings.new_highlight.is_zero() && !baseline{
+            self.new_nodes.insert(key);
+            self.node_position_map.get_mut(&key).unwrap().set_glow(1.0);
+        }
+        for alert in self.rules.add_node(&key, time){
+            self.add_alert(alert);
+        }
+    }
+
+    // Nodes and pairs of the session are no longer highlighted when seen
+    pub fn set_baseline(&mut self, session: &Session){
+        self.baseline = Some(Baseline::new(session));
+    }
+
+    pub fn clear_baseline(&mut self){
+        self.baseline = None;
+    }
+
+    pub fn get_baseline(&self) -> Option<&Baseline>{
+        self.baseline.as_ref()
+    }
+
+    // 1 for something first seen at the time of the last packet, 0 once older than the highlight duration
+    // A live capture keeps aging its nodes when quiet, a replayed file only with its packets
+    pub fn set_live(&mut self, live: bool){
+        self.live = live;
+    }
+
+    fn get_glow(&self, first_seen: SystemTime) -> f32{
+        let duration = self.settings.new_highlight;
+        if duration.is_zero(){
+            return 0.0;
+        }
+        let now = if self.live {SystemTime::now().max(self.last_packet_time)} else {self.last_packet_time};
+        let age = now.duration_since(first_seen).unwrap_or_default();
+        1.0 - (age.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+    }
+
+    // Fades the new nodes and pairs, forgets them once faded
+    fn refresh_glow(&mut self){
+        let mut faded = Vec::new();
+        for key in &self.new_nodes{
+            let node = &self.node_position_map[key];
+            let glow = self.get_glow(node.get_data().first_seen.unwrap_or(self.last_packet_time));
+            if glow <= 0.0{
+                faded.push(*key);
+            }
+            self.node_position_map.get_mut(key).unwrap().set_glow(glow);
+        }
+        for key in faded{
+            self.new_nodes.remove(&key);
+        }
+        let pairs = std::mem::take(&mut self.new_pairs);
+        self.new_pairs = pairs.into_iter().filter(|(_, first_seen)| self.get_glow(*first_seen) > 0.0).collect();
+    }
+
+    pub fn is_new_pair(&self, source: &NodeKey, dest: &NodeKey) -> bool{
+        self.new_pairs.contains_key(&(*source.min(dest), *source.max(dest)))
+    }
+
+    // Line between the nodes of the new pairs, fading with their age
+    fn draw_new_pairs(&self, renderer: &mut dyn Renderer){
+        for ((source, dest), first_seen) in &self.new_pairs{
+            if !self.is_shown(source) || !self.is_shown(dest){
+                continue;
+            }
+            let (Some(source_node), Some(dest_node)) = (self.node_position_map.get(source), self.node_position_map.get(dest)) else {
+                continue;
+            };
+            let from = source_node.get_position().relative_to(&self.camera);
+            let to = dest_node.get_landing_point(source_node.get_position()).relative_to(&self.camera);
+            renderer.draw_line(&from, &to, NEW_PAIR_THICKNESS, GREEN.with_alpha(self.get_glow(*first_seen)));
         }
     }
-    
+
+    // `time` is the one of the packet, to pick names that were still valid when it was captured
+    fn insert_node(&mut self, key: NodeKey, time: SystemTime){
+        let geo = self.lookup_geo(&key.ip);
+        let asn = geo.as_ref().and_then(|geo| geo.asn);
+        // The world map places nodes by location, not by AS
+        let grouped = self.settings.group_by_asn && self.settings.layout != LayoutMode::World;
+        let group = asn.filter(|_| grouped).and_then(|asn| self.get_asn_groups().remove(&asn));
+        let local = self.local_addresses.contains(&key.ip);
+        let pin = local && self.settings.pin_local && self.settings.layout != LayoutMode::World;
+        let pos = match (group, self.settings.layout) {
+            _ if pin => self.get_local_position(self.get_local_keys().len()),
+            (Some(nodes), _) => Engine::get_group_position(nodes[0].get_position(), nodes.len()),
+            (None, LayoutMode::Random) => self.get_screen_random_position(),
+            (None, LayoutMode::Spiral) => self.get_spiral_position(),
+            (None, LayoutMode::World) => self.get_world_position(geo.as_ref()),
+        };
+        let mut node = Node::new(key, &pos);
+        node.set_local(local);
+        node.set_pinned(pin);
+        node.set_names(self.names.names(&key.ip, time));
+        node.set_geo(geo);
+        node.set_bus(is_bus(self.group_mode, &key));
+        self.node_position_map.insert(key, node);
+        self.refresh_color(&key);
+        self.init_search_state(&key);
+    }
+
+    pub fn set_search(&mut self, text: &str){
+        let search = SearchQuery::parse(text);
+        if search == self.search{
+            return;
+        }
+        self.search = search;
+        self.focused_node = None;
+        self.refresh_search_state();
+    }
+
+    // Nodes matching the current search, sorted by ip
+    pub fn get_search_matches(&self) -> &Vec<NodeKey>{
+        &self.search_matches
+    }
+
+    pub fn get_focused_node(&self) -> Option<NodeKey>{
+        self.focused_node
+    }
+
+    // Centers the camera on the node and marks it as focused
+    pub fn jump_to(&mut self, key: &NodeKey){
+        let Some(node) = self.node_position_map.get(key) else {
+            return;
+        };
+        let pos = node.get_position();
+        self.camera = Point{
+            x: pos.x - self.screen_size.0/2.0,
+            y: pos.y - self.screen_size.1/2.0,
+        };
+        self.focused_node = Some(*key);
+        self.refresh_search_state();
+    }
+
+    fn get_search_state(&self, data_matches: bool, key: &NodeKey) -> SearchState{
+        if self.focused_node == Some(*key){
+            SearchState::Focused
+        }else if self.search.is_empty(){
+            SearchState::None
+        }else if data_matches{
+            SearchState::Match
+        }else{
+            SearchState::Dimmed
+        }
+    }
+
+    fn is_highlighted(&self, key: &NodeKey) -> bool{
+        match self.node_position_map.get(key) {
+            Some(node) => matches!(node.get_search_state(), SearchState::Match | SearchState::Focused),
+            None => false,
+        }
+    }
+
+    fn refresh_search_state(&mut self){
+        self.search_matches.clear();
+        let states: Vec<(NodeKey, bool, SearchState)> = self.node_position_map.iter().map(|(key, node)| {
+            let data_matches = self.search.matches(node.get_data(), node.get_names());
+            (*key, data_matches, self.get_search_state(data_matches, key))
+        }).collect();
+        for (key, data_matches, state) in states{
+            if data_matches{
+                self.search_matches.push(key);
+            }
+            self.node_position_map.get_mut(&key).unwrap().set_search_state(state);
+        }
+        self.search_matches.sort();
+
+        let search_active = !self.search.is_empty();
+        let dimmed: Vec<bool> = self.packet_position_map.iter().map(|packet| {
+            search_active && !self.is_highlighted(packet.get_source_node()) && !self.is_highlighted(packet.get_dest_node())
+        }).collect();
+        for (packet, dimmed) in self.packet_position_map.iter_mut().zip(dimmed){
+            packet.set_dimmed(dimmed);
+        }
+    }
+
+    // Sets the search state of a node that was just inserted
+    fn init_search_state(&mut self, key: &NodeKey){
+        if self.search.is_empty(){
+            return;
+        }
+        let node = &self.node_position_map[key];
+        let data_matches = self.search.matches(node.get_data(), node.get_names());
+        let state = self.get_search_state(data_matches, key);
+        if data_matches{
+            let idx = self.search_matches.binary_search(key).unwrap_or_else(|idx| idx);
+            self.search_matches.insert(idx, *key);
+        }
+        self.node_position_map.get_mut(key).unwrap().set_search_state(state);
+    }
+
 }
 
 
@@ -77,15 +913,61 @@ impl PhysicsEngine for Engine {
 
     fn add_packet(&mut self, packet: &IPPacketInfo) {
 
+        self.flows.add_packet(packet);
+        self.scan.set_config(self.settings.scan);
+        let mut alerts = self.scan.add_packet(packet, &self.flows);
+        self.history.add_packet(packet);
+        alerts.extend(self.rules.add_packet(packet, &self.history));
+        let direction = PacketDirection::of(packet, &self.local_addresses);
+        self.directions.add_packet(direction, packet);
+        self.last_packet_time = self.last_packet_time.max(packet.timestamp);
+
+        let (source, dest) = packet.node_keys(self.per_vlan);
+
         // Insert source
-        let source_node = self.node_position_map.entry(packet.source).or_insert(Node::new(packet.source,&Engine::get_screen_random_position()));
+        if !self.node_position_map.contains_key(&source){
+            self.insert_new_node(source, packet.timestamp);
+        }
+        let source_node = self.node_position_map.get_mut(&source).unwrap();
+        let known_pair = source_node.get_data().sent_to.contains_key(&packet.dest) || source_node.get_data().recv_from.contains_key(&packet.dest);
+        let source_vlan_count = source_node.get_data().vlans.len();
         source_node.add_packet(packet);
-        let source_pos = source_node.get_position().clone();
+        let source_tagged = source_node.get_data().vlans.len() != source_vlan_count;
+        let source_pos = *source_node.get_position();
 
         // Insert dest
-        let dest_node = self.node_position_map.entry(packet.dest).or_insert(Node::new(packet.dest,&Engine::get_screen_random_position()));
+        if !self.node_position_map.contains_key(&dest){
+            self.insert_new_node(dest, packet.timestamp);
+        }
+        let dest_node = self.node_position_map.get_mut(&dest).unwrap();
+        let server_name_count = dest_node.get_data().server_names.len();
+        let dest_vlan_count = dest_node.get_data().vlans.len();
         dest_node.add_packet(packet);
-        let dest_pos = dest_node.get_position();
+        let dest_tagged = dest_node.get_data().vlans.len() != dest_vlan_count;
+        // A new server name may make the destination match the search
+        let mut search_changed = dest_node.get_data().server_names.len() != server_name_count;
+        // Nodes are coloured and searched before their packets, a new VLAN may change both
+        for (key, tagged) in [(source, source_tagged), (dest, dest_tagged)]{
+            if tagged{
+                self.refresh_color(&key);
+                search_changed = true;
+            }
+        }
+
+        for record in &packet.dns_records{
+            self.names.add(record, packet.timestamp);
+            search_changed |= self.refresh_names(&record.ip, packet.timestamp);
+        }
+        if search_changed && !self.search.is_empty(){
+            self.refresh_search_state();
+        }
+        for alert in alerts{
+            self.add_alert(alert);
+        }
+        let baseline = self.baseline.as_ref().is_some_and(|baseline| baseline.contains_pair(&source, &dest));
+        if !known_pair && !baseline && source != dest && !self.settings.new_highlight.is_zero(){
+            self.new_pairs.insert((source.min(dest), source.max(dest)), packet.timestamp);
+        }
 
         // Check max packets
         if self.settings.max_packets < self.packet_position_map.len() as u32{
@@ -93,10 +975,53 @@ impl PhysicsEngine for Engine {
         }
 
         // Insert packet
-        self.packet_position_map.push(PacketEntity::new(packet,&source_pos,dest_pos,self.settings.speed_launch,self.settings.angle_launch));
+        if !self.is_shown(&source){
+            return;
+        }
+        if self.settings.group_mode == GroupMode::Burst && AddressClass::of(&dest.ip).is_group(){
+            for member in self.get_burst_members(&source, &dest){
+                if self.settings.max_packets < self.packet_position_map.len() as u32{
+                    break;
+                }
+                let member_pos = *self.node_position_map[&member].get_position();
+                self.launch_packet(PacketData{source, dest: member, direction}, &source_pos, &member_pos);
+            }
+            return;
+        }
+        if !self.is_shown(&dest){
+            return;
+        }
+        let dest_pos = self.node_position_map[&dest].get_landing_point(&source_pos);
+        self.launch_packet(PacketData::new(packet, self.per_vlan, &self.local_addresses), &source_pos, &dest_pos);
     }
 
-    fn update(&mut self) {
+    fn update(&mut self, input: &dyn InputState) {
+        self.screen_size = input.screen_size();
+        if self.settings.color_by != self.color_by{
+            self.refresh_colors();
+        }
+        if self.settings.group_mode != self.group_mode{
+            self.refresh_group_mode();
+        }
+        if self.settings.pin_local != self.pinned_local{
+            self.place_local_nodes();
+        }
+        let world_map = WorldMap::fit(self.screen_size);
+        let layout_changed = self.settings.layout != self.layout;
+        if self.settings.layout == LayoutMode::World && (layout_changed || world_map != self.world_map){
+            // The map fits the screen when the camera is at the origin
+            self.camera = Point{x: 0.0, y: 0.0};
+            self.world_map = world_map;
+            self.place_on_map();
+        }
+        self.world_map = world_map;
+        self.layout = self.settings.layout;
+        if self.settings.group_by_asn != self.grouped_by_asn{
+            if self.settings.group_by_asn && self.settings.layout != LayoutMode::World{
+                self.group_by_asn();
+            }
+            self.grouped_by_asn = self.settings.group_by_asn;
+        }
         if !self.settings.update{
             return;
         }
@@ -104,27 +1029,39 @@ impl PhysicsEngine for Engine {
         for packet in &mut self.packet_position_map{
             packet.update();
         }
+        for node in self.node_position_map.values_mut().filter(|node| node.is_flashing()){
+            node.update();
+        }
+        self.refresh_glow();
 
-        let mouse_pos = Point::new(mouse_position());
+        let mouse_pos = input.mouse_position();
+        let mouse_pos = Point{x: mouse_pos.x + self.camera.x, y: mouse_pos.y + self.camera.y};
 
         // Drag picked node
-        if is_mouse_button_down(MouseButton::Left) && self.picked_node.is_some(){
-            let ip = self.picked_node.unwrap();
-            self.move_node(&ip,&mouse_pos);
+        if input.is_mouse_down() && self.picked_node.is_some(){
+            let key = self.picked_node.unwrap();
+            self.move_node(&key,&mouse_pos);
         }else{
             self.picked_node = None;
         }
         
         // Set tooltip
         self.tooltip_node = None;
-        for (ip,node) in self.node_position_map.iter_mut(){
-            if node.is_point_inside(&mouse_pos){
-                if is_mouse_button_down(MouseButton::Left){
-                    self.picked_node = Some(*ip);
+        let hovered = self.node_position_map.iter()
+            .find(|(key, node)| node.is_point_inside(&mouse_pos) && self.is_shown(key))
+            .map(|(key, _)| *key);
+        if let Some(key) = hovered{
+            let node = self.node_position_map.get_mut(&key).unwrap();
+            if input.is_secondary_clicked(){
+                node.set_pinned(!node.is_pinned());
+            }
+            if input.is_mouse_down(){
+                self.selected_node = Some(key);
+                if !node.is_pinned(){
+                    self.picked_node = Some(key);
                 }
-                self.tooltip_node = Some(*ip);
-                break;
             }
+            self.tooltip_node = Some(key);
         }
 
         self.packet_position_map.retain(|packet| {   
@@ -142,23 +1079,28 @@ impl GraphicsEngine for Engine {
         return &mut self.settings;
     }
 
-    fn draw(&self) {
+    fn draw(&self, renderer: &mut dyn Renderer) {
         
-        self.node_position_map.iter().for_each(|(_,node)| node.draw());
-        self.packet_position_map.iter().for_each(|packet| packet.draw());
+        if self.settings.layout == LayoutMode::World{
+            self.world_map.draw(renderer, &self.camera);
+        }else if self.settings.group_by_asn{
+            self.draw_asn_groups(renderer);
+        }
+        self.draw_new_pairs(renderer);
+        let nodes = || self.node_position_map.iter().filter(|(key, _)| self.is_shown(key)).map(|(_, node)| node);
+        // Packets launched before their class was hidden
+        let packets = || self.packet_position_map.iter()
+            .filter(|packet| self.is_shown(packet.get_source_node()) && self.is_shown(packet.get_dest_node()));
+        nodes().for_each(|node| node.draw(renderer, &self.camera));
+        if self.settings.draw_labels{
+            nodes().for_each(|node| node.draw_label(renderer, &self.camera));
+        }
+        packets().for_each(|packet| packet.draw(renderer, &self.camera));
         
         // Draw debug
         if self.settings.draw_debug{
-            self.node_position_map.iter().for_each(|(_,node)| node.draw_debug());
-            self.packet_position_map.iter().for_each(|packet| packet.draw_debug());
-        }
-
-        // Display tooltip
-        if self.tooltip_node.is_some() && self.settings.draw_tooltip{
-            egui_macroquad::ui(|egui_ctx| {
-                self.node_position_map.get(&self.tooltip_node.unwrap()).unwrap().draw_tooltip(egui_ctx);
-            });
-            egui_macroquad::draw();
+            nodes().for_each(|node| node.draw_debug(renderer, &self.camera));
+            packets().for_each(|packet| packet.draw_debug(renderer, &self.camera));
         }
 
     }
@@ -0,0 +1,52 @@
+use crate::engine::{Color, InputState, Renderer};
+use crate::math::Point;
+use macroquad::prelude as mq;
+
+fn to_macroquad(color: Color) -> mq::Color{
+    mq::Color::new(color.r, color.g, color.b, color.a)
+}
+
+// Draws on the macroquad window
+pub struct MacroquadRenderer;
+
+impl Renderer for MacroquadRenderer{
+    fn draw_circle(&mut self, center: &Point, radius: f32, color: Color){
+        mq::draw_circle(center.x, center.y, radius, to_macroquad(color));
+    }
+
+    fn draw_circle_lines(&mut self, center: &Point, radius: f32, thickness: f32, color: Color){
+        mq::draw_circle_lines(center.x, center.y, radius, thickness, to_macroquad(color));
+    }
+
+    fn draw_line(&mut self, from: &Point, to: &Point, thickness: f32, color: Color){
+        mq::draw_line(from.x, from.y, to.x, to.y, thickness, to_macroquad(color));
+    }
+
+    fn draw_text(&mut self, text: &str, position: &Point, size: f32, color: Color){
+        let dimensions = mq::measure_text(text, None, size as u16, 1.0);
+        let x = position.x - dimensions.width/2.0;
+        let y = position.y + dimensions.offset_y;
+        mq::draw_text(text, x, y, size, to_macroquad(color));
+    }
+}
+
+// Reads the macroquad window and mouse state
+pub struct MacroquadInput;
+
+impl InputState for MacroquadInput{
+    fn screen_size(&self) -> (f32, f32){
+        (mq::screen_width(), mq::screen_height())
+    }
+
+    fn mouse_position(&self) -> Point{
+        Point::new(mq::mouse_position())
+    }
+
+    fn is_mouse_down(&self) -> bool{
+        mq::is_mouse_button_down(mq::MouseButton::Left)
+    }
+
+    fn is_secondary_clicked(&self) -> bool{
+        mq::is_mouse_button_pressed(mq::MouseButton::Right)
+    }
+}
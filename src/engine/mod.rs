@@ -1,9 +1,160 @@
-use crate::data::IPPacketInfo;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::data::{AddressClass, IPPacketInfo, NodeData, ScanConfig};
 
 mod engine;
+mod render;
+mod recording;
+mod session;
+mod world_map;
+#[cfg(feature = "gui")]
+mod macroquad_backend;
 
 pub use engine::Engine;
+pub use render::{category_color, Color, Renderer, InputState, YELLOW, ORANGE, RED, GREEN, BLUE, WHITE};
+pub use recording::{RecordingRenderer, DrawCommand, StaticInput};
+pub use session::{Baseline, Session, SessionNode, SessionError, SESSION_VERSION};
+pub use world_map::WorldMap;
+#[cfg(feature = "gui")]
+pub use macroquad_backend::{MacroquadRenderer, MacroquadInput};
 
+pub const DEFAULT_NEW_HIGHLIGHT: Duration = Duration::from_secs(30);
+
+// Strategy used to place new nodes on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode{
+    Random,
+    Spiral,
+    // Public nodes at their GeoIP coordinates on a world map
+    World,
+}
+
+impl FromStr for LayoutMode{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(LayoutMode::Random),
+            "spiral" => Ok(LayoutMode::Spiral),
+            "world" => Ok(LayoutMode::World),
+            _ => Err(format!("Unknown layout mode '{}', expected 'random', 'spiral' or 'world'", s)),
+        }
+    }
+}
+
+impl fmt::Display for LayoutMode{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutMode::Random => write!(f, "random"),
+            LayoutMode::Spiral => write!(f, "spiral"),
+            LayoutMode::World => write!(f, "world"),
+        }
+    }
+}
+
+// How multicast and broadcast destinations are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupMode{
+    // Like any other node
+    Node,
+    // Bar the packets drop onto, their fan-in is spread along it
+    Bus,
+    // No node, the packets fan out to every local node of the same VLAN and IP version
+    Burst,
+}
+
+impl GroupMode{
+    pub const ALL: [GroupMode; 3] = [GroupMode::Node, GroupMode::Bus, GroupMode::Burst];
+}
+
+impl FromStr for GroupMode{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "node" => Ok(GroupMode::Node),
+            "bus" => Ok(GroupMode::Bus),
+            "burst" => Ok(GroupMode::Burst),
+            _ => Err(format!("Unknown group mode '{}', expected 'node', 'bus' or 'burst'", s)),
+        }
+    }
+}
+
+impl fmt::Display for GroupMode{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupMode::Node => write!(f, "node"),
+            GroupMode::Bus => write!(f, "bus"),
+            GroupMode::Burst => write!(f, "burst"),
+        }
+    }
+}
+
+// Attribute giving its color to each node, nodes without it stay yellow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode{
+    None,
+    Vendor,
+    Country,
+    Asn,
+    Vlan,
+    // Multicast, broadcast, link-local, loopback, private or public
+    Class,
+}
+
+impl ColorMode{
+    pub const ALL: [ColorMode; 6] = [ColorMode::None, ColorMode::Vendor, ColorMode::Country, ColorMode::Asn, ColorMode::Vlan, ColorMode::Class];
+
+    // Value of the attribute for the node, nodes with the same value get the same color
+    pub fn key(&self, data: &NodeData) -> Option<String>{
+        match self {
+            ColorMode::None => None,
+            ColorMode::Vendor => data.vendor.clone(),
+            ColorMode::Country => data.geo.as_ref()?.country.clone(),
+            ColorMode::Asn => data.geo.as_ref()?.as_label(),
+            ColorMode::Vlan => data.vlan_label(),
+            // The addresses of the MAC view are not real IP addresses
+            ColorMode::Class => Some(data.class().to_string()).filter(|_| !data.mac_node),
+        }
+    }
+}
+
+impl FromStr for ColorMode{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(ColorMode::None),
+            "vendor" => Ok(ColorMode::Vendor),
+            "country" => Ok(ColorMode::Country),
+            "asn" => Ok(ColorMode::Asn),
+            "vlan" => Ok(ColorMode::Vlan),
+            "class" => Ok(ColorMode::Class),
+            _ => Err(format!("Unknown color mode '{}', expected 'none', 'vendor', 'country', 'asn', 'vlan' or 'class'", s)),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::None => write!(f, "none"),
+            ColorMode::Vendor => write!(f, "vendor"),
+            ColorMode::Country => write!(f, "country"),
+            ColorMode::Asn => write!(f, "asn"),
+            ColorMode::Vlan => write!(f, "vlan"),
+            ColorMode::Class => write!(f, "class"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EngineSettings{
     pub update: bool,
     pub draw_debug: bool,
@@ -14,15 +165,55 @@ pub struct EngineSettings{
 
     pub delete_distance: f32,
     pub draw_tooltip: bool,
+    // Hostnames and vendors under the nodes
+    pub draw_labels: bool,
+    pub layout: LayoutMode,
+    pub color_by: ColorMode,
+    // Public nodes of the same AS are placed together
+    pub group_by_asn: bool,
+    pub group_mode: GroupMode,
+    // Nodes of these classes and their packets are not drawn
+    pub hidden_classes: Vec<AddressClass>,
+    // Nodes of the local host are pinned to the center of the screen
+    pub pin_local: bool,
+    // Thresholds of the port scan and host sweep alerts
+    pub scan: ScanConfig,
+    // Capture time new nodes and connections glow for, zero turns it off
+    pub new_highlight: Duration,
+}
+
+impl Default for EngineSettings{
+    fn default() -> Self {
+        EngineSettings {
+            update: true,
+            draw_debug: false,
+            max_packets: 10000,
+
+            angle_launch: 0.1,
+            speed_launch: 1.5,
+            delete_distance: 8.0,
+
+            draw_tooltip: true,
+            draw_labels: true,
+            layout: LayoutMode::Random,
+            color_by: ColorMode::None,
+            group_by_asn: false,
+            group_mode: GroupMode::Bus,
+            hidden_classes: Vec::new(),
+            pin_local: true,
+            scan: ScanConfig::default(),
+            new_highlight: DEFAULT_NEW_HIGHLIGHT,
+        }
+    }
 }
 
 pub trait PhysicsEngine {
     fn get_settings(&mut self) -> &mut EngineSettings;
     fn add_packet(&mut self, packet: &IPPacketInfo);
-    fn update(&mut self);
+    fn update(&mut self, input: &dyn InputState);
 }
 
 pub trait GraphicsEngine {
     fn get_settings(&mut self) -> &mut EngineSettings;
-    fn draw(&self);
+    fn draw(&self, renderer: &mut dyn Renderer);
 }
@@ -0,0 +1,82 @@
+use crate::engine::{Color, InputState, Renderer};
+use crate::math::Point;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand{
+    Circle{center: Point, radius: f32, color: Color},
+    CircleLines{center: Point, radius: f32, thickness: f32, color: Color},
+    Line{from: Point, to: Point, thickness: f32, color: Color},
+    Text{text: String, position: Point, size: f32, color: Color},
+}
+
+// Renderer keeping every draw call in memory, used to check what the engine draws without a window
+#[derive(Debug, Default)]
+pub struct RecordingRenderer{
+    pub commands: Vec<DrawCommand>,
+}
+
+impl RecordingRenderer{
+    pub fn new() -> RecordingRenderer{
+        RecordingRenderer{commands: Vec::new()}
+    }
+
+    pub fn clear(&mut self){
+        self.commands.clear();
+    }
+}
+
+impl Renderer for RecordingRenderer{
+    fn draw_circle(&mut self, center: &Point, radius: f32, color: Color){
+        self.commands.push(DrawCommand::Circle{center: *center, radius, color});
+    }
+
+    fn draw_circle_lines(&mut self, center: &Point, radius: f32, thickness: f32, color: Color){
+        self.commands.push(DrawCommand::CircleLines{center: *center, radius, thickness, color});
+    }
+
+    fn draw_line(&mut self, from: &Point, to: &Point, thickness: f32, color: Color){
+        self.commands.push(DrawCommand::Line{from: *from, to: *to, thickness, color});
+    }
+
+    fn draw_text(&mut self, text: &str, position: &Point, size: f32, color: Color){
+        self.commands.push(DrawCommand::Text{text: text.to_string(), position: *position, size, color});
+    }
+}
+
+// Input that only changes when its fields are changed
+#[derive(Debug, Clone)]
+pub struct StaticInput{
+    pub screen_size: (f32, f32),
+    pub mouse_position: Point,
+    pub mouse_down: bool,
+    pub secondary_clicked: bool,
+}
+
+impl StaticInput{
+    pub fn new(width: f32, height: f32) -> StaticInput{
+        StaticInput{
+            screen_size: (width, height),
+            mouse_position: Point{x: -1.0, y: -1.0},
+            mouse_down: false,
+            secondary_clicked: false,
+        }
+    }
+}
+
+impl InputState for StaticInput{
+    fn screen_size(&self) -> (f32, f32){
+        self.screen_size
+    }
+
+    fn mouse_position(&self) -> Point{
+        self.mouse_position
+    }
+
+    fn is_mouse_down(&self) -> bool{
+        self.mouse_down
+    }
+
+    fn is_secondary_clicked(&self) -> bool{
+        self.secondary_clicked
+    }
+}
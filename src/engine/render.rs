@@ -0,0 +1,65 @@
+use crate::math::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color{
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color{
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Color{
+        Color{r, g, b, a}
+    }
+
+    pub const fn with_alpha(&self, a: f32) -> Color{
+        Color{r: self.r, g: self.g, b: self.b, a}
+    }
+}
+
+// Same values as the macroquad palette
+pub const YELLOW: Color = Color::new(0.99, 0.98, 0.00, 1.00);
+pub const ORANGE: Color = Color::new(1.00, 0.63, 0.00, 1.00);
+pub const RED: Color = Color::new(0.90, 0.16, 0.22, 1.00);
+pub const GREEN: Color = Color::new(0.00, 0.89, 0.19, 1.00);
+pub const BLUE: Color = Color::new(0.00, 0.47, 0.95, 1.00);
+pub const WHITE: Color = Color::new(1.00, 1.00, 1.00, 1.00);
+
+// Color of a category, the same for every run. The hash of the name picks the hue
+pub fn category_color(key: &str) -> Color{
+    // FNV-1a
+    let hash = key.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    let hue = (hash % 360) as f32 / 60.0;
+    let (saturation, value) = (0.65, 0.95);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = value - chroma;
+    Color::new(r + min, g + min, b + min, 1.0)
+}
+
+// Drawing primitives used by the entities, coordinates are in screen space
+pub trait Renderer{
+    fn draw_circle(&mut self, center: &Point, radius: f32, color: Color);
+    fn draw_circle_lines(&mut self, center: &Point, radius: f32, thickness: f32, color: Color);
+    fn draw_line(&mut self, from: &Point, to: &Point, thickness: f32, color: Color);
+    // Text centered horizontally on `position`, which is the top of the text
+    fn draw_text(&mut self, text: &str, position: &Point, size: f32, color: Color);
+}
+
+// State of the window and the mouse for the current frame
+pub trait InputState{
+    fn screen_size(&self) -> (f32, f32);
+    fn mouse_position(&self) -> Point;
+    fn is_mouse_down(&self) -> bool;
+    // True only on the frame the secondary button was pressed
+    fn is_secondary_clicked(&self) -> bool;
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::net::IpAddr;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::data::{FlowKey, FlowStats, NodeData, NodeKey};
+use crate::engine::EngineSettings;
+use crate::math::Point;
+
+// Incremented every time the format changes, older versions are upgraded on load.
+// 2: names, MACs, vendor, VLANs, geo, tunnels and first seen time of the nodes, server names of the flows, per VLAN flag
+pub const SESSION_VERSION: u32 = 2;
+// Version 1 sessions only lack fields, they are read with their defaults
+const FIRST_SESSION_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SessionError{
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SessionError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "{}", e),
+            SessionError::Format(e) => write!(f, "invalid session file: {}", e),
+            SessionError::UnsupportedVersion(version) => write!(f, "unsupported session version {}, expected {} to {}", version, FIRST_SESSION_VERSION, SESSION_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for SessionError{}
+
+impl From<io::Error> for SessionError{
+    fn from(e: io::Error) -> Self {
+        SessionError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SessionError{
+    fn from(e: serde_json::Error) -> Self {
+        SessionError::Format(e)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionNode{
+    pub data: NodeData,
+    pub position: Point,
+    pub pinned: bool,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+// Snapshot of the engine, packets in flight are not saved
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session{
+    pub version: u32,
+    pub settings: EngineSettings,
+    pub camera: Point,
+    // Nodes with the same IP on different VLANs were kept apart, their keys have a VLAN
+    #[serde(default)]
+    pub per_vlan: bool,
+    pub nodes: Vec<SessionNode>,
+    pub flows: Vec<(FlowKey, FlowStats)>,
+}
+
+impl Session{
+    pub fn save(&self, path: &Path) -> Result<(), SessionError>{
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Session, SessionError>{
+        let reader = BufReader::new(File::open(path)?);
+        // Check the version before the rest of the format, which may have changed
+        let value: serde_json::Value = serde_json::from_reader(reader)?;
+        let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0) as u32;
+        if !(FIRST_SESSION_VERSION..=SESSION_VERSION).contains(&version){
+            return Err(SessionError::UnsupportedVersion(version));
+        }
+        let mut session: Session = serde_json::from_value(value)?;
+        session.version = SESSION_VERSION;
+        Ok(session)
+    }
+}
+
+// Nodes and peer pairs of a saved session, the ones seen again are not new
+pub struct Baseline{
+    nodes: HashSet<NodeKey>,
+    // Node and address of a peer it sent to or received from
+    pairs: HashSet<(NodeKey, IpAddr)>,
+}
+
+impl Baseline{
+    pub fn new(session: &Session) -> Baseline{
+        let mut nodes = HashSet::new();
+        let mut pairs = HashSet::new();
+        for node in &session.nodes{
+            let key = node.data.key();
+            nodes.insert(key);
+            pairs.extend(node.data.sent_to.keys().chain(node.data.recv_from.keys()).map(|peer| (key, *peer)));
+        }
+        Baseline{nodes, pairs}
+    }
+
+    pub fn contains_node(&self, key: &NodeKey) -> bool{
+        self.nodes.contains(key)
+    }
+
+    pub fn contains_pair(&self, source: &NodeKey, dest: &NodeKey) -> bool{
+        self.pairs.contains(&(*source, dest.ip))
+    }
+
+    pub fn len(&self) -> usize{
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.nodes.is_empty()
+    }
+}
@@ -0,0 +1,99 @@
+use crate::engine::{Renderer, WHITE};
+use crate::math::Point;
+
+// Width over height of the equirectangular projection
+const MAP_RATIO: f32 = 2.0;
+const GRID_STEP: f64 = 30.0;
+const GRID_ALPHA: f32 = 0.1;
+const EQUATOR_ALPHA: f32 = 0.25;
+// Local network inset, in the south Pacific, as a fraction of the map
+const INSET_X: f32 = 0.02;
+const INSET_Y: f32 = 0.72;
+const INSET_WIDTH: f32 = 0.2;
+const INSET_HEIGHT: f32 = 0.25;
+const INSET_LABEL_SIZE: f32 = 14.0;
+// Height of the packet arcs relative to the distance they cover
+const ARC_HEIGHT: f32 = 0.2;
+
+// Equirectangular world map in world coordinates, public nodes are placed at their
+// GeoIP coordinates and the other ones in the local network inset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldMap{
+    pub origin: Point,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl WorldMap{
+    // Biggest map fitting the screen when the camera is at the origin
+    pub fn fit(screen_size: (f32, f32)) -> WorldMap{
+        let (screen_width, screen_height) = screen_size;
+        let width = screen_width.min(screen_height * MAP_RATIO);
+        let height = width / MAP_RATIO;
+        WorldMap{
+            origin: Point{x: (screen_width - width) / 2.0, y: (screen_height - height) / 2.0},
+            width,
+            height,
+        }
+    }
+
+    pub fn project(&self, latitude: f64, longitude: f64) -> Point{
+        let x = (longitude.clamp(-180.0, 180.0) + 180.0) / 360.0;
+        let y = (90.0 - latitude.clamp(-90.0, 90.0)) / 180.0;
+        Point{
+            x: self.origin.x + x as f32 * self.width,
+            y: self.origin.y + y as f32 * self.height,
+        }
+    }
+
+    pub fn inset_center(&self) -> Point{
+        Point{
+            x: self.origin.x + (INSET_X + INSET_WIDTH / 2.0) * self.width,
+            y: self.origin.y + (INSET_Y + INSET_HEIGHT / 2.0) * self.height,
+        }
+    }
+
+    // Control point of the curve a packet follows from `from` to `to`. Like great circles,
+    // the curves bend towards the closest pole
+    pub fn arc_control(&self, from: &Point, to: &Point) -> Point{
+        let middle = from.get_middle_point(to);
+        let bend = ARC_HEIGHT * from.distance(to);
+        let equator = self.origin.y + self.height / 2.0;
+        let direction = if middle.y <= equator {-1.0} else {1.0};
+        Point{x: middle.x, y: middle.y + direction * bend}
+    }
+
+    // Outline, parallels and meridians, and the local network inset
+    pub fn draw(&self, renderer: &mut dyn Renderer, camera: &Point){
+        let grid = WHITE.with_alpha(GRID_ALPHA);
+        let mut longitude = -180.0;
+        while longitude <= 180.0{
+            let top = self.project(90.0, longitude).relative_to(camera);
+            let bottom = self.project(-90.0, longitude).relative_to(camera);
+            renderer.draw_line(&top, &bottom, 1.0, grid);
+            longitude += GRID_STEP;
+        }
+        let mut latitude = -90.0;
+        while latitude <= 90.0{
+            let color = if latitude == 0.0 {WHITE.with_alpha(EQUATOR_ALPHA)} else {grid};
+            let left = self.project(latitude, -180.0).relative_to(camera);
+            let right = self.project(latitude, 180.0).relative_to(camera);
+            renderer.draw_line(&left, &right, 1.0, color);
+            latitude += GRID_STEP;
+        }
+
+        let corner = |x: f32, y: f32| Point{x: self.origin.x + x * self.width, y: self.origin.y + y * self.height}.relative_to(camera);
+        let corners = [
+            corner(INSET_X, INSET_Y),
+            corner(INSET_X + INSET_WIDTH, INSET_Y),
+            corner(INSET_X + INSET_WIDTH, INSET_Y + INSET_HEIGHT),
+            corner(INSET_X, INSET_Y + INSET_HEIGHT),
+        ];
+        let inset = WHITE.with_alpha(EQUATOR_ALPHA);
+        for idx in 0..corners.len(){
+            renderer.draw_line(&corners[idx], &corners[(idx + 1) % corners.len()], 1.0, inset);
+        }
+        let label_pos = Point{x: (corners[0].x + corners[1].x) / 2.0, y: corners[0].y - INSET_LABEL_SIZE};
+        renderer.draw_text("Local network", &label_pos, INSET_LABEL_SIZE, inset);
+    }
+}
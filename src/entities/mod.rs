@@ -1,16 +1,16 @@
 use crate::math::Point;
+use crate::engine::Renderer;
 mod packet;
 mod node;
 
-pub use node::Node;
+pub use node::{Node, SearchState};
 pub use packet::PacketEntity;
 
 
 pub trait Entity{
-    fn draw(&self);
-    fn draw_debug(&self);
+    fn draw(&self, renderer: &mut dyn Renderer, camera: &Point);
+    fn draw_debug(&self, renderer: &mut dyn Renderer, camera: &Point);
     fn move_to(&mut self, point: &Point);
-    fn draw_tooltip(&self, ctx: &egui::Context);
     fn update(&mut self);
     fn get_position(&self) -> &Point;
     fn is_point_inside(&self, point: &Point) -> bool;
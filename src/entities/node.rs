@@ -1,63 +1,245 @@
 use std::hash::{Hash, Hasher};
 use std::cmp::{Eq, PartialEq};
-use std::borrow::Borrow;
-use std::net::IpAddr;
-use crate::math::{Point, convert_bytes_str};
-use crate::data::IPPacketInfo;
-use crate::data::NodeData;
+use crate::math::Point;
+use crate::data::{GeoInfo, HostDiscovery, IPPacketInfo, MacChange, OuiTable};
+use crate::data::{NodeData, NodeKey};
+use crate::engine::{Color, Renderer, GREEN, ORANGE, RED, WHITE, YELLOW};
 use crate::entities::Entity;
-use macroquad::prelude::*;
 
 const DEFAULT_RADIUS: f32 = 10.0;
+const DIMMED_ALPHA: f32 = 0.15;
+const PIN_RADIUS: f32 = 2.5;
+const LABEL_SIZE: f32 = 14.0;
+const LABEL_MARGIN: f32 = 4.0;
+const VENDOR_SIZE: f32 = 12.0;
+const VENDOR_ALPHA: f32 = 0.6;
+// Multicast and broadcast nodes drawn as a bus
+const BUS_HALF_LENGTH: f32 = 40.0;
+const BUS_THICKNESS: f32 = 6.0;
+// Frames a node flashes for after an alert, the ring blinks every FLASH_PERIOD frames
+const FLASH_FRAMES: u32 = 180;
+const FLASH_PERIOD: u32 = 15;
+const FLASH_MARGIN: f32 = 6.0;
+// New nodes have a halo and a ring moving away from them as they age
+const GLOW_MARGIN: f32 = 8.0;
+const GLOW_ALPHA: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchState{
+    None,
+    Match,
+    Focused,
+    Dimmed,
+}
 
 pub struct Node{
     data: NodeData,
     pos: Point,
     radius: f32,
+    search_state: SearchState,
+    // Pinned nodes can't be dragged
+    pinned: bool,
+    // Hostnames of the node, best first
+    names: Vec<String>,
+    // Color of the category the node belongs to
+    color: Color,
+    // Drawn as a bar, for the multicast and broadcast nodes
+    bus: bool,
+    // Frames left to flash
+    flash: u32,
+    // 1 for a node just seen for the first time, down to 0 when it is no longer new
+    glow: f32,
 }
 
 impl Node{
-    pub fn new(ip: IpAddr, pos: &Point) -> Node{
+    pub fn new(key: NodeKey, pos: &Point) -> Node{
+        Node::from_data(NodeData::from_key(key), pos, false)
+    }
+
+    pub fn from_data(data: NodeData, pos: &Point, pinned: bool) -> Node{
         Node{
-            data: NodeData::new(ip),
+            data,
             pos: *pos,
             radius: DEFAULT_RADIUS,
+            search_state: SearchState::None,
+            pinned,
+            names: Vec::new(),
+            color: YELLOW,
+            bus: false,
+            flash: 0,
+            glow: 0.0,
         }
     }
     pub fn add_packet(&mut self, packet: &IPPacketInfo){
         self.data.add_packet(packet);
     }
-}
 
-impl Entity for Node {
+    pub fn add_discovery(&mut self, discovery: &HostDiscovery) -> Option<MacChange>{
+        self.data.add_discovery(discovery)
+    }
+
+    pub fn set_geo(&mut self, geo: Option<GeoInfo>){
+        self.data.geo = geo;
+    }
+
+    pub fn refresh_vendor(&mut self, oui: &OuiTable) -> bool{
+        self.data.refresh_vendor(oui)
+    }
+
+    pub fn set_local(&mut self, local: bool){
+        self.data.local = local;
+    }
+
+    pub fn set_mac_node(&mut self, mac_node: bool){
+        self.data.mac_node = mac_node;
+    }
+
+    pub fn get_data(&self) -> &NodeData{
+        &self.data
+    }
+
+    pub fn get_search_state(&self) -> SearchState{
+        self.search_state
+    }
+
+    pub fn set_search_state(&mut self, state: SearchState){
+        self.search_state = state;
+    }
+
+    pub fn is_pinned(&self) -> bool{
+        self.pinned
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool){
+        self.pinned = pinned;
+    }
+
+    pub fn get_names(&self) -> &Vec<String>{
+        &self.names
+    }
+
+    pub fn set_names(&mut self, names: Vec<String>){
+        self.names = names;
+    }
+
+    pub fn get_color(&self) -> Color{
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color){
+        self.color = color;
+    }
 
-    fn draw_tooltip(&self, ctx: &egui::Context){
-        egui::show_tooltip_at_pointer(ctx, egui::Id::new("my_tooltip"), |ui| {
-                ui.label(format!("IP: {:?}", self.data.ip));
-                ui.label(format!("Number of packets received: {:?}", self.data.stats.packet_count_recv));
-                ui.label(format!("Data received: {}", convert_bytes_str(self.data.stats.packet_bytes_recv)));
-                ui.label(format!("Number of packets sent: {:?}", self.data.stats.packet_count_sent));
-                ui.label(format!("Data sent: {}", convert_bytes_str(self.data.stats.packet_bytes_sent)));
-        });
+    pub fn is_bus(&self) -> bool{
+        self.bus
     }
 
+    pub fn set_bus(&mut self, bus: bool){
+        self.bus = bus;
+    }
+
+    // Blinks a red ring around the node for a few seconds
+    pub fn flash(&mut self){
+        self.flash = FLASH_FRAMES;
+    }
+
+    pub fn is_flashing(&self) -> bool{
+        self.flash > 0
+    }
+
+    pub fn get_glow(&self) -> f32{
+        self.glow
+    }
+
+    pub fn set_glow(&mut self, glow: f32){
+        self.glow = glow.clamp(0.0, 1.0);
+    }
+
+    // Where the packets coming from `from` end, the point of the bus under them for a bus
+    pub fn get_landing_point(&self, from: &Point) -> Point{
+        if !self.bus{
+            return self.pos;
+        }
+        Point{x: from.x.clamp(self.pos.x - BUS_HALF_LENGTH, self.pos.x + BUS_HALF_LENGTH), y: self.pos.y}
+    }
+
+    fn draw_shape(&self, renderer: &mut dyn Renderer, pos: &Point, color: Color){
+        if self.bus{
+            let from = Point{x: pos.x - BUS_HALF_LENGTH, y: pos.y};
+            let to = Point{x: pos.x + BUS_HALF_LENGTH, y: pos.y};
+            renderer.draw_line(&from, &to, BUS_THICKNESS, color);
+        }else{
+            renderer.draw_circle(pos, self.radius, color);
+        }
+    }
+
+    // Best hostname of the node, or the first server name clients asked it for
+    pub fn get_label(&self) -> Option<&str>{
+        self.names.first().or(self.data.server_names.first()).map(|name| name.as_str())
+    }
+
+    // Hostname and vendor under the node, drawn separately so they can be turned off
+    pub fn draw_label(&self, renderer: &mut dyn Renderer, camera: &Point){
+        let pos = self.pos.relative_to(camera);
+        let color = if self.search_state == SearchState::Dimmed {WHITE.with_alpha(DIMMED_ALPHA)} else {WHITE};
+        let mut label_pos = Point{x: pos.x, y: pos.y + self.radius + LABEL_MARGIN};
+        if let Some(label) = self.get_label(){
+            renderer.draw_text(label, &label_pos, LABEL_SIZE, color);
+            label_pos.y += LABEL_SIZE;
+        }
+        if let Some(vendor) = &self.data.vendor{
+            renderer.draw_text(vendor, &label_pos, VENDOR_SIZE, color.with_alpha(color.a * VENDOR_ALPHA));
+        }
+    }
+}
+
+impl Entity for Node {
+
     fn move_to(&mut self, point: &Point){
         self.pos = *point;
     }
 
     fn update(&mut self) {
-        
+        self.flash = self.flash.saturating_sub(1);
     }
 
     fn get_position(&self) -> &Point{
         &self.pos
     }
 
-    fn draw(&self){
-        draw_circle(self.pos.x, self.pos.y, self.radius, YELLOW);
+    fn draw(&self, renderer: &mut dyn Renderer, camera: &Point){
+        let pos = self.pos.relative_to(camera);
+        if self.glow > 0.0 && self.search_state != SearchState::Dimmed{
+            let radius = if self.bus {BUS_HALF_LENGTH} else {self.radius};
+            renderer.draw_circle(&pos, radius + GLOW_MARGIN, GREEN.with_alpha(GLOW_ALPHA * self.glow));
+            renderer.draw_circle_lines(&pos, radius + GLOW_MARGIN * (2.0 - self.glow), 2.0, GREEN.with_alpha(self.glow));
+        }
+        match self.search_state {
+            SearchState::None => self.draw_shape(renderer, &pos, self.color),
+            SearchState::Dimmed => self.draw_shape(renderer, &pos, self.color.with_alpha(DIMMED_ALPHA)),
+            SearchState::Match => {
+                self.draw_shape(renderer, &pos, ORANGE);
+                renderer.draw_circle_lines(&pos, self.radius + 3.0, 2.0, ORANGE);
+            }
+            SearchState::Focused => {
+                self.draw_shape(renderer, &pos, ORANGE);
+                renderer.draw_circle_lines(&pos, self.radius + 3.0, 2.0, WHITE);
+                renderer.draw_circle_lines(&pos, self.radius + 7.0, 1.0, WHITE);
+            }
+        }
+        if self.pinned{
+            renderer.draw_circle(&pos, PIN_RADIUS, WHITE);
+        }
+        if self.flash > 0 && (self.flash / FLASH_PERIOD).is_multiple_of(2){
+            let radius = if self.bus {BUS_HALF_LENGTH} else {self.radius};
+            renderer.draw_circle_lines(&pos, radius + FLASH_MARGIN, 3.0, RED);
+        }
     }
 
     fn is_point_inside(&self, point: &Point) -> bool{
+        if self.bus{
+            return (point.x - self.pos.x).abs() < BUS_HALF_LENGTH && (point.y - self.pos.y).abs() < self.radius;
+        }
         let distance = self.pos.distance(point);
         if distance < self.radius{
             return true;
@@ -65,7 +247,7 @@ impl Entity for Node {
         return false;
     }
 
-    fn draw_debug(&self){
+    fn draw_debug(&self, _: &mut dyn Renderer, _: &Point){
 
     }
 
@@ -73,13 +255,13 @@ impl Entity for Node {
 
 impl PartialEq for Node{
     fn eq(&self, other: &Self) -> bool {
-        &self.data.ip == &other.data.ip
+        self.data.key() == other.data.key()
     }
 }
 
-impl PartialEq<IpAddr> for Node{
-    fn eq(&self, other: &IpAddr) -> bool {
-        &self.data.ip == other
+impl PartialEq<NodeKey> for Node{
+    fn eq(&self, other: &NodeKey) -> bool {
+        self.data.key() == *other
     }
 }
 
@@ -87,12 +269,6 @@ impl Eq for Node{}
 
 impl Hash for Node{
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.data.ip.hash(state);
-    }
-}
-
-impl Borrow<IpAddr> for Node {
-    fn borrow(&self) -> &IpAddr {
-        &self.data.ip
+        self.data.key().hash(state);
     }
 }
\ No newline at end of file
@@ -1,8 +1,17 @@
-use std::net::IpAddr;
 use crate::math::{Point,Vector};
 use crate::entities::Entity;
-use crate::data::{PacketData, IPPacketInfo};
-use macroquad::prelude::*;
+use crate::data::{NodeKey, PacketData, PacketDirection};
+use crate::engine::{Renderer, BLUE, GREEN, RED};
+
+const DIMMED_ALPHA: f32 = 0.15;
+
+// Quadratic curve followed by packets on the world map
+struct Arc{
+    control: Point,
+    // Position on the curve, from 0 at the source to 1 at the destination
+    progress: f32,
+    step: f32,
+}
 
 pub struct PacketEntity{
     src: Point,
@@ -10,31 +19,45 @@ pub struct PacketEntity{
     dest: Point,
     velocity: Vector,
     packet_data: PacketData,
+    dimmed: bool,
+    arc: Option<Arc>,
 }
 
 impl PacketEntity{
-    pub fn new(packet: &IPPacketInfo, src_point: &Point, dst_point: &Point, start_velocity: f32, start_angle: f32) -> Self{
+    // `start_angle` is the deviation from the straight line between source and destination
+    pub fn new(packet_data: PacketData, src_point: &Point, dst_point: &Point, start_velocity: f32, start_angle: f32) -> Self{
         let unit_vector = src_point.get_unit_vector(dst_point);
-        let rotated_vector = unit_vector.rotate(rand::gen_range(-start_angle,start_angle));
+        let rotated_vector = unit_vector.rotate(start_angle);
         let force_multiplier: f32 = start_velocity;
 
-        PacketData::new(packet);
-
         PacketEntity{
             src: Point{x:src_point.x,y:src_point.y},
             pos: Point{x:src_point.x,y:src_point.y},
             dest: Point{x:dst_point.x,y:dst_point.y},
             velocity: &rotated_vector * force_multiplier,
-            packet_data: PacketData::new(packet),
+            packet_data,
+            dimmed: false,
+            arc: None,
         }
     }
 
-    pub fn get_ip_source(&self) -> &IpAddr{
+    // Packet moving at `speed` along the curve from the source to the destination bent towards `control`
+    pub fn with_arc(packet_data: PacketData, src_point: &Point, control: &Point, dst_point: &Point, speed: f32) -> Self{
+        let length = src_point.distance(control) + control.distance(dst_point);
+        let mut packet_entity = PacketEntity::new(packet_data, src_point, dst_point, speed, 0.0);
+        packet_entity.arc = Some(Arc{control: *control, progress: 0.0, step: speed / length.max(1.0)});
+        packet_entity
+    }
+
+    pub fn get_source_node(&self) -> &NodeKey{
         &self.packet_data.source
     }
-    pub fn get_ip_dest(&self) -> &IpAddr{
+    pub fn get_dest_node(&self) -> &NodeKey{
         &self.packet_data.dest
     }
+    pub fn get_direction(&self) -> PacketDirection{
+        self.packet_data.direction
+    }
     pub fn get_source(&self) -> &Point{
         &self.src
     }
@@ -45,31 +68,48 @@ impl PacketEntity{
     pub fn set_destination(&mut self, point: &Point){
         self.dest = *point;
     }
+
+    pub fn set_dimmed(&mut self, dimmed: bool){
+        self.dimmed = dimmed;
+    }
 }
 
 impl Entity for PacketEntity{
-    fn draw(&self){
-        draw_circle(self.pos.x, self.pos.y, 1.5, RED);
+    fn draw(&self, renderer: &mut dyn Renderer, camera: &Point){
+        let pos = self.pos.relative_to(camera);
+        // Transit packets keep the color they had before the local host was known
+        let color = match self.packet_data.direction {
+            PacketDirection::Inbound => GREEN,
+            PacketDirection::Outbound => BLUE,
+            PacketDirection::Transit => RED,
+        };
+        let color = if self.dimmed {color.with_alpha(DIMMED_ALPHA)} else {color};
+        renderer.draw_circle(&pos, 1.5, color);
     }
 
     fn get_position(&self) -> &Point {
         &self.pos
     }
 
-    fn draw_tooltip(&self, _: &egui::Context) {
-        
-    }
-
     fn move_to(&mut self, point: &Point) {
         self.pos = *point;
     }
 
-    fn draw_debug(&self){
-        draw_line(self.pos.x, self.pos.y, self.dest.x, self.dest.y, 1.0, BLUE);
-        draw_line(self.pos.x, self.pos.y, self.pos.x+(self.velocity.x*10.0), self.pos.y+(self.velocity.y*10.0), 3.0, RED);
-        draw_line(self.src.x, self.src.y, self.pos.x, self.pos.y, 1.0, GREEN);
+    fn draw_debug(&self, renderer: &mut dyn Renderer, camera: &Point){
+        let pos = self.pos.relative_to(camera);
+        let src = self.src.relative_to(camera);
+        let dest = self.dest.relative_to(camera);
+        let velocity_end = Point{x: pos.x+(self.velocity.x*10.0), y: pos.y+(self.velocity.y*10.0)};
+        renderer.draw_line(&pos, &dest, 1.0, BLUE);
+        renderer.draw_line(&pos, &velocity_end, 3.0, RED);
+        renderer.draw_line(&src, &pos, 1.0, GREEN);
     }
     fn update(&mut self){
+        if let Some(arc) = &mut self.arc{
+            arc.progress = (arc.progress + arc.step).min(1.0);
+            self.pos.set_bazier_next_point(&self.src, &arc.control, &self.dest, arc.progress);
+            return;
+        }
         let vec_to_dest_unit = self.pos.get_unit_vector(&self.dest);
         let vec_velocity_unit = self.velocity.get_unit_vector();
         let dist_to_dest = self.pos.distance(&self.dest);
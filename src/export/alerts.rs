@@ -0,0 +1,61 @@
+use std::io::{self, Write};
+use std::net::IpAddr;
+use serde::Serialize;
+use crate::data::{Alert, AlertKind};
+use crate::export::stats::{csv_field, vlan_column, write_rows};
+use crate::export::StatsFormat;
+use crate::math::convert_time_str;
+
+// One line per alert, in order of detection
+#[derive(Serialize)]
+struct AlertRow{
+    // UTC, RFC 3339
+    timestamp: String,
+    kind: AlertKind,
+    source: IpAddr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vlan: Option<u16>,
+    target: Option<IpAddr>,
+    port: Option<u16>,
+    protocol: u8,
+    count: usize,
+    // Condition of the user rule that raised the alert
+    rule: Option<String>,
+}
+
+impl AlertRow{
+    fn new(alert: &Alert) -> AlertRow{
+        AlertRow{
+            timestamp: convert_time_str(alert.timestamp),
+            kind: alert.kind,
+            source: alert.source.ip,
+            vlan: alert.source.vlan,
+            target: alert.target,
+            port: alert.port,
+            protocol: alert.protocol,
+            count: alert.count,
+            rule: alert.rule.clone(),
+        }
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String{
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+pub fn write_alerts<W: Write>(writer: &mut W, format: StatsFormat, alerts: &[Alert]) -> io::Result<()>{
+    let rows = alerts.iter().map(AlertRow::new);
+    if alerts.iter().any(|alert| alert.source.vlan.is_some()){
+        return write_rows(writer, format, "timestamp,kind,source,vlan,target,port,protocol,count,rule", rows, |row| {
+            format!("{},{},{}{},{},{},{},{},{}", row.timestamp, row.kind, row.source, vlan_column(row.vlan), optional(row.target), optional(row.port), row.protocol, row.count, csv_field(&optional(row.rule.as_ref())))
+        });
+    }
+    write_rows(writer, format, "timestamp,kind,source,target,port,protocol,count,rule", rows, |row| {
+        format!("{},{},{},{},{},{},{},{}", row.timestamp, row.kind, row.source, optional(row.target), optional(row.port), row.protocol, row.count, csv_field(&optional(row.rule.as_ref())))
+    })
+}
+
+// Same fields as a JSONL line, used as the body of the webhooks
+pub fn alert_json(alert: &Alert) -> serde_json::Value{
+    serde_json::to_value(AlertRow::new(alert)).unwrap_or_default()
+}
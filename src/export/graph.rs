@@ -0,0 +1,217 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::export::{escape_xml, ExportNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat{
+    GraphML,
+    Gexf,
+    Dot,
+}
+
+impl GraphFormat{
+    pub const ALL: [GraphFormat; 3] = [GraphFormat::GraphML, GraphFormat::Gexf, GraphFormat::Dot];
+
+    pub fn extension(&self) -> &'static str{
+        match self {
+            GraphFormat::GraphML => "graphml",
+            GraphFormat::Gexf => "gexf",
+            GraphFormat::Dot => "dot",
+        }
+    }
+}
+
+impl FromStr for GraphFormat{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "graphml" => Ok(GraphFormat::GraphML),
+            "gexf" => Ok(GraphFormat::Gexf),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => Err(format!("Unknown graph format '{}', expected 'graphml', 'gexf' or 'dot'", s)),
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+pub fn write_graph<W: Write>(writer: &mut W, format: GraphFormat, nodes: &[ExportNode]) -> io::Result<()>{
+    match format {
+        GraphFormat::GraphML => write_graphml(writer, nodes),
+        GraphFormat::Gexf => write_gexf(writer, nodes),
+        GraphFormat::Dot => write_dot(writer, nodes),
+    }
+}
+
+// Optional node attributes, with their type, only written when known
+const NODE_ATTRIBUTES: [(&str, &str); 9] = [
+    ("names", "string"),
+    ("server_names", "string"),
+    ("mac", "string"),
+    ("vendor", "string"),
+    ("country", "string"),
+    ("city", "string"),
+    ("as", "string"),
+    ("class", "string"),
+    ("local", "boolean"),
+];
+
+fn node_attributes(node: &ExportNode) -> Vec<(&'static str, String)>{
+    let data = node.data;
+    let geo = data.geo.as_ref();
+    let joined = |values: &[String]| Some(values.join(", ")).filter(|value| !value.is_empty());
+    let values = [
+        joined(node.names),
+        joined(&data.server_names),
+        data.current_mac().map(|mac| mac.to_string()),
+        data.vendor.clone(),
+        geo.and_then(|geo| geo.country.clone()),
+        geo.and_then(|geo| geo.city.clone()),
+        geo.and_then(|geo| geo.as_label()),
+        // The nodes of the MAC view have no address
+        Some(data.class().to_string()).filter(|_| !data.mac_node),
+        Some(data.local.to_string()),
+    ];
+    NODE_ATTRIBUTES.iter().zip(values).filter_map(|((name, _), value)| Some((*name, value?))).collect()
+}
+
+// Quotes and backslashes end or escape the DOT strings
+fn escape_dot(text: &str) -> String{
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Every pair of nodes that exchanged packets, from the sender side
+fn edges<'a>(nodes: &'a [ExportNode]) -> impl Iterator<Item = (String, String, u32, u64)> + 'a{
+    nodes.iter().flat_map(|node| {
+        node.data.sent_to.iter().map(move |(dest, stats)| {
+            // Peers are on the VLAN of the node
+            (node.data.node_id(&node.data.ip), node.data.node_id(dest), stats.packet_count_sent, stats.packet_bytes_sent)
+        })
+    })
+}
+
+pub fn write_graphml<W: Write>(writer: &mut W, nodes: &[ExportNode]) -> io::Result<()>{
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(writer, r#"  <key id="packets_sent" for="node" attr.name="packets_sent" attr.type="long"/>"#)?;
+    writeln!(writer, r#"  <key id="bytes_sent" for="node" attr.name="bytes_sent" attr.type="long"/>"#)?;
+    writeln!(writer, r#"  <key id="packets_recv" for="node" attr.name="packets_recv" attr.type="long"/>"#)?;
+    writeln!(writer, r#"  <key id="bytes_recv" for="node" attr.name="bytes_recv" attr.type="long"/>"#)?;
+    for (name, kind) in NODE_ATTRIBUTES{
+        writeln!(writer, r#"  <key id="{}" for="node" attr.name="{}" attr.type="{}"/>"#, name, name, kind)?;
+    }
+    writeln!(writer, r#"  <key id="x" for="node" attr.name="x" attr.type="float"/>"#)?;
+    writeln!(writer, r#"  <key id="y" for="node" attr.name="y" attr.type="float"/>"#)?;
+    writeln!(writer, r#"  <key id="packets" for="edge" attr.name="packets" attr.type="long"/>"#)?;
+    writeln!(writer, r#"  <key id="bytes" for="edge" attr.name="bytes" attr.type="long"/>"#)?;
+    writeln!(writer, r#"  <key id="weight" for="edge" attr.name="weight" attr.type="double"/>"#)?;
+    writeln!(writer, r#"  <graph id="network" edgedefault="directed">"#)?;
+    for node in nodes{
+        let stats = &node.data.stats;
+        writeln!(writer, r#"    <node id="{}">"#, escape_xml(&node.data.node_id(&node.data.ip)))?;
+        writeln!(writer, r#"      <data key="packets_sent">{}</data>"#, stats.packet_count_sent)?;
+        writeln!(writer, r#"      <data key="bytes_sent">{}</data>"#, stats.packet_bytes_sent)?;
+        writeln!(writer, r#"      <data key="packets_recv">{}</data>"#, stats.packet_count_recv)?;
+        writeln!(writer, r#"      <data key="bytes_recv">{}</data>"#, stats.packet_bytes_recv)?;
+        for (name, value) in node_attributes(node){
+            writeln!(writer, r#"      <data key="{}">{}</data>"#, name, escape_xml(&value))?;
+        }
+        if let Some(position) = &node.position{
+            writeln!(writer, r#"      <data key="x">{}</data>"#, position.x)?;
+            writeln!(writer, r#"      <data key="y">{}</data>"#, position.y)?;
+        }
+        writeln!(writer, "    </node>")?;
+    }
+    for (source, dest, packets, bytes) in edges(nodes){
+        writeln!(writer, r#"    <edge source="{}" target="{}">"#, escape_xml(&source), escape_xml(&dest))?;
+        writeln!(writer, r#"      <data key="packets">{}</data>"#, packets)?;
+        writeln!(writer, r#"      <data key="bytes">{}</data>"#, bytes)?;
+        writeln!(writer, r#"      <data key="weight">{}</data>"#, packets)?;
+        writeln!(writer, "    </edge>")?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")
+}
+
+// The y axis is flipped in GEXF and DOT, where it points up instead of down like on the screen
+pub fn write_gexf<W: Write>(writer: &mut W, nodes: &[ExportNode]) -> io::Result<()>{
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<gexf xmlns="http://gexf.net/1.2" xmlns:viz="http://gexf.net/1.2/viz" version="1.2">"#)?;
+    writeln!(writer, r#"  <graph defaultedgetype="directed">"#)?;
+    writeln!(writer, r#"    <attributes class="node">"#)?;
+    writeln!(writer, r#"      <attribute id="packets_sent" title="packets_sent" type="long"/>"#)?;
+    writeln!(writer, r#"      <attribute id="bytes_sent" title="bytes_sent" type="long"/>"#)?;
+    writeln!(writer, r#"      <attribute id="packets_recv" title="packets_recv" type="long"/>"#)?;
+    writeln!(writer, r#"      <attribute id="bytes_recv" title="bytes_recv" type="long"/>"#)?;
+    for (name, kind) in NODE_ATTRIBUTES{
+        writeln!(writer, r#"      <attribute id="{}" title="{}" type="{}"/>"#, name, name, kind)?;
+    }
+    writeln!(writer, "    </attributes>")?;
+    writeln!(writer, r#"    <attributes class="edge">"#)?;
+    writeln!(writer, r#"      <attribute id="packets" title="packets" type="long"/>"#)?;
+    writeln!(writer, r#"      <attribute id="bytes" title="bytes" type="long"/>"#)?;
+    writeln!(writer, "    </attributes>")?;
+    writeln!(writer, "    <nodes>")?;
+    for node in nodes{
+        let stats = &node.data.stats;
+        let id = escape_xml(&node.data.node_id(&node.data.ip));
+        writeln!(writer, r#"      <node id="{}" label="{}">"#, id, id)?;
+        writeln!(writer, "        <attvalues>")?;
+        writeln!(writer, r#"          <attvalue for="packets_sent" value="{}"/>"#, stats.packet_count_sent)?;
+        writeln!(writer, r#"          <attvalue for="bytes_sent" value="{}"/>"#, stats.packet_bytes_sent)?;
+        writeln!(writer, r#"          <attvalue for="packets_recv" value="{}"/>"#, stats.packet_count_recv)?;
+        writeln!(writer, r#"          <attvalue for="bytes_recv" value="{}"/>"#, stats.packet_bytes_recv)?;
+        for (name, value) in node_attributes(node){
+            writeln!(writer, r#"          <attvalue for="{}" value="{}"/>"#, name, escape_xml(&value))?;
+        }
+        writeln!(writer, "        </attvalues>")?;
+        if let Some(position) = &node.position{
+            writeln!(writer, r#"        <viz:position x="{}" y="{}" z="0.0"/>"#, position.x, -position.y)?;
+        }
+        writeln!(writer, "      </node>")?;
+    }
+    writeln!(writer, "    </nodes>")?;
+    writeln!(writer, "    <edges>")?;
+    for (id, (source, dest, packets, bytes)) in edges(nodes).enumerate(){
+        writeln!(writer, r#"      <edge id="{}" source="{}" target="{}" weight="{}">"#, id, escape_xml(&source), escape_xml(&dest), packets)?;
+        writeln!(writer, "        <attvalues>")?;
+        writeln!(writer, r#"          <attvalue for="packets" value="{}"/>"#, packets)?;
+        writeln!(writer, r#"          <attvalue for="bytes" value="{}"/>"#, bytes)?;
+        writeln!(writer, "        </attvalues>")?;
+        writeln!(writer, "      </edge>")?;
+    }
+    writeln!(writer, "    </edges>")?;
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</gexf>")
+}
+
+pub fn write_dot<W: Write>(writer: &mut W, nodes: &[ExportNode]) -> io::Result<()>{
+    writeln!(writer, "digraph network {{")?;
+    for node in nodes{
+        let stats = &node.data.stats;
+        write!(
+            writer,
+            r#"  "{}" [packets_sent={}, bytes_sent={}, packets_recv={}, bytes_recv={}"#,
+            escape_dot(&node.data.node_id(&node.data.ip)), stats.packet_count_sent, stats.packet_bytes_sent, stats.packet_count_recv, stats.packet_bytes_recv,
+        )?;
+        for (name, value) in node_attributes(node){
+            write!(writer, r#", {}="{}""#, name, escape_dot(&value))?;
+        }
+        if let Some(position) = &node.position{
+            write!(writer, r#", pos="{},{}!""#, position.x, -position.y)?;
+        }
+        writeln!(writer, "];")?;
+    }
+    for (source, dest, packets, bytes) in edges(nodes){
+        writeln!(writer, r#"  "{}" -> "{}" [packets={}, bytes={}, weight={}];"#, escape_dot(&source), escape_dot(&dest), packets, bytes, packets)?;
+    }
+    writeln!(writer, "}}")
+}
@@ -0,0 +1,76 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::data::{Alert, NodeData};
+use crate::math::Point;
+
+mod alerts;
+mod graph;
+mod stats;
+
+pub use alerts::{alert_json, write_alerts};
+pub use graph::{GraphFormat, write_graph, write_graphml, write_gexf, write_dot};
+pub use stats::{StatsFormat, write_node_stats, write_pair_stats};
+
+pub const GRAPH_FILE_NAME: &str = "graph";
+pub const NODE_STATS_FILE_NAME: &str = "nodes";
+pub const PAIR_STATS_FILE_NAME: &str = "pairs";
+pub const ALERTS_FILE_NAME: &str = "alerts";
+
+// Node given to the exporters, the position is only known when the graph was drawn
+pub struct ExportNode<'a>{
+    pub data: &'a NodeData,
+    pub position: Option<Point>,
+    // Hostnames, best first, the headless mode doesn't resolve them
+    pub names: &'a [String],
+}
+
+// Writes the graph into `dir`, creating it if needed, and returns the path of the file
+pub fn write_graph_to_dir(dir: &Path, format: GraphFormat, nodes: &[ExportNode]) -> io::Result<PathBuf>{
+    fs::create_dir_all(dir)?;
+    let path = dir.join(GRAPH_FILE_NAME).with_extension(format.extension());
+    let mut writer = BufWriter::new(File::create(&path)?);
+    write_graph(&mut writer, format, nodes)?;
+    writer.flush()?;
+    Ok(path)
+}
+
+// Writes the node and pair statistics into `dir`, creating it if needed, and returns the paths of the files
+pub fn write_stats_to_dir(dir: &Path, format: StatsFormat, nodes: &[NodeData]) -> io::Result<Vec<PathBuf>>{
+    fs::create_dir_all(dir)?;
+    let node_path = dir.join(NODE_STATS_FILE_NAME).with_extension(format.extension());
+    let mut writer = BufWriter::new(File::create(&node_path)?);
+    write_node_stats(&mut writer, format, nodes)?;
+    writer.flush()?;
+
+    let pair_path = dir.join(PAIR_STATS_FILE_NAME).with_extension(format.extension());
+    let mut writer = BufWriter::new(File::create(&pair_path)?);
+    write_pair_stats(&mut writer, format, nodes)?;
+    writer.flush()?;
+    Ok(vec![node_path, pair_path])
+}
+
+// Writes the alerts into `dir`, creating it if needed, and returns the path of the file
+pub fn write_alerts_to_dir(dir: &Path, format: StatsFormat, alerts: &[Alert]) -> io::Result<PathBuf>{
+    fs::create_dir_all(dir)?;
+    let path = dir.join(ALERTS_FILE_NAME).with_extension(format.extension());
+    let mut writer = BufWriter::new(File::create(&path)?);
+    write_alerts(&mut writer, format, alerts)?;
+    writer.flush()?;
+    Ok(path)
+}
+
+pub(crate) fn escape_xml(text: &str) -> String{
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars(){
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::data::NodeData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsFormat{
+    Csv,
+    Jsonl,
+}
+
+impl StatsFormat{
+    pub const ALL: [StatsFormat; 2] = [StatsFormat::Csv, StatsFormat::Jsonl];
+
+    pub fn extension(&self) -> &'static str{
+        match self {
+            StatsFormat::Csv => "csv",
+            StatsFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+impl FromStr for StatsFormat{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(StatsFormat::Csv),
+            "jsonl" => Ok(StatsFormat::Jsonl),
+            _ => Err(format!("Unknown statistics format '{}', expected 'csv' or 'jsonl'", s)),
+        }
+    }
+}
+
+impl fmt::Display for StatsFormat{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+// One line per node
+#[derive(Serialize)]
+struct NodeRow{
+    // The MAC address for the nodes of the MAC view
+    ip: String,
+    // Only set when nodes are unique per VLAN
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vlan: Option<u16>,
+    packets_sent: u32,
+    bytes_sent: u64,
+    packets_recv: u32,
+    bytes_recv: u64,
+}
+
+// One line per (source, dest) pair, from the sender side
+#[derive(Serialize)]
+struct PairRow{
+    source: String,
+    dest: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vlan: Option<u16>,
+    packets: u32,
+    bytes: u64,
+}
+
+pub(super) fn write_rows<W: Write, R: Serialize>(writer: &mut W, format: StatsFormat, header: &str, rows: impl Iterator<Item = R>, csv_line: impl Fn(&R) -> String) -> io::Result<()>{
+    match format {
+        StatsFormat::Csv => {
+            writeln!(writer, "{}", header)?;
+            for row in rows{
+                writeln!(writer, "{}", csv_line(&row))?;
+            }
+        }
+        StatsFormat::Jsonl => {
+            for row in rows{
+                serde_json::to_writer(&mut *writer, &row)?;
+                writeln!(writer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Nodes are sorted by IP so that exports of the same capture can be compared
+fn sorted(nodes: &[NodeData]) -> Vec<&NodeData>{
+    let mut nodes: Vec<&NodeData> = nodes.iter().collect();
+    nodes.sort_by_key(|node| node.key());
+    nodes
+}
+
+// The CSV files get a vlan column when nodes are unique per VLAN
+fn has_vlans(nodes: &[NodeData]) -> bool{
+    nodes.iter().any(|node| node.vlan.is_some())
+}
+
+// Fields with a separator, a quote or a line break are quoted, their quotes doubled
+pub(super) fn csv_field(text: &str) -> String{
+    if text.contains([',', '"', '\n', '\r']){
+        return format!("\"{}\"", text.replace('"', "\"\""));
+    }
+    text.to_string()
+}
+
+pub(super) fn vlan_column(vlan: Option<u16>) -> String{
+    vlan.map(|vlan| format!(",{}", vlan)).unwrap_or_else(|| ",".to_string())
+}
+
+pub fn write_node_stats<W: Write>(writer: &mut W, format: StatsFormat, nodes: &[NodeData]) -> io::Result<()>{
+    let rows = sorted(nodes).into_iter().map(|node| NodeRow{
+        ip: node.address(&node.ip),
+        vlan: node.vlan,
+        packets_sent: node.stats.packet_count_sent,
+        bytes_sent: node.stats.packet_bytes_sent,
+        packets_recv: node.stats.packet_count_recv,
+        bytes_recv: node.stats.packet_bytes_recv,
+    });
+    if has_vlans(nodes){
+        return write_rows(writer, format, "ip,vlan,packets_sent,bytes_sent,packets_recv,bytes_recv", rows, |row| {
+            format!("{}{},{},{},{},{}", csv_field(&row.ip), vlan_column(row.vlan), row.packets_sent, row.bytes_sent, row.packets_recv, row.bytes_recv)
+        });
+    }
+    write_rows(writer, format, "ip,packets_sent,bytes_sent,packets_recv,bytes_recv", rows, |row| {
+        format!("{},{},{},{},{}", csv_field(&row.ip), row.packets_sent, row.bytes_sent, row.packets_recv, row.bytes_recv)
+    })
+}
+
+pub fn write_pair_stats<W: Write>(writer: &mut W, format: StatsFormat, nodes: &[NodeData]) -> io::Result<()>{
+    let rows = sorted(nodes).into_iter().flat_map(|node| {
+        let mut peers: Vec<_> = node.sent_to.iter().collect();
+        peers.sort_by_key(|(dest, _)| **dest);
+        peers.into_iter().map(|(dest, stats)| PairRow{
+            source: node.address(&node.ip),
+            dest: node.address(dest),
+            vlan: node.vlan,
+            packets: stats.packet_count_sent,
+            bytes: stats.packet_bytes_sent,
+        }).collect::<Vec<_>>()
+    });
+    if has_vlans(nodes){
+        return write_rows(writer, format, "source,dest,vlan,packets,bytes", rows, |row| {
+            format!("{},{}{},{},{}", csv_field(&row.source), csv_field(&row.dest), vlan_column(row.vlan), row.packets, row.bytes)
+        });
+    }
+    write_rows(writer, format, "source,dest,packets,bytes", rows, |row| {
+        format!("{},{},{},{}", csv_field(&row.source), csv_field(&row.dest), row.packets, row.bytes)
+    })
+}
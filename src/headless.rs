@@ -0,0 +1,118 @@
+use crate::cli::{self, Args};
+use crate::data::{Alert, HostDiscovery, IPPacketInfo, TrafficGraph};
+use crate::math::convert_time_str;
+use crate::notifier::AlertNotifier;
+use crate::capture::NetworkManager;
+use crate::report;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SUMMARY_INTERVAL: u64 = 10;
+const DEFAULT_TOP: usize = 10;
+const RECV_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Captures packets and aggregates statistics without opening a window,
+// until the duration expires, the capture file ends or Ctrl-C is pressed
+pub fn run(args: &Args) -> Result<(), String> {
+    let (tx, rx) = channel::<IPPacketInfo>();
+    let (discovery_tx, discovery_rx) = channel::<HostDiscovery>();
+    let mut network_manager = NetworkManager::new(tx);
+    network_manager.set_discovery_sender(discovery_tx);
+    network_manager.set_decapsulate(args.decapsulate);
+    if !cli::open_capture(args, &mut network_manager)? {
+        return Err("Headless mode requires --interface or --pcap-file".to_string());
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .map_err(|e| format!("Could not set Ctrl-C handler: {}", e))?;
+
+    let local_addresses = cli::local_addresses(args, &network_manager);
+    let capture_running = running.clone();
+    thread::spawn(move || {
+        while !network_manager.is_finished() && capture_running.load(Ordering::SeqCst) {
+            network_manager.listen_packets();
+        }
+    });
+
+    let start = Instant::now();
+    let deadline = args.duration.map(|seconds| start + Duration::from_secs(seconds));
+    let summary_interval = Duration::from_secs(args.summary_interval.unwrap_or(DEFAULT_SUMMARY_INTERVAL));
+    let top = args.top.unwrap_or(DEFAULT_TOP);
+    let mut next_summary = start + summary_interval;
+    let mut graph = TrafficGraph::new();
+    graph.set_per_vlan(args.vlan_nodes);
+    graph.set_local_addresses(local_addresses);
+    graph.set_scan_config(args.scan_config());
+    graph.set_rules(args.rules.clone().unwrap_or_default());
+    let mut notifier = AlertNotifier::new();
+    if let Some(oui) = args.oui_table()? {
+        graph.set_oui_table(oui);
+    }
+    if let Some(geoip) = args.geoip_database()? {
+        graph.set_geoip(geoip);
+    }
+
+    while running.load(Ordering::SeqCst) {
+        match rx.recv_timeout(RECV_TIMEOUT) {
+            Ok(packet) => {
+                add_packet(&mut graph, &mut notifier, &packet);
+                rx.try_iter().for_each(|packet| add_packet(&mut graph, &mut notifier, &packet));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            // The capture thread ended, the capture file was fully read
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        add_discoveries(&mut graph, &mut notifier, &discovery_rx);
+        let now = Instant::now();
+        if now >= next_summary {
+            report::write_summary(&mut io::stdout(), now - start, &graph, top).map_err(|e| e.to_string())?;
+            next_summary = now + summary_interval;
+        }
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            break;
+        }
+    }
+    running.store(false, Ordering::SeqCst);
+    add_discoveries(&mut graph, &mut notifier, &discovery_rx);
+
+    report::write_summary(&mut io::stdout(), start.elapsed(), &graph, top).map_err(|e| e.to_string())?;
+    if args.output_dir.is_none() {
+        report::write_report(&mut io::stdout(), graph.get_nodes()).map_err(|e| e.to_string())?;
+    }
+    for path in cli::write_outputs(args, &graph.export_nodes(), &graph.window_nodes(args.stats_window()), graph.get_alerts())? {
+        println!("Written {}", path.display());
+    }
+    Ok(())
+}
+
+// Alerts are printed and their actions run as soon as they are raised
+fn raise(notifier: &mut AlertNotifier, alert: &Alert) {
+    println!("[{}] {}", convert_time_str(alert.timestamp), alert);
+    if let Err(e) = notifier.notify(alert) {
+        eprintln!("Could not run the actions of the alert: {}", e);
+    }
+}
+
+fn add_packet(graph: &mut TrafficGraph, notifier: &mut AlertNotifier, packet: &IPPacketInfo) {
+    for alert in graph.add_packet(packet) {
+        raise(notifier, &alert);
+    }
+}
+
+fn add_discoveries(graph: &mut TrafficGraph, notifier: &mut AlertNotifier, discoveries: &Receiver<HostDiscovery>) {
+    for discovery in discoveries.try_iter() {
+        let raised = graph.get_alerts().len();
+        if let Some(change) = graph.add_discovery(&discovery) {
+            println!("MAC of {} changed from {} to {}", change.ip, change.old, change.new);
+        }
+        for alert in &graph.get_alerts()[raised..] {
+            raise(notifier, alert);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Capture IP packets and build a graph of the nodes talking to each other.
+//!
+//! The capture and statistics parts can be used without the graphical interface:
+//! - `capture`: packet capture from network devices and pcap files (requires libpcap)
+//! - `gui`: macroquad renderer and input for the engine
+//! - `cli`: command line options and the headless mode
+
+pub mod data;
+pub mod engine;
+pub mod entities;
+pub mod export;
+pub mod math;
+pub mod notifier;
+pub mod report;
+pub mod resolver;
+
+#[cfg(feature = "capture")]
+pub mod capture;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod headless;
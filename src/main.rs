@@ -1,18 +1,18 @@
+use macroquad::audio;
 use macroquad::prelude::*;
-use ui::UI;
-use std::time::Instant;
+use ui::{View, UI};
+use std::time::{Duration, Instant};
 use std::sync::mpsc::channel;
-use network_manager::NetworkManager;
 use std::thread;
-use data::IPPacketInfo;
-use engine::{PhysicsEngine, GraphicsEngine};
+use network_vizualizer::capture::NetworkManager;
+use network_vizualizer::cli::{self, exit_with_error, Args};
+use network_vizualizer::data::{FrameInfo, HostDiscovery, IPPacketInfo, OuiTable};
+use network_vizualizer::engine::{self, PhysicsEngine, GraphicsEngine, MacroquadInput, MacroquadRenderer, StaticInput};
+use network_vizualizer::headless;
+use network_vizualizer::notifier::{beep_wav, AlertNotifier};
+use network_vizualizer::resolver::ReverseResolver;
 
-pub mod data;
-pub mod entities;
 mod ui;
-mod network_manager;
-mod math;
-pub mod engine;
 
 fn window_conf() -> Conf {
     Conf {
@@ -22,28 +22,181 @@ fn window_conf() -> Conf {
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn write_outputs(args: &Args, engine: &engine::Engine) {
+    if let Err(e) = cli::write_outputs(args, &engine.export_nodes(), &engine.window_nodes(args.stats_window()), engine.get_alerts()) {
+        eprintln!("{}", e);
+    }
+}
+
+fn new_engine(args: &Args, oui: Option<OuiTable>) -> engine::Engine {
+    let mut engine = engine::Engine::new();
+    if let Some(max_packets) = args.max_packets {
+        engine.get_settings().max_packets = max_packets;
+    }
+    if let Some(layout) = args.layout {
+        engine.get_settings().layout = layout;
+    }
+    if let Some(color_by) = args.color_by {
+        engine.get_settings().color_by = color_by;
+    }
+    if let Some(group_mode) = args.group_mode {
+        engine.get_settings().group_mode = group_mode;
+    }
+    if let Some(classes) = &args.hide_classes {
+        engine.get_settings().hidden_classes = classes.clone();
+    }
+    engine.get_settings().pin_local = !args.no_pin_local;
+    engine.get_settings().scan = args.scan_config();
+    if let Some(seconds) = args.new_highlight {
+        engine.get_settings().new_highlight = Duration::from_secs(seconds);
+    }
+    if let Some(oui) = oui {
+        engine.set_oui_table(oui);
+    }
+    engine.set_per_vlan(args.vlan_nodes);
+    engine.set_live(args.pcap_file.is_none());
+    engine
+}
+
+fn main() {
+    let args = match Args::load() {
+        Ok(args) => args,
+        Err(e) => exit_with_error(e),
+    };
+    if args.headless {
+        if let Err(e) = headless::run(&args) {
+            exit_with_error(e);
+        }
+        return;
+    }
+    macroquad::Window::from_config(window_conf(), run_gui(args));
+}
+
+async fn run_gui(args: Args) {
     let (tx, rx) = channel::<IPPacketInfo>();
-    let mut ui = UI{};
+    let mut ui = UI::new();
+    let (discovery_tx, discovery_rx) = channel::<HostDiscovery>();
     let mut network_manager = NetworkManager::new(tx);
-    ui.draw_network_device_menu(&mut network_manager).await;
+    network_manager.set_discovery_sender(discovery_tx);
+    network_manager.set_decapsulate(args.decapsulate);
+    let frame_rx = args.l2.then(|| {
+        let (frame_tx, frame_rx) = channel::<FrameInfo>();
+        network_manager.set_frame_sender(frame_tx);
+        frame_rx
+    });
+    match cli::open_capture(&args, &mut network_manager) {
+        Ok(true) => {}
+        Ok(false) => {
+            ui.draw_network_device_menu(&mut network_manager).await;
+            let configured = cli::set_filter(&args, &mut network_manager)
+                .and_then(|()| cli::start_recording(&args, &mut network_manager));
+            if let Err(e) = configured {
+                exit_with_error(e);
+            }
+        }
+        Err(e) => exit_with_error(e),
+    }
+    // The ring can take hundreds of megabytes, it is only kept when asked for
+    if let Some(ring_minutes) = args.ring_minutes.filter(|minutes| *minutes > 0) {
+        match network_manager.start_ring(Duration::from_secs(ring_minutes * 60)) {
+            Ok(ring) => ui.set_packet_ring(ring),
+            Err(e) => eprintln!("Could not keep the last packets: {}", e),
+        }
+    }
 
+    let local_addresses = cli::local_addresses(&args, &network_manager);
     thread::spawn(move || {
-        loop {
+        while !network_manager.is_finished() {
             network_manager.listen_packets();
         }
     });
-    let mut engine = engine::Engine::new();
+    let oui = args.oui_table().unwrap_or_else(|e| exit_with_error(e));
+    let mut engine = new_engine(&args, oui.clone());
+    // Ethernet frames go to a second engine, drawn instead of the first one in the MAC view
+    // Tunnel packets go to a third engine, between the tunnel endpoints
+    let mut outer_engine = args.decapsulate.then(|| new_engine(&args, oui.clone()));
+    let mut mac_engine = frame_rx.as_ref().map(|_| new_engine(&args, oui));
+    // Rules and the baseline only apply to the IP view, the one saved in sessions
+    engine.set_rules(args.rules.clone().unwrap_or_default());
+    match args.baseline() {
+        Ok(Some(baseline)) => engine.set_baseline(&baseline),
+        Ok(None) => {}
+        Err(e) => exit_with_error(e),
+    }
+    let mut notifier = AlertNotifier::new();
+    match audio::load_sound_from_bytes(&beep_wav()).await {
+        Ok(beep) => notifier.set_sound(move || audio::play_sound_once(beep)),
+        Err(e) => eprintln!("Could not load the alert sound: {}", e),
+    }
+    engine.set_local_addresses(local_addresses.clone());
+    if let Some(outer_engine) = &mut outer_engine {
+        outer_engine.set_local_addresses(local_addresses);
+    }
+    let available = [Some(View::Ip), outer_engine.as_ref().map(|_| View::Outer), mac_engine.as_ref().map(|_| View::Mac)];
+    ui.set_views(available.into_iter().flatten().collect());
+    match args.geoip_database() {
+        Ok(Some(geoip)) => engine.set_geoip(geoip),
+        Ok(None) => {}
+        Err(e) => exit_with_error(e),
+    }
+    let mut resolver = match args.resolver_config() {
+        Ok(Some(config)) => match ReverseResolver::start(config) {
+            Ok(resolver) => Some(resolver),
+            Err(e) => exit_with_error(format!("Could not start the reverse DNS resolver: {}", e)),
+        },
+        Ok(None) => None,
+        Err(e) => exit_with_error(e),
+    };
+    let deadline = args.duration.map(|seconds| Instant::now() + Duration::from_secs(seconds));
+    prevent_quit();
     loop{
         let timestamp = Instant::now();
         rx.try_iter().for_each(|packet| {
             engine.add_packet(&packet);
+            if let Some(outer_engine) = &mut outer_engine {
+                outer_engine.add_packet(packet.outer());
+            }
+            if let Some(resolver) = &mut resolver {
+                for ip in [packet.source, packet.dest] {
+                    if engine.get_node(&ip).is_some_and(|node| node.get_names().is_empty()) {
+                        resolver.request(ip);
+                    }
+                }
+            }
         });
+        if let Some(resolver) = &mut resolver {
+            engine.add_reverse_names(&resolver.results());
+        }
+        discovery_rx.try_iter().for_each(|discovery| {
+            engine.add_discovery(&discovery);
+        });
+        for alert in engine.take_new_alerts() {
+            if let Err(e) = notifier.notify(&alert) {
+                eprintln!("Could not run the actions of the alert: {}", e);
+            }
+        }
+        if let (Some(mac_engine), Some(frame_rx)) = (&mut mac_engine, &frame_rx) {
+            frame_rx.try_iter().for_each(|frame| mac_engine.add_frame(&frame));
+        }
         clear_background(BLACK);
-        engine.update();
-        engine.draw();
-        ui.draw_settings(timestamp, &mut engine);
+        // The hidden views keep moving their packets but don't get the mouse
+        let hidden_input = StaticInput::new(screen_width(), screen_height());
+        let mut views = vec![(View::Ip, &mut engine)];
+        views.extend(outer_engine.as_mut().map(|engine| (View::Outer, engine)));
+        views.extend(mac_engine.as_mut().map(|engine| (View::Mac, engine)));
+        let (shown, hidden): (Vec<_>, Vec<_>) = views.into_iter().partition(|(view, _)| *view == ui.get_view());
+        for (_, hidden) in hidden {
+            hidden.update(&hidden_input);
+        }
+        let shown = shown.into_iter().next().unwrap().1;
+        shown.update(&MacroquadInput);
+        shown.draw(&mut MacroquadRenderer);
+        ui.draw_tooltip(shown);
+        ui.draw_settings(timestamp, shown);
+        if is_quit_requested() || deadline.is_some_and(|deadline| timestamp >= deadline) {
+            break;
+        }
         next_frame().await
     };
-}
\ No newline at end of file
+    write_outputs(&args, &engine);
+}
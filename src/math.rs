@@ -1,5 +1,7 @@
 use std::ops::{Mul, Add, AddAssign};
-#[derive(Debug, Copy, Clone)]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point{
     pub x: f32,
     pub y: f32,
@@ -103,6 +105,10 @@ impl Point{
         let magnitude = (x_diff.powf(2.0) + y_diff.powf(2.0)).sqrt();
         return Vector{x:x_diff/magnitude, y:y_diff/magnitude};
     }
+    // Position of the point relative to a camera located at `origin`
+    pub fn relative_to(&self, origin: &Point) -> Point{
+        Point{x: self.x - origin.x, y: self.y - origin.y}
+    }
     pub fn get_middle_point(&self, b: &Point) -> Point{
         let x = (self.x + b.x)/2.0;
         let y = (self.y + b.y)/2.0;
@@ -154,3 +160,44 @@ pub fn convert_bytes_str(bytes: u64) -> String{
     }
 }
 
+// Two largest units of a duration, "1d 2h" or "5m 30s"
+pub fn convert_duration_str(seconds: u64) -> String{
+    let units = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+    let Some(idx) = units.iter().position(|(size, _)| seconds >= *size) else {
+        return "0s".to_string();
+    };
+    let (size, unit) = units[idx];
+    match units.get(idx + 1) {
+        Some((next_size, next_unit)) if seconds % size >= *next_size => {
+            format!("{}{} {}{}", seconds / size, unit, seconds % size / next_size, next_unit)
+        }
+        _ => format!("{}{}", seconds / size, unit),
+    }
+}
+
+// UTC date and time of a timestamp, "2024-05-01T13:45:10Z"
+pub fn convert_time_str(time: SystemTime) -> String{
+    let seconds = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, second) = (seconds / 86400, seconds % 86400);
+    // Civil date of the day count, from Howard Hinnant's days_from_civil inverse
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096) / 365;
+    let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+    let mp = (5*day_of_year + 2) / 153;
+    let day = day_of_year - (153*mp + 2)/5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = year_of_era + era*400 + if month <= 2 {1} else {0};
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, second / 3600, second % 3600 / 60, second % 60)
+}
+
+// Time of a pcap header, the current time when a corrupt file gives one before 1970 or out of range.
+// Microseconds past one second are clamped
+pub fn capture_time(seconds: i64, microseconds: i64) -> SystemTime{
+    let Ok(seconds) = u64::try_from(seconds) else {
+        return SystemTime::now();
+    };
+    let nanoseconds = microseconds.clamp(0, 999_999) as u32 * 1000;
+    UNIX_EPOCH.checked_add(Duration::new(seconds, nanoseconds)).unwrap_or_else(SystemTime::now)
+}
@@ -0,0 +1,123 @@
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread;
+use std::time::Duration;
+use crate::data::{Alert, RuleAction, Webhook};
+use crate::export::alert_json;
+use crate::math::convert_time_str;
+
+// Alerts waiting to be posted, the ones raised when the queue is full are dropped
+const QUEUE_SIZE: usize = 256;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(2);
+const BEEP_RATE: u32 = 44100;
+const BEEP_FREQUENCY: f32 = 880.0;
+const BEEP_DURATION: f32 = 0.2;
+
+// Runs the log, sound and webhook actions of the alerts, highlighting is left to the engine.
+// Webhooks are posted by a background thread so that a slow endpoint never blocks the caller
+#[derive(Default)]
+pub struct AlertNotifier{
+    webhooks: Option<SyncSender<(Webhook, String)>>,
+    // Plays the sound action, the terminal bell is rung when not set
+    sound: Option<Box<dyn FnMut()>>,
+}
+
+impl AlertNotifier{
+    pub fn new() -> AlertNotifier{
+        AlertNotifier::default()
+    }
+
+    // A window has no terminal, its bell is silent
+    pub fn set_sound(&mut self, sound: impl FnMut() + 'static){
+        self.sound = Some(Box::new(sound));
+    }
+
+    // Returns the first error of the actions, the other actions are still run
+    pub fn notify(&mut self, alert: &Alert) -> io::Result<()>{
+        let mut result = Ok(());
+        for action in &alert.actions{
+            let done = match action {
+                RuleAction::Highlight => Ok(()),
+                RuleAction::Log(path) => OpenOptions::new().create(true).append(true).open(path)
+                    .and_then(|mut file| writeln!(file, "[{}] {}", convert_time_str(alert.timestamp), alert)),
+                RuleAction::Sound => match &mut self.sound {
+                    Some(sound) => {
+                        sound();
+                        Ok(())
+                    }
+                    // Terminal bell
+                    None => io::stdout().write_all(b"\x07").and_then(|()| io::stdout().flush()),
+                },
+                RuleAction::Webhook(webhook) => {
+                    self.post(webhook, alert_json(alert).to_string());
+                    Ok(())
+                }
+            };
+            if result.is_ok(){
+                result = done;
+            }
+        }
+        result
+    }
+
+    fn post(&mut self, webhook: &Webhook, body: String){
+        let sender = self.webhooks.get_or_insert_with(|| {
+            let (sender, receiver) = sync_channel::<(Webhook, String)>(QUEUE_SIZE);
+            thread::spawn(move || {
+                for (webhook, body) in receiver{
+                    if let Err(e) = post_json(&webhook, &body){
+                        eprintln!("Could not post alert to {}: {}", webhook, e);
+                    }
+                }
+            });
+            sender
+        });
+        let _ = sender.try_send((webhook.clone(), body));
+    }
+}
+
+// HTTP/1.1 POST of a JSON body, the response is read and ignored
+pub fn post_json(webhook: &Webhook, body: &str) -> io::Result<()>{
+    let address = (webhook.host.trim_start_matches('[').trim_end_matches(']'), webhook.port).to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address for the host"))?;
+    let mut stream = TcpStream::connect_timeout(&address, WEBHOOK_TIMEOUT)?;
+    stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+    stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        webhook.path, webhook.host, webhook.port, body.len(), body,
+    )?;
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    Ok(())
+}
+
+// Short tone as a 16-bit mono WAV file, fading out to avoid a click at the end
+pub fn beep_wav() -> Vec<u8>{
+    let count = (BEEP_RATE as f32 * BEEP_DURATION) as u32;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + count * 2).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    // PCM in one channel, with 2 bytes per sample
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&BEEP_RATE.to_le_bytes());
+    wav.extend_from_slice(&(BEEP_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&16_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(count * 2).to_le_bytes());
+    for i in 0..count{
+        let time = i as f32 / BEEP_RATE as f32;
+        let volume = 0.3 * (1.0 - i as f32 / count as f32);
+        let sample = (time * BEEP_FREQUENCY * std::f32::consts::TAU).sin() * volume * i16::MAX as f32;
+        wav.extend_from_slice(&(sample as i16).to_le_bytes());
+    }
+    wav
+}
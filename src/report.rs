@@ -0,0 +1,74 @@
+use crate::data::{NodeData, PacketDirection, TrafficGraph};
+use crate::math::convert_bytes_str;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const REPORT_FILE_NAME: &str = "report.txt";
+
+// " Paris, France (AS15169 Google LLC)" after the columns, empty without GeoIP databases or for local nodes
+fn geo_column(node: &NodeData) -> String{
+    let Some(geo) = &node.geo else {
+        return String::new();
+    };
+    match (geo.location(), geo.as_label()) {
+        (Some(location), Some(asn)) => format!(" {} ({})", location, asn),
+        (location, asn) => location.or(asn).map(|label| format!(" {}", label)).unwrap_or_default(),
+    }
+}
+
+// Writes a plain text table with the traffic of every node, busiest nodes first
+pub fn write_report<'a, W: Write>(writer: &mut W, nodes: impl Iterator<Item = &'a NodeData>) -> io::Result<()>{
+    let mut nodes: Vec<&NodeData> = nodes.collect();
+    nodes.sort_by_key(|node| std::cmp::Reverse(node.stats.packet_bytes_sent + node.stats.packet_bytes_recv));
+
+    writeln!(writer, "Nodes: {}", nodes.len())?;
+    writeln!(writer, "{:<40} {:>12} {:>12} {:>12} {:>12} Location", "IP", "Pkts sent", "Data sent", "Pkts recv", "Data recv")?;
+    for node in nodes{
+        writeln!(
+            writer,
+            "{:<40} {:>12} {:>12} {:>12} {:>12}{}",
+            node.key().to_string(),
+            node.stats.packet_count_sent,
+            convert_bytes_str(node.stats.packet_bytes_sent),
+            node.stats.packet_count_recv,
+            convert_bytes_str(node.stats.packet_bytes_recv),
+            geo_column(node),
+        )?;
+    }
+    Ok(())
+}
+
+// Writes the report into `dir`, creating it if needed, and returns the path of the report
+pub fn write_report_to_dir<'a>(dir: &Path, nodes: impl Iterator<Item = &'a NodeData>) -> io::Result<PathBuf>{
+    fs::create_dir_all(dir)?;
+    let path = dir.join(REPORT_FILE_NAME);
+    let mut file = File::create(&path)?;
+    write_report(&mut file, nodes)?;
+    Ok(path)
+}
+
+// Short periodic summary with the busiest nodes
+pub fn write_summary<W: Write>(writer: &mut W, elapsed: Duration, graph: &TrafficGraph, top: usize) -> io::Result<()>{
+    let nodes = graph.get_nodes().count();
+    writeln!(writer, "[{:>6}s] {} packets, {} nodes, top talkers:", elapsed.as_secs(), graph.get_packet_count(), nodes)?;
+    if graph.has_local_addresses(){
+        let directions: Vec<String> = PacketDirection::ALL.iter().map(|direction| {
+            let stats = graph.get_directions().get(*direction);
+            format!("{} {} ({})", direction, stats.packet_count, convert_bytes_str(stats.packet_bytes))
+        }).collect();
+        writeln!(writer, "  {}", directions.join(", "))?;
+    }
+    for node in graph.top_talkers(top){
+        writeln!(
+            writer,
+            "  {:<40} sent {:>10} recv {:>10}{}",
+            node.key().to_string(),
+            convert_bytes_str(node.stats.packet_bytes_sent),
+            convert_bytes_str(node.stats.packet_bytes_recv),
+            geo_column(node),
+        )?;
+    }
+    Ok(())
+}
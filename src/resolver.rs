@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::data::{build_ptr_query, message_id, parse_response, DnsRecord, DNS_PORT};
+
+// Addresses waiting for a lookup, the ones requested when the queue is full are tried again later
+const QUEUE_SIZE: usize = 1024;
+const MAX_RESPONSE_SIZE: usize = 4096;
+// The expired requests are forgotten at most this often
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct ResolverConfig{
+    pub server: SocketAddr,
+    pub max_per_second: u32,
+    pub timeout: Duration,
+    // Addresses without name are not asked again before this delay
+    pub negative_ttl: Duration,
+}
+
+impl ResolverConfig{
+    pub fn new(server: SocketAddr) -> ResolverConfig{
+        ResolverConfig{
+            server,
+            max_per_second: 10,
+            timeout: Duration::from_secs(2),
+            negative_ttl: Duration::from_secs(600),
+        }
+    }
+}
+
+// First name server of /etc/resolv.conf
+pub fn system_resolver() -> Option<SocketAddr>{
+    let config = fs::read_to_string("/etc/resolv.conf").ok()?;
+    config.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|address| address.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        .next()
+}
+
+// PTR lookups made by a background thread, one at a time and rate limited,
+// so that asking for names never blocks the caller
+pub struct ReverseResolver{
+    requests: SyncSender<IpAddr>,
+    results: Receiver<DnsRecord>,
+    // Time until which every requested address is not asked again, after the TTL
+    // of its name or the negative TTL when it has none
+    requested: HashMap<IpAddr, Instant>,
+    negative_ttl: Duration,
+    next_prune: Instant,
+}
+
+impl ReverseResolver{
+    pub fn start(config: ResolverConfig) -> io::Result<ReverseResolver>{
+        let bind_address: SocketAddr = match config.server {
+            SocketAddr::V4(_) => "0.0.0.0:0".parse().unwrap(),
+            SocketAddr::V6(_) => "[::]:0".parse().unwrap(),
+        };
+        let socket = UdpSocket::bind(bind_address)?;
+        socket.connect(config.server)?;
+        socket.set_read_timeout(Some(config.timeout))?;
+
+        let (request_sender, request_receiver) = sync_channel::<IpAddr>(QUEUE_SIZE);
+        let (result_sender, result_receiver) = channel::<DnsRecord>();
+        let interval = Duration::from_secs(1) / config.max_per_second.max(1);
+        thread::spawn(move || {
+            let mut id: u16 = rand::random();
+            for ip in request_receiver{
+                let started = Instant::now();
+                id = id.wrapping_add(1);
+                for record in lookup(&socket, id, &ip){
+                    if result_sender.send(record).is_err(){
+                        return;
+                    }
+                }
+                if let Some(wait) = interval.checked_sub(started.elapsed()){
+                    thread::sleep(wait);
+                }
+            }
+        });
+        Ok(ReverseResolver{
+            requests: request_sender,
+            results: result_receiver,
+            requested: HashMap::new(),
+            negative_ttl: config.negative_ttl,
+            next_prune: Instant::now(),
+        })
+    }
+
+    // Queues a lookup unless the address was asked recently
+    pub fn request(&mut self, ip: IpAddr){
+        let now = Instant::now();
+        if self.requested.get(&ip).is_some_and(|until| now < *until){
+            return;
+        }
+        if self.requests.try_send(ip).is_ok(){
+            self.requested.insert(ip, now + self.negative_ttl);
+        }
+    }
+
+    // Names found since the last call
+    pub fn results(&mut self) -> Vec<DnsRecord>{
+        let now = Instant::now();
+        let records: Vec<DnsRecord> = self.results.try_iter().collect();
+        for record in &records{
+            self.requested.insert(record.ip, now + Duration::from_secs(record.ttl.into()));
+        }
+        if now >= self.next_prune{
+            self.requested.retain(|_, until| now < *until);
+            self.next_prune = now + PRUNE_INTERVAL.min(self.negative_ttl);
+        }
+        records
+    }
+
+    // Addresses not to be asked again yet
+    pub fn requested(&self) -> usize{
+        self.requested.len()
+    }
+}
+
+// Records of the response to the query, empty when the address has no name or the server didn't answer
+fn lookup(socket: &UdpSocket, id: u16, ip: &IpAddr) -> Vec<DnsRecord>{
+    if socket.send(&build_ptr_query(id, ip)).is_err(){
+        return Vec::new();
+    }
+    let mut buffer = [0; MAX_RESPONSE_SIZE];
+    // Late responses to previous queries are skipped until the timeout
+    while let Ok(size) = socket.recv(&mut buffer){
+        let response = &buffer[..size];
+        if message_id(response) == Some(id){
+            return parse_response(response).into_iter().filter(|record| record.ip == *ip).collect();
+        }
+    }
+    Vec::new()
+}
@@ -1,14 +1,117 @@
-use crate::engine::Engine;
-use crate::network_manager::NetworkManager;
+use network_vizualizer::engine::{ColorMode, Engine, GroupMode, LayoutMode, Session};
+use network_vizualizer::capture::{NetworkManager, PacketRing};
+use network_vizualizer::entities::Node;
+use network_vizualizer::data::TimeWindow;
+use network_vizualizer::export::{self, GraphFormat, StatsFormat};
+use network_vizualizer::data::{AddressClass, MacAddress, NodeData, PacketDirection, Rule};
+use network_vizualizer::math::{convert_bytes_str, convert_duration_str, convert_time_str};
 use egui_extras::{Column, TableBuilder};
 use macroquad::prelude::*;
 use pcap::Device;
 use std::net::IpAddr;
-use std::time::Instant;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-pub struct UI {}
+const DEFAULT_SESSION_PATH: &str = "session.json";
+const DEFAULT_EXPORT_PATH: &str = "graph.graphml";
+const DEFAULT_STATS_DIR: &str = "stats";
+const DEFAULT_RING_PATH: &str = "last.pcap";
+const DEFAULT_ALERTS_PATH: &str = "alerts.csv";
+const MAX_DETAIL_FLOWS: usize = 50;
+const MAX_LEGEND_ENTRIES: usize = 20;
+const MAX_SCAN_WINDOW_SECONDS: u64 = 3600;
+const MAX_NEW_HIGHLIGHT_SECONDS: u64 = 3600;
+
+// Engine drawn in the window, the other ones keep receiving packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    // Nodes keyed by IP, the inner packets when tunnels are decapsulated
+    Ip,
+    // Nodes of the tunnel endpoints
+    Outer,
+    // Nodes keyed by MAC address
+    Mac,
+}
+
+impl View {
+    fn label(&self) -> &'static str {
+        match self {
+            View::Ip => "IP",
+            View::Outer => "Outer IP",
+            View::Mac => "MAC",
+        }
+    }
+}
+
+pub struct UI {
+    search_text: String,
+    session_path: String,
+    session_status: String,
+    export_format: GraphFormat,
+    export_path: String,
+    stats_format: StatsFormat,
+    stats_dir: String,
+    // 0 exports the whole capture
+    stats_minutes: u64,
+    export_status: String,
+    packet_ring: Option<Arc<Mutex<PacketRing>>>,
+    ring_path: String,
+    ring_minutes: u64,
+    ring_status: String,
+    alerts_format: StatsFormat,
+    alerts_path: String,
+    alerts_status: String,
+    rule_text: String,
+    rule_status: String,
+    view: View,
+    views: Vec<View>,
+}
 
 impl UI {
+    pub fn new() -> UI {
+        UI {
+            search_text: String::new(),
+            session_path: DEFAULT_SESSION_PATH.to_string(),
+            session_status: String::new(),
+            export_format: GraphFormat::GraphML,
+            export_path: DEFAULT_EXPORT_PATH.to_string(),
+            stats_format: StatsFormat::Csv,
+            stats_dir: DEFAULT_STATS_DIR.to_string(),
+            stats_minutes: 0,
+            export_status: String::new(),
+            packet_ring: None,
+            ring_path: DEFAULT_RING_PATH.to_string(),
+            ring_minutes: 1,
+            ring_status: String::new(),
+            alerts_format: StatsFormat::Csv,
+            alerts_path: DEFAULT_ALERTS_PATH.to_string(),
+            alerts_status: String::new(),
+            rule_text: String::new(),
+            rule_status: String::new(),
+            view: View::Ip,
+            views: vec![View::Ip],
+        }
+    }
+
+    // Views the Settings window switches between
+    pub fn set_views(&mut self, views: Vec<View>) {
+        self.views = views;
+    }
+
+    pub fn get_view(&self) -> View {
+        self.view
+    }
+
+    // Ring of the last captured frames, enables the Recording window
+    pub fn set_packet_ring(&mut self, ring: Arc<Mutex<PacketRing>>) {
+        // Saves the whole ring unless fewer minutes are picked
+        self.ring_minutes = ring.lock().map(|ring| ring.get_duration().as_secs() / 60).unwrap_or(1).max(1);
+        self.packet_ring = Some(ring);
+    }
+
     pub async fn draw_network_device_menu(&mut self, network_manager: &mut NetworkManager) {
         //transform devices into a hashmap with ids
         let devices : Vec<Device> = NetworkManager::get_network_devices();
@@ -73,11 +176,43 @@ impl UI {
         }
     }
 
+    pub fn draw_tooltip(&mut self, engine: &Engine) {
+        let Some(node) = engine.get_tooltip_node() else {
+            return;
+        };
+        egui_macroquad::ui(|egui_ctx| {
+            UI::draw_node_tooltip(egui_ctx, node, self.view == View::Mac);
+        });
+        egui_macroquad::draw();
+    }
+
+    // Nodes of the MAC view only have a MAC address and its vendor
+    fn draw_node_tooltip(ctx: &egui::Context, node: &Node, mac_view: bool) {
+        let data = node.get_data();
+        egui::show_tooltip_at_pointer(ctx, egui::Id::new("my_tooltip"), |ui| {
+            if !mac_view {
+                ui.label(format!("IP: {} ({})", data.key(), data.class()));
+                if data.local {
+                    ui.label("Local host");
+                }
+                UI::draw_names(ui, node);
+            }
+            UI::draw_link_info(ui, data);
+            UI::draw_tags(ui, data);
+            UI::draw_tunnels(ui, data);
+            UI::draw_geo_info(ui, data);
+            ui.label(format!("Number of packets received: {:?}", data.stats.packet_count_recv));
+            ui.label(format!("Data received: {}", convert_bytes_str(data.stats.packet_bytes_recv)));
+            ui.label(format!("Number of packets sent: {:?}", data.stats.packet_count_sent));
+            ui.label(format!("Data sent: {}", convert_bytes_str(data.stats.packet_bytes_sent)));
+        });
+    }
+
     pub fn draw_settings(&mut self, start_timestamp: Instant, engine: &mut Engine) {
         let packet_count = engine.get_visible_packet_count();
-        let settings = engine.get_settings();
         //draw_text(&format!("Valid packets: {}", engine.get_visible_packet_count()), 10.0, 10.0, 20.0, WHITE);
         egui_macroquad::ui(|egui_ctx| {
+            let settings = engine.get_settings();
             egui::Window::new("Settings").show(egui_ctx, |ui| {
                 ui.add(
                     egui::Slider::new(&mut settings.max_packets, 0..=100000)
@@ -85,7 +220,12 @@ impl UI {
                         .text("Max Packets"),
                 );
                 ui.label(format!("Number of packets: {:?}", packet_count));
+                ui.checkbox(&mut settings.pin_local, "Pin local host to the center");
+                let mut new_highlight = settings.new_highlight.as_secs();
+                ui.add(egui::Slider::new(&mut new_highlight, 0..=MAX_NEW_HIGHLIGHT_SECONDS).logarithmic(true).text("Highlight new (s, 0 = off)"));
+                settings.new_highlight = Duration::from_secs(new_highlight);
                 ui.checkbox(&mut settings.draw_tooltip, "Draw tooltip");
+                ui.checkbox(&mut settings.draw_labels, "Draw hostnames");
                 ui.checkbox(&mut settings.update, "Start/Stop particle update");
                 // Checkbox
                 ui.checkbox(&mut settings.draw_debug, "Draw debug");
@@ -95,7 +235,57 @@ impl UI {
                 ui.add(
                     egui::Slider::new(&mut settings.speed_launch, 0.0..=2.0).text("Speed launch"),
                 );
+                egui::ComboBox::from_label("Layout")
+                    .selected_text(settings.layout.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut settings.layout, LayoutMode::Random, "random");
+                        ui.selectable_value(&mut settings.layout, LayoutMode::Spiral, "spiral");
+                        ui.selectable_value(&mut settings.layout, LayoutMode::World, "world");
+                    });
+                egui::ComboBox::from_label("Color by")
+                    .selected_text(settings.color_by.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in ColorMode::ALL {
+                            ui.selectable_value(&mut settings.color_by, mode, mode.to_string());
+                        }
+                    });
+                ui.checkbox(&mut settings.group_by_asn, "Group by AS");
+                egui::ComboBox::from_label("Multicast and broadcast")
+                    .selected_text(settings.group_mode.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in GroupMode::ALL {
+                            ui.selectable_value(&mut settings.group_mode, mode, mode.to_string());
+                        }
+                    });
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Show:");
+                    for class in AddressClass::ALL {
+                        let mut shown = !settings.hidden_classes.contains(&class);
+                        if ui.checkbox(&mut shown, class.to_string()).changed() {
+                            settings.hidden_classes.retain(|hidden| *hidden != class);
+                            if !shown {
+                                settings.hidden_classes.push(class);
+                            }
+                        }
+                    }
+                });
+                if self.views.len() > 1 {
+                    ui.horizontal(|ui| {
+                        ui.label("View:");
+                        for view in &self.views {
+                            ui.radio_value(&mut self.view, *view, view.label());
+                        }
+                    });
+                }
             });
+            UI::draw_legend(egui_ctx, engine);
+            UI::draw_directions(egui_ctx, engine);
+            self.draw_search(egui_ctx, engine);
+            self.draw_session(egui_ctx, engine);
+            self.draw_export(egui_ctx, engine);
+            self.draw_recording(egui_ctx);
+            self.draw_alerts(egui_ctx, engine);
+            UI::draw_node_details(egui_ctx, engine, self.view == View::Mac);
         });
         egui_macroquad::draw();
         draw_text(
@@ -106,4 +296,428 @@ impl UI {
             WHITE,
         );
     }
+
+    // Colors of the values of the attribute the nodes are colored by
+    fn draw_legend(egui_ctx: &egui::Context, engine: &Engine) {
+        let legend = engine.get_color_legend();
+        if legend.is_empty() {
+            return;
+        }
+        egui::Window::new("Legend").show(egui_ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for (key, color, count) in legend.iter().take(MAX_LEGEND_ENTRIES) {
+                    let color = egui::Color32::from_rgb((color.r * 255.0) as u8, (color.g * 255.0) as u8, (color.b * 255.0) as u8);
+                    ui.colored_label(color, format!("{} ({})", key, count));
+                }
+                if legend.len() > MAX_LEGEND_ENTRIES {
+                    ui.label(format!("{} more", legend.len() - MAX_LEGEND_ENTRIES));
+                }
+            });
+        });
+    }
+
+    // Traffic of the local host, green inbound and blue outbound packets
+    fn draw_directions(egui_ctx: &egui::Context, engine: &Engine) {
+        if engine.get_local_addresses().is_empty() {
+            return;
+        }
+        egui::Window::new("Local host").default_open(false).show(egui_ctx, |ui| {
+            let addresses: Vec<String> = engine.get_local_addresses().iter().map(|ip| ip.to_string()).collect();
+            ui.label(addresses.join(", "));
+            for direction in PacketDirection::ALL {
+                let stats = engine.get_directions().get(direction);
+                ui.label(format!("{}: {} packets, {}", direction, stats.packet_count, convert_bytes_str(stats.packet_bytes)));
+            }
+        });
+    }
+
+    fn draw_search(&mut self, egui_ctx: &egui::Context, engine: &mut Engine) {
+        // Each view has its own engine, the one shown gets the search
+        engine.set_search(&self.search_text);
+        egui::Window::new("Search").show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("IP, CIDR or hostname:").on_hover_text("vendor:<name> matches the vendors of the MAC addresses, vlan:<id> the nodes seen on the VLAN, class:<multicast|broadcast|link-local|loopback|private|public> the nodes of the address class");
+                if ui.text_edit_singleline(&mut self.search_text).changed() {
+                    engine.set_search(&self.search_text);
+                }
+            });
+            let matches = engine.get_search_matches().clone();
+            ui.label(format!("Matches: {}", matches.len()));
+            ui.separator();
+            let focused = engine.get_focused_node();
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for ip in matches {
+                    ui.horizontal(|ui| {
+                        if focused == Some(ip) {
+                            ui.strong(ip.to_string());
+                        } else {
+                            ui.label(ip.to_string());
+                        }
+                        if ui.button("Jump to").clicked() {
+                            engine.jump_to(&ip);
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    fn draw_session(&mut self, egui_ctx: &egui::Context, engine: &mut Engine) {
+        egui::Window::new("Session").default_open(false).show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.session_path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.session_status = match engine.to_session().save(Path::new(&self.session_path)) {
+                        Ok(()) => format!("Saved to {}", self.session_path),
+                        Err(e) => format!("Could not save: {}", e),
+                    };
+                }
+                if ui.button("Open").clicked() {
+                    self.session_status = match Session::load(Path::new(&self.session_path)) {
+                        Ok(session) => {
+                            engine.load_session(session);
+                            format!("Opened {}", self.session_path)
+                        }
+                        Err(e) => format!("Could not open: {}", e),
+                    };
+                }
+                // Only what is missing from the baseline is highlighted as new
+                if ui.button("Use as baseline").clicked() {
+                    self.session_status = match Session::load(Path::new(&self.session_path)) {
+                        Ok(session) => {
+                            engine.set_baseline(&session);
+                            format!("Baseline {}", self.session_path)
+                        }
+                        Err(e) => format!("Could not open: {}", e),
+                    };
+                }
+            });
+            if let Some(baseline_nodes) = engine.get_baseline().map(|baseline| baseline.len()) {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Baseline of {} nodes", baseline_nodes));
+                    if ui.button("Clear baseline").clicked() {
+                        engine.clear_baseline();
+                    }
+                });
+            }
+            ui.label("Right click a node to pin it");
+            if !self.session_status.is_empty() {
+                ui.label(&self.session_status);
+            }
+        });
+    }
+
+    fn draw_export(&mut self, egui_ctx: &egui::Context, engine: &Engine) {
+        egui::Window::new("Export").default_open(false).show(egui_ctx, |ui| {
+            let previous_format = self.export_format;
+            egui::ComboBox::from_label("Format")
+                .selected_text(self.export_format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in GraphFormat::ALL {
+                        ui.selectable_value(&mut self.export_format, format, format.to_string());
+                    }
+                });
+            if self.export_format != previous_format {
+                self.export_path = Path::new(&self.export_path)
+                    .with_extension(self.export_format.extension())
+                    .to_string_lossy()
+                    .into_owned();
+            }
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.export_path);
+            });
+            if ui.button("Export").clicked() {
+                let result = File::create(&self.export_path).and_then(|file| {
+                    let mut writer = BufWriter::new(file);
+                    export::write_graph(&mut writer, self.export_format, &engine.export_nodes())?;
+                    writer.flush()
+                });
+                self.export_status = match result {
+                    Ok(()) => format!("Exported to {}", self.export_path),
+                    Err(e) => format!("Could not export: {}", e),
+                };
+            }
+            ui.separator();
+            egui::ComboBox::from_label("Statistics format")
+                .selected_text(self.stats_format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in StatsFormat::ALL {
+                        ui.selectable_value(&mut self.stats_format, format, format.to_string());
+                    }
+                });
+            ui.add(egui::Slider::new(&mut self.stats_minutes, 0..=60).text("Last minutes (0 = all)"));
+            ui.horizontal(|ui| {
+                ui.label("Directory:");
+                ui.text_edit_singleline(&mut self.stats_dir);
+            });
+            if ui.button("Export statistics").clicked() {
+                let window = match self.stats_minutes {
+                    0 => TimeWindow::All,
+                    minutes => TimeWindow::Last(Duration::from_secs(minutes * 60)),
+                };
+                let nodes = engine.window_nodes(window);
+                self.export_status = match export::write_stats_to_dir(Path::new(&self.stats_dir), self.stats_format, &nodes) {
+                    Ok(paths) => format!("Exported {} nodes to {}", nodes.len(), paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")),
+                    Err(e) => format!("Could not export: {}", e),
+                };
+            }
+            if !self.export_status.is_empty() {
+                ui.label(&self.export_status);
+            }
+        });
+    }
+
+    fn draw_recording(&mut self, egui_ctx: &egui::Context) {
+        let Some(ring) = &self.packet_ring else {
+            return;
+        };
+        egui::Window::new("Recording").default_open(false).show(egui_ctx, |ui| {
+            let Ok(ring) = ring.lock() else {
+                ui.label("Recording unavailable");
+                return;
+            };
+            let max_minutes = (ring.get_duration().as_secs() / 60).max(1);
+            ui.label(format!("{} packets in memory", ring.len()));
+            ui.add(egui::Slider::new(&mut self.ring_minutes, 1..=max_minutes).text("Minutes"));
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.ring_path);
+            });
+            if ui.button(format!("Save last {} minutes", self.ring_minutes)).clicked() {
+                let duration = Duration::from_secs(self.ring_minutes * 60);
+                self.ring_status = match ring.save(Path::new(&self.ring_path), duration) {
+                    Ok(count) => format!("Saved {} packets to {}", count, self.ring_path),
+                    Err(e) => format!("Could not save: {}", e),
+                };
+            }
+            if !self.ring_status.is_empty() {
+                ui.label(&self.ring_status);
+            }
+        });
+    }
+
+    // Port scans and host sweeps, newest first
+    fn draw_alerts(&mut self, egui_ctx: &egui::Context, engine: &mut Engine) {
+        let title = match engine.get_alerts().len() {
+            0 => "Alerts".to_string(),
+            count => format!("Alerts ({})", count),
+        };
+        egui::Window::new(title).id(egui::Id::new("alerts")).default_open(false).show(egui_ctx, |ui| {
+            let scan = &mut engine.get_settings().scan;
+            let mut window = scan.window.as_secs();
+            ui.add(egui::Slider::new(&mut window, 1..=MAX_SCAN_WINDOW_SECONDS).logarithmic(true).text("Window (s)"));
+            scan.window = Duration::from_secs(window);
+            ui.add(egui::Slider::new(&mut scan.port_threshold, 0..=1000).logarithmic(true).text("Ports of one host (0 = off)"));
+            ui.add(egui::Slider::new(&mut scan.host_threshold, 0..=1000).logarithmic(true).text("Hosts on one port (0 = off)"));
+            ui.separator();
+            ui.label("Rules:");
+            let mut removed = None;
+            for (index, rule) in engine.get_rules().iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(rule.to_string());
+                    if ui.button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                engine.remove_rule(index);
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.rule_text)
+                    .on_hover_text("e.g. sent>50MB/1m dst:public => highlight,log:alerts.log");
+                if ui.button("Add rule").clicked() {
+                    match self.rule_text.parse::<Rule>() {
+                        Ok(rule) => {
+                            engine.add_rule(rule);
+                            self.rule_text.clear();
+                            self.rule_status.clear();
+                        }
+                        Err(e) => self.rule_status = e,
+                    }
+                }
+            });
+            if !self.rule_status.is_empty() {
+                ui.colored_label(egui::Color32::RED, &self.rule_status);
+            }
+            ui.separator();
+            let mut jump = None;
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for alert in engine.get_alerts().iter().rev() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::RED, format!("{} {}", convert_time_str(alert.timestamp), alert));
+                        if engine.get_node(&alert.source).is_some() && ui.button("Jump to").clicked() {
+                            jump = Some(alert.source);
+                        }
+                    });
+                }
+            });
+            if let Some(key) = jump {
+                engine.jump_to(&key);
+            }
+            ui.separator();
+            let previous_format = self.alerts_format;
+            egui::ComboBox::from_label("Alerts format")
+                .selected_text(self.alerts_format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in StatsFormat::ALL {
+                        ui.selectable_value(&mut self.alerts_format, format, format.to_string());
+                    }
+                });
+            if self.alerts_format != previous_format {
+                self.alerts_path = Path::new(&self.alerts_path)
+                    .with_extension(self.alerts_format.extension())
+                    .to_string_lossy()
+                    .into_owned();
+            }
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut self.alerts_path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Export alerts").clicked() {
+                    let result = File::create(&self.alerts_path).and_then(|file| {
+                        let mut writer = BufWriter::new(file);
+                        export::write_alerts(&mut writer, self.alerts_format, engine.get_alerts())?;
+                        writer.flush()
+                    });
+                    self.alerts_status = match result {
+                        Ok(()) => format!("Exported {} alerts to {}", engine.get_alerts().len(), self.alerts_path),
+                        Err(e) => format!("Could not export: {}", e),
+                    };
+                }
+                if ui.button("Clear").clicked() {
+                    engine.clear_alerts();
+                }
+            });
+            if !self.alerts_status.is_empty() {
+                ui.label(&self.alerts_status);
+            }
+        });
+    }
+
+    // MAC addresses and DHCP lease learned from ARP and DHCP
+    fn draw_link_info(ui: &mut egui::Ui, data: &NodeData) {
+        if let Some(mac) = data.current_mac() {
+            ui.label(format!("MAC: {}", mac));
+        }
+        if let Some(vendor) = &data.vendor {
+            ui.label(format!("Vendor: {}", vendor));
+        }
+        if data.macs.len() > 1 {
+            let others: Vec<String> = data.macs.iter()
+                .filter(|entry| Some(entry.mac) != data.current_mac())
+                .map(|entry| entry.mac.to_string())
+                .collect();
+            ui.colored_label(egui::Color32::RED, format!("MAC changed, also seen: {}", others.join(", ")));
+        }
+        let Some(dhcp) = &data.dhcp else {
+            return;
+        };
+        if let Some(hostname) = &dhcp.hostname {
+            ui.label(format!("DHCP hostname: {}", hostname));
+        }
+        if let Some(vendor_class) = &dhcp.vendor_class {
+            ui.label(format!("DHCP vendor: {}", vendor_class));
+        }
+        if let Some(lease_time) = dhcp.lease_time {
+            let server = dhcp.server.map(|server| format!(" from {}", server)).unwrap_or_default();
+            ui.label(format!("DHCP lease: {}{}", convert_duration_str(lease_time as u64), server));
+        }
+    }
+
+    // VLANs and MPLS labels the packets of the node were tagged with
+    fn draw_tags(ui: &mut egui::Ui, data: &NodeData) {
+        if let Some(vlans) = data.vlan_label() {
+            ui.label(vlans);
+        }
+        if !data.mpls_labels.is_empty() {
+            let labels: Vec<String> = data.mpls_labels.iter().map(|label| label.to_string()).collect();
+            ui.label(format!("MPLS labels: {}", labels.join(", ")));
+        }
+    }
+
+    // Outer endpoints of the tunnels the packets of the node came through
+    fn draw_tunnels(ui: &mut egui::Ui, data: &NodeData) {
+        for tunnel in &data.tunnels {
+            ui.label(format!("Tunnel: {}", tunnel));
+        }
+    }
+
+    // Location and AS of public addresses, from the GeoIP databases
+    fn draw_geo_info(ui: &mut egui::Ui, data: &NodeData) {
+        let Some(geo) = &data.geo else {
+            return;
+        };
+        if let Some(location) = geo.location() {
+            ui.label(format!("Location: {}", location));
+        }
+        if let Some(as_label) = geo.as_label() {
+            ui.label(format!("AS: {}", as_label));
+        }
+    }
+
+    fn draw_names(ui: &mut egui::Ui, node: &Node) {
+        if !node.get_names().is_empty() {
+            ui.label(format!("Hostnames: {}", node.get_names().join(", ")));
+        }
+        let data = node.get_data();
+        if !data.server_names.is_empty() {
+            ui.label(format!("Server names: {}", data.server_names.join(", ")));
+        }
+    }
+
+    fn draw_node_details(egui_ctx: &egui::Context, engine: &mut Engine, mac_view: bool) {
+        let Some(node) = engine.get_selected_node() else {
+            return;
+        };
+        let data = node.get_data();
+        let mut open = true;
+        egui::Window::new("Node details").open(&mut open).show(egui_ctx, |ui| {
+            if !mac_view {
+                ui.label(format!("IP: {} ({})", data.key(), data.class()));
+                if data.local {
+                    ui.label("Local host");
+                }
+                UI::draw_names(ui, node);
+            }
+            UI::draw_link_info(ui, data);
+            UI::draw_tags(ui, data);
+            UI::draw_tunnels(ui, data);
+            UI::draw_geo_info(ui, data);
+            if let Some(first_seen) = data.first_seen {
+                ui.label(format!("First seen: {}", convert_time_str(first_seen)));
+            }
+            ui.label(format!("Sent: {} packets, {}", data.stats.packet_count_sent, convert_bytes_str(data.stats.packet_bytes_sent)));
+            ui.label(format!("Received: {} packets, {}", data.stats.packet_count_recv, convert_bytes_str(data.stats.packet_bytes_recv)));
+            ui.separator();
+            let flows = engine.get_node_flows(&data.ip);
+            ui.label(format!("Flows: {}", flows.len()));
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for (key, stats) in flows.iter().take(MAX_DETAIL_FLOWS) {
+                    let port = |port: Option<u16>| port.map(|port| format!(":{}", port)).unwrap_or_default();
+                    let address = |ip: &IpAddr| match MacAddress::from_node_ip(ip) {
+                        Some(mac) if mac_view => mac.to_string(),
+                        _ => ip.to_string(),
+                    };
+                    let mut line = format!(
+                        "{}{} -> {}{} ({}) {} packets, {}",
+                        address(&key.source), port(key.source_port), address(&key.dest), port(key.dest_port), key.protocol,
+                        stats.packet_count, convert_bytes_str(stats.packet_bytes),
+                    );
+                    if let Some(name) = &stats.server_name {
+                        line.push_str(&format!(", {}", name));
+                    }
+                    ui.label(line);
+                }
+            });
+        });
+        if !open {
+            engine.select_node(None);
+        }
+    }
 }
@@ -0,0 +1,101 @@
+use std::net::IpAddr;
+use std::time::Duration;
+use network_vizualizer::data::{AddressClass, IPPacketInfo, SearchQuery};
+use network_vizualizer::entities::Entity;
+use network_vizualizer::engine::{DrawCommand, Engine, GraphicsEngine, GroupMode, PhysicsEngine, RecordingRenderer, StaticInput};
+
+mod common;
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+fn packet(source: &str, dest: &str) -> IPPacketInfo {
+    IPPacketInfo::new(ip(source), ip(dest), 100)
+}
+
+fn new_engine(group_mode: GroupMode) -> Engine {
+    let (mut engine, input) = common::new_engine();
+    engine.get_settings().group_mode = group_mode;
+    engine.update(&input);
+    engine
+}
+
+#[test]
+fn addresses_are_classified() {
+    let classes = [
+        ("255.255.255.255", AddressClass::Broadcast),
+        ("224.0.0.251", AddressClass::Multicast),
+        ("ff02::1", AddressClass::Multicast),
+        ("169.254.10.1", AddressClass::LinkLocal),
+        ("fe80::1", AddressClass::LinkLocal),
+        ("0.0.0.0", AddressClass::LinkLocal),
+        ("127.0.0.1", AddressClass::Loopback),
+        ("::1", AddressClass::Loopback),
+        ("192.168.1.1", AddressClass::Private),
+        ("100.64.0.1", AddressClass::Private),
+        ("fd00::1", AddressClass::Private),
+        ("::ffff:10.0.0.1", AddressClass::Private),
+        ("8.8.8.8", AddressClass::Public),
+        ("2001:4860:4860::8888", AddressClass::Public),
+    ];
+    for (address, class) in classes {
+        assert_eq!(AddressClass::of(&ip(address)), class, "{}", address);
+    }
+    assert_eq!("Link-Local".parse::<AddressClass>(), Ok(AddressClass::LinkLocal));
+    assert_eq!(SearchQuery::parse("class: multicast"), SearchQuery::Class(AddressClass::Multicast));
+}
+
+#[test]
+fn group_destinations_are_buses_with_spread_landing_points() {
+    let mut engine = new_engine(GroupMode::Bus);
+    engine.add_packet(&packet("10.0.0.1", "224.0.0.251"));
+    let bus = engine.get_node(&ip("224.0.0.251")).unwrap();
+    assert!(bus.is_bus());
+    assert!(!engine.get_node(&ip("10.0.0.1")).unwrap().is_bus());
+
+    let landing = engine.get_packets().next().unwrap().get_destination();
+    let (source, bus) = (*engine.get_node(&ip("10.0.0.1")).unwrap().get_position(), *bus.get_position());
+    assert_eq!(landing.y, bus.y);
+    assert_eq!(landing.x, source.x.clamp(bus.x - 40.0, bus.x + 40.0));
+
+    engine.get_settings().group_mode = GroupMode::Node;
+    engine.update(&StaticInput::new(800.0, 600.0));
+    assert!(!engine.get_node(&ip("224.0.0.251")).unwrap().is_bus());
+}
+
+#[test]
+fn bursts_fan_out_to_the_local_nodes_of_the_same_ip_version() {
+    let mut engine = new_engine(GroupMode::Burst);
+    engine.add_packet(&packet("192.168.1.2", "192.168.1.3"));
+    engine.add_packet(&packet("192.168.1.4", "8.8.8.8"));
+    engine.add_packet(&packet("fe80::1", "fe80::2"));
+    let before = engine.get_visible_packet_count();
+
+    engine.add_packet(&packet("192.168.1.2", "255.255.255.255"));
+    let burst: Vec<IpAddr> = engine.get_packets().skip(before).map(|packet| packet.get_dest_node().ip).collect();
+    assert_eq!(burst, vec![ip("192.168.1.3"), ip("192.168.1.4")]);
+    // The group node keeps the statistics but is not drawn
+    let broadcast = engine.get_node(&ip("255.255.255.255")).unwrap();
+    assert_eq!(broadcast.get_data().stats.packet_count_recv, 1);
+    assert!(!engine.is_node_shown(&broadcast.get_data().key()));
+}
+
+#[test]
+fn hidden_classes_are_not_drawn() {
+    let mut engine = new_engine(GroupMode::Node);
+    // The halos of the new nodes are circles too
+    engine.get_settings().new_highlight = Duration::ZERO;
+    engine.add_packet(&packet("10.0.0.1", "8.8.8.8"));
+    engine.add_packet(&packet("10.0.0.1", "10.0.0.2"));
+    engine.get_settings().hidden_classes = vec![AddressClass::Public];
+    // Packets to hidden nodes are not launched
+    engine.add_packet(&packet("10.0.0.2", "8.8.4.4"));
+    assert_eq!(engine.get_visible_packet_count(), 2);
+
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    let circles = renderer.commands.iter().filter(|command| matches!(command, DrawCommand::Circle { .. })).count();
+    // Two private nodes and the packet between them
+    assert_eq!(circles, 3);
+}
@@ -0,0 +1,34 @@
+// Fixtures shared by the integration tests, each test file only uses some of them
+#![allow(dead_code)]
+
+use std::net::{IpAddr, Ipv4Addr};
+use network_vizualizer::data::{IPPacketInfo, NodeKey};
+use network_vizualizer::engine::{Engine, PhysicsEngine, StaticInput};
+use network_vizualizer::entities::Entity;
+use network_vizualizer::math::Point;
+
+pub const WIDTH: f32 = 800.0;
+pub const HEIGHT: f32 = 600.0;
+
+pub fn ip(last: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+}
+
+pub fn key(last: u8) -> NodeKey {
+    ip(last).into()
+}
+
+pub fn packet(source: u8, dest: u8) -> IPPacketInfo {
+    IPPacketInfo::new(ip(source), ip(dest), 100)
+}
+
+pub fn new_engine() -> (Engine, StaticInput) {
+    let mut engine = Engine::with_seed(42);
+    let input = StaticInput::new(WIDTH, HEIGHT);
+    engine.update(&input);
+    (engine, input)
+}
+
+pub fn node_position(engine: &Engine, ip: &IpAddr) -> Point {
+    *engine.get_nodes().find(|node| node.get_data().ip == *ip).unwrap().get_position()
+}
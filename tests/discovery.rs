@@ -0,0 +1,98 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use network_vizualizer::data::{parse_arp, parse_dhcp, DiscoverySource, HostDiscovery, MacAddress, NodeData, TrafficGraph};
+
+const MAC: MacAddress = MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+const OTHER_MAC: MacAddress = MacAddress([0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb]);
+
+fn ip(last: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(192, 168, 1, last))
+}
+
+fn time(second: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(second)
+}
+
+fn arp(sender_ip: [u8; 4]) -> Vec<u8> {
+    let mut arp = vec![0, 1, 8, 0, 6, 4, 0, 2];
+    arp.extend_from_slice(&MAC.0);
+    arp.extend_from_slice(&sender_ip);
+    arp.extend_from_slice(&[0; 6]);
+    arp.extend_from_slice(&[192, 168, 1, 1]);
+    arp
+}
+
+fn dhcp(your_ip: [u8; 4], options: &[u8]) -> Vec<u8> {
+    let mut dhcp = vec![2, 1, 6, 0];
+    dhcp.extend_from_slice(&[0; 12]);
+    dhcp.extend_from_slice(&your_ip);
+    dhcp.extend_from_slice(&[0; 8]);
+    dhcp.extend_from_slice(&MAC.0);
+    dhcp.resize(236, 0);
+    dhcp.extend_from_slice(&[99, 130, 83, 99]);
+    dhcp.extend_from_slice(options);
+    dhcp.push(255);
+    dhcp
+}
+
+fn discovery(mac: MacAddress, second: u64) -> HostDiscovery {
+    HostDiscovery { ip: ip(10), mac, source: DiscoverySource::Arp, dhcp: None, timestamp: time(second), vlan: None }
+}
+
+#[test]
+fn arp_gives_the_sender_address() {
+    let discovery = parse_arp(&arp([192, 168, 1, 10]), time(1)).unwrap();
+    assert_eq!(discovery.ip, ip(10));
+    assert_eq!(discovery.mac, MAC);
+    assert_eq!(discovery.mac.to_string(), "00:11:22:33:44:55");
+    // Probes have no sender address
+    assert!(parse_arp(&arp([0; 4]), time(1)).is_none());
+}
+
+#[test]
+fn dhcp_ack_gives_the_lease() {
+    let options = [
+        53, 1, 5,
+        51, 4, 0, 0, 0x0e, 0x10,
+        54, 4, 192, 168, 1, 1,
+    ];
+    let discovery = parse_dhcp(&dhcp([192, 168, 1, 10], &options), time(1)).unwrap();
+    assert_eq!(discovery.ip, ip(10));
+    assert_eq!(discovery.mac, MAC);
+    let info = discovery.dhcp.unwrap();
+    assert_eq!(info.lease_time, Some(3600));
+    assert_eq!(info.server, Some(ip(1)));
+}
+
+#[test]
+fn dhcp_request_gives_hostname_and_vendor() {
+    let mut options = vec![53, 1, 3, 50, 4, 192, 168, 1, 10, 12, 6];
+    options.extend_from_slice(b"laptop");
+    options.extend_from_slice(&[60, 8]);
+    options.extend_from_slice(b"MSFT 5.0");
+    let discovery = parse_dhcp(&dhcp([0; 4], &options), time(1)).unwrap();
+    assert_eq!(discovery.ip, ip(10));
+    let info = discovery.dhcp.unwrap();
+    assert_eq!(info.hostname.as_deref(), Some("laptop"));
+    assert_eq!(info.vendor_class.as_deref(), Some("MSFT 5.0"));
+    // Discover messages have no address yet
+    assert!(parse_dhcp(&dhcp([0; 4], &[53, 1, 1]), time(1)).is_none());
+}
+
+#[test]
+fn mac_changes_are_detected() {
+    let mut node = NodeData::new(ip(10));
+    assert!(node.add_discovery(&discovery(MAC, 1)).is_none());
+    assert!(node.add_discovery(&discovery(MAC, 2)).is_none());
+    let change = node.add_discovery(&discovery(OTHER_MAC, 3)).unwrap();
+    assert_eq!((change.old, change.new), (MAC, OTHER_MAC));
+    assert_eq!(node.current_mac(), Some(OTHER_MAC));
+    assert_eq!(node.macs.len(), 2);
+}
+
+#[test]
+fn discovered_hosts_get_a_node() {
+    let mut graph = TrafficGraph::new();
+    graph.add_discovery(&discovery(MAC, 1));
+    assert_eq!(graph.get_node(&ip(10)).unwrap().current_mac(), Some(MAC));
+}
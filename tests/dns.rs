@@ -0,0 +1,95 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use network_vizualizer::data::{parse_response, parse_tcp_response, DnsRecord};
+
+fn name(name: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for label in name.split('.') {
+        bytes.push(label.len() as u8);
+        bytes.extend_from_slice(label.as_bytes());
+    }
+    bytes.push(0);
+    bytes
+}
+
+fn record(owner: &[u8], record_type: u16, ttl: u32, data: &[u8]) -> Vec<u8> {
+    let mut bytes = owner.to_vec();
+    bytes.extend_from_slice(&record_type.to_be_bytes());
+    bytes.extend_from_slice(&1u16.to_be_bytes());
+    bytes.extend_from_slice(&ttl.to_be_bytes());
+    bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+// Response to a question on `question` with the given answers
+fn response(flags: u16, question: &str, answers: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![0x12, 0x34];
+    bytes.extend_from_slice(&flags.to_be_bytes());
+    bytes.extend_from_slice(&1u16.to_be_bytes());
+    bytes.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&[0, 0, 0, 0]);
+    bytes.extend_from_slice(&name(question));
+    bytes.extend_from_slice(&[0, 1, 0, 1]);
+    for answer in answers {
+        bytes.extend_from_slice(answer);
+    }
+    bytes
+}
+
+// Pointer to the question name, right after the header
+const QUESTION_POINTER: [u8; 2] = [0xc0, 12];
+
+#[test]
+fn a_and_aaaa_records_are_read() {
+    let message = response(0x8180, "example.com", &[
+        record(&QUESTION_POINTER, 1, 300, &[93, 184, 216, 34]),
+        record(&QUESTION_POINTER, 28, 60, &Ipv6Addr::LOCALHOST.octets()),
+    ]);
+    assert_eq!(parse_response(&message), vec![
+        DnsRecord { ip: IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)), name: "example.com".to_string(), ttl: 300 },
+        DnsRecord { ip: IpAddr::V6(Ipv6Addr::LOCALHOST), name: "example.com".to_string(), ttl: 60 },
+    ]);
+}
+
+#[test]
+fn cname_chains_give_the_asked_name() {
+    let message = response(0x8180, "www.example.com", &[
+        record(&QUESTION_POINTER, 5, 300, &name("cdn.example.net")),
+        record(&name("cdn.example.net"), 1, 20, &[10, 0, 0, 1]),
+    ]);
+    let records = parse_response(&message);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].name, "www.example.com");
+}
+
+#[test]
+fn ptr_records_name_the_reversed_address() {
+    let message = response(0x8400, "1.0.168.192.in-addr.arpa", &[
+        record(&QUESTION_POINTER, 12, 120, &name("printer.local")),
+    ]);
+    assert_eq!(parse_response(&message), vec![
+        DnsRecord { ip: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)), name: "printer.local".to_string(), ttl: 120 },
+    ]);
+}
+
+#[test]
+fn queries_errors_and_garbage_give_nothing() {
+    let answer = record(&QUESTION_POINTER, 1, 300, &[10, 0, 0, 1]);
+    assert!(parse_response(&response(0x0100, "example.com", std::slice::from_ref(&answer))).is_empty());
+    assert!(parse_response(&response(0x8183, "example.com", &[answer])).is_empty());
+    assert!(parse_response(&[0xff; 5]).is_empty());
+    // Question name pointing to itself
+    let mut looping = response(0x8180, "example.com", &[]);
+    looping[12] = 0xc0;
+    looping[13] = 12;
+    assert!(parse_response(&looping).is_empty());
+}
+
+#[test]
+fn tcp_messages_are_length_prefixed() {
+    let message = response(0x8180, "example.com", &[record(&QUESTION_POINTER, 1, 300, &[10, 0, 0, 1])]);
+    let mut payload = (message.len() as u16).to_be_bytes().to_vec();
+    payload.extend_from_slice(&message);
+    assert_eq!(parse_tcp_response(&payload).len(), 1);
+    assert!(parse_tcp_response(&payload[..payload.len() - 1]).is_empty());
+}
@@ -0,0 +1,274 @@
+use std::time::UNIX_EPOCH;
+use network_vizualizer::data::{DiscoverySource, DnsRecord, HostDiscovery, OuiTable};
+use network_vizualizer::engine::{Color, ColorMode, DrawCommand, GraphicsEngine, PhysicsEngine, RecordingRenderer, Session, SessionError, ORANGE, RED, SESSION_VERSION, YELLOW};
+use network_vizualizer::entities::Entity;
+use network_vizualizer::math::Point;
+use common::{ip, key, new_engine, node_position, packet, HEIGHT, WIDTH};
+
+mod common;
+
+fn circles(renderer: &RecordingRenderer) -> Vec<(Point, f32, Color)> {
+    renderer.commands.iter().filter_map(|command| match command {
+        DrawCommand::Circle { center, radius, color } => Some((*center, *radius, *color)),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn add_packet_creates_nodes_and_packet() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+
+    assert_eq!(engine.get_nodes().count(), 2);
+    assert_eq!(engine.get_visible_packet_count(), 1);
+    for node in engine.get_nodes() {
+        let pos = node.get_position();
+        assert!((0.0..=WIDTH).contains(&pos.x) && (0.0..=HEIGHT).contains(&pos.y));
+    }
+    let source = engine.get_nodes().find(|node| node.get_data().ip == ip(1)).unwrap();
+    assert_eq!(source.get_data().stats.packet_count_sent, 1);
+    assert_eq!(source.get_data().stats.packet_bytes_sent, 100);
+}
+
+#[test]
+fn add_packet_respects_max_packets() {
+    let (mut engine, _) = new_engine();
+    engine.get_settings().max_packets = 3;
+    for _ in 0..10 {
+        engine.add_packet(&packet(1, 2));
+    }
+    assert_eq!(engine.get_nodes().count(), 2);
+    // The limit is checked before inserting, so one packet goes over it
+    assert_eq!(engine.get_visible_packet_count(), 4);
+}
+
+#[test]
+fn update_removes_packets_reaching_destination() {
+    let (mut engine, input) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    for _ in 0..10000 {
+        engine.update(&input);
+        if engine.get_visible_packet_count() == 0 {
+            return;
+        }
+    }
+    panic!("packet never reached its destination");
+}
+
+#[test]
+fn update_does_nothing_when_stopped() {
+    let (mut engine, input) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    engine.get_settings().update = false;
+
+    let mut before = RecordingRenderer::new();
+    engine.draw(&mut before);
+    for _ in 0..100 {
+        engine.update(&input);
+    }
+    let mut after = RecordingRenderer::new();
+    engine.draw(&mut after);
+    assert_eq!(before.commands, after.commands);
+}
+
+#[test]
+fn draw_records_nodes_and_packets() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    let circles = circles(&renderer);
+    // Both nodes are new and drawn over a halo
+    assert_eq!(circles.len(), 5);
+    assert_eq!(circles.iter().filter(|(_, _, color)| *color == RED).count(), 1);
+}
+
+#[test]
+fn dragging_moves_node() {
+    let (mut engine, mut input) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    let start = node_position(&engine, &ip(1));
+
+    input.mouse_position = start;
+    input.mouse_down = true;
+    engine.update(&input);
+    input.mouse_position = Point { x: 10.0, y: 20.0 };
+    engine.update(&input);
+
+    assert_eq!(node_position(&engine, &ip(1)), Point { x: 10.0, y: 20.0 });
+}
+
+#[test]
+fn search_highlights_matching_nodes() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    engine.add_packet(&packet(3, 2));
+    engine.set_search("10.0.0.0/31");
+    assert_eq!(engine.get_search_matches(), &vec![key(1)]);
+
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    let highlighted: Vec<Point> = circles(&renderer)
+        .into_iter()
+        .filter(|(_, _, color)| *color == ORANGE)
+        .map(|(center, _, _)| center)
+        .collect();
+    assert_eq!(highlighted, vec![node_position(&engine, &ip(1))]);
+}
+
+#[test]
+fn pinned_node_is_not_dragged() {
+    let (mut engine, mut input) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    let start = node_position(&engine, &ip(1));
+
+    input.mouse_position = start;
+    input.secondary_clicked = true;
+    engine.update(&input);
+    input.secondary_clicked = false;
+    input.mouse_down = true;
+    engine.update(&input);
+    input.mouse_position = Point { x: 10.0, y: 20.0 };
+    engine.update(&input);
+
+    assert_eq!(node_position(&engine, &ip(1)), start);
+}
+
+#[test]
+fn session_round_trip() {
+    let (mut engine, mut input) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    engine.add_packet(&packet(2, 1));
+    engine.add_packet(&packet(3, 1));
+    engine.get_settings().max_packets = 42;
+    input.mouse_position = node_position(&engine, &ip(3));
+    input.secondary_clicked = true;
+    engine.update(&input);
+
+    let path = std::env::temp_dir().join(format!("network_vizualizer_session_{}.json", std::process::id()));
+    engine.to_session().save(&path).unwrap();
+    let session = Session::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let (mut loaded, _) = new_engine();
+    loaded.load_session(session);
+    assert_eq!(loaded.get_settings().max_packets, 42);
+    assert_eq!(loaded.get_visible_packet_count(), 0);
+    assert_eq!(loaded.get_flows().len(), 3);
+    for node in engine.get_nodes() {
+        let ip = node.get_data().ip;
+        let other = loaded.get_nodes().find(|other| other.get_data().ip == ip).unwrap();
+        assert_eq!(other.get_position(), node.get_position());
+        assert_eq!(other.is_pinned(), ip == self::ip(3));
+        assert_eq!(other.get_data().stats.packet_count_sent, node.get_data().stats.packet_count_sent);
+        assert_eq!(other.get_data().sent_to.len(), node.get_data().sent_to.len());
+        assert_eq!(other.get_data().recv_from.len(), node.get_data().recv_from.len());
+    }
+}
+
+#[test]
+fn first_version_sessions_are_upgraded_and_unknown_ones_refused() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    let mut value = serde_json::to_value(engine.to_session()).unwrap();
+    assert_eq!(value["version"], SESSION_VERSION);
+    let path = std::env::temp_dir().join(format!("network_vizualizer_version_{}.json", std::process::id()));
+
+    // The first version had no per VLAN flag nor first seen time
+    value["version"] = 1.into();
+    value.as_object_mut().unwrap().remove("per_vlan");
+    for node in value["nodes"].as_array_mut().unwrap() {
+        node["data"].as_object_mut().unwrap().remove("first_seen");
+    }
+    std::fs::write(&path, value.to_string()).unwrap();
+    let session = Session::load(&path).unwrap();
+    assert_eq!(session.version, SESSION_VERSION);
+    assert!(!session.per_vlan);
+    assert_eq!(session.nodes.len(), 2);
+
+    value["version"] = (SESSION_VERSION + 1).into();
+    std::fs::write(&path, value.to_string()).unwrap();
+    assert!(matches!(Session::load(&path), Err(SessionError::UnsupportedVersion(version)) if version == SESSION_VERSION + 1));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn dns_responses_name_nodes() {
+    let (mut engine, _) = new_engine();
+    let mut response = packet(53, 1);
+    response.dns_records.push(DnsRecord { ip: ip(2), name: "server.example.com".to_string(), ttl: 300 });
+    engine.add_packet(&response);
+    engine.add_packet(&packet(1, 2));
+
+    let node = engine.get_nodes().find(|node| node.get_data().ip == ip(2)).unwrap();
+    assert_eq!(node.get_label(), Some("server.example.com"));
+
+    engine.set_search("example.com");
+    assert_eq!(engine.get_search_matches(), &vec![key(2)]);
+
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    assert!(renderer.commands.iter().any(|command| matches!(command, DrawCommand::Text { text, .. } if text == "server.example.com")));
+}
+
+#[test]
+fn reverse_names_come_after_passive_ones() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    engine.add_reverse_names(&[DnsRecord { ip: ip(2), name: "ptr.example.net".to_string(), ttl: 300 }]);
+    assert_eq!(engine.get_node(&ip(2)).unwrap().get_label(), Some("ptr.example.net"));
+
+    let mut response = packet(53, 1);
+    response.dns_records.push(DnsRecord { ip: ip(2), name: "www.example.com".to_string(), ttl: 300 });
+    engine.add_packet(&response);
+    assert_eq!(engine.get_node(&ip(2)).unwrap().get_names(), &vec!["www.example.com".to_string(), "ptr.example.net".to_string()]);
+}
+
+#[test]
+fn server_names_label_and_match_the_destination() {
+    let (mut engine, _) = new_engine();
+    let mut hello = packet(1, 2);
+    hello.server_name = Some("cdn.example.com".to_string());
+    engine.add_packet(&hello);
+
+    let node = engine.get_node(&ip(2)).unwrap();
+    assert_eq!(node.get_data().server_names, vec!["cdn.example.com".to_string()]);
+    assert_eq!(node.get_label(), Some("cdn.example.com"));
+    assert!(engine.get_node(&ip(1)).unwrap().get_data().server_names.is_empty());
+    assert_eq!(engine.get_node_flows(&ip(2))[0].1.server_name.as_deref(), Some("cdn.example.com"));
+
+    engine.set_search("cdn.example");
+    assert_eq!(engine.get_search_matches(), &vec![key(2)]);
+}
+
+#[test]
+fn nodes_are_colored_and_searched_by_vendor() {
+    let (mut engine, input) = new_engine();
+    let discovery = |last: u8, mac: &str| HostDiscovery {
+        ip: ip(last),
+        mac: mac.parse().unwrap(),
+        source: DiscoverySource::Arp,
+        dhcp: None,
+        timestamp: UNIX_EPOCH,
+        vlan: None,
+    };
+    engine.set_oui_table(OuiTable::parse("00:11:22 Example Networks\n00:33:44 Other Vendor\n"));
+    engine.add_discovery(&discovery(1, "00:11:22:00:00:01"));
+    engine.add_discovery(&discovery(2, "00:11:22:00:00:02"));
+    engine.add_discovery(&discovery(3, "00:33:44:00:00:03"));
+    engine.add_packet(&packet(1, 4));
+    assert_eq!(engine.get_node(&ip(1)).unwrap().get_data().vendor.as_deref(), Some("Example Networks"));
+    assert_eq!(engine.get_node(&ip(4)).unwrap().get_color(), YELLOW);
+
+    engine.get_settings().color_by = ColorMode::Vendor;
+    engine.update(&input);
+    let color = engine.get_node(&ip(1)).unwrap().get_color();
+    assert_ne!(color, YELLOW);
+    assert_eq!(engine.get_node(&ip(2)).unwrap().get_color(), color);
+    assert_ne!(engine.get_node(&ip(3)).unwrap().get_color(), color);
+    assert_eq!(engine.get_color_legend()[0], ("Example Networks".to_string(), color, 2));
+
+    engine.set_search("vendor:example");
+    assert_eq!(*engine.get_search_matches(), vec![key(1), key(2)]);
+}
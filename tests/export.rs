@@ -0,0 +1,157 @@
+use std::time::{Duration, UNIX_EPOCH};
+use network_vizualizer::data::{Alert, AlertKind, GeoInfo, IPPacketInfo, TimeWindow, TrafficGraph};
+use network_vizualizer::export::{write_alerts, write_graph, write_node_stats, write_pair_stats, ExportNode, GraphFormat, StatsFormat};
+use network_vizualizer::math::Point;
+use common::{ip, key};
+
+mod common;
+
+fn graph() -> TrafficGraph {
+    let mut graph = TrafficGraph::new();
+    graph.add_packet(&IPPacketInfo::new(ip(1), ip(2), 100));
+    graph.add_packet(&IPPacketInfo::new(ip(1), ip(2), 50));
+    graph.add_packet(&IPPacketInfo::new(ip(2), ip(3), 10));
+    graph
+}
+
+fn export(format: GraphFormat, nodes: &[ExportNode]) -> String {
+    let mut output = Vec::new();
+    write_graph(&mut output, format, nodes).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn dot_contains_nodes_and_edges() {
+    let graph = graph();
+    let dot = export(GraphFormat::Dot, &graph.export_nodes());
+    assert!(dot.starts_with("digraph network {"));
+    assert!(dot.contains(r#""10.0.0.1" [packets_sent=2, bytes_sent=150, packets_recv=0, bytes_recv=0, class="private", local="false"];"#));
+    assert!(dot.contains(r#""10.0.0.1" -> "10.0.0.2" [packets=2, bytes=150, weight=2];"#));
+    assert!(dot.contains(r#""10.0.0.2" -> "10.0.0.3" [packets=1, bytes=10, weight=1];"#));
+    assert!(!dot.contains(r#""10.0.0.2" -> "10.0.0.1""#));
+}
+
+#[test]
+fn graphml_contains_positions_when_known() {
+    let graph = graph();
+    let data = graph.get_node(&ip(1)).unwrap();
+    let nodes = [ExportNode { data, position: Some(Point { x: 12.0, y: 34.0 }), names: &[] }];
+    let graphml = export(GraphFormat::GraphML, &nodes);
+    assert!(graphml.contains(r#"<node id="10.0.0.1">"#));
+    assert!(graphml.contains(r#"<data key="x">12</data>"#));
+    assert!(graphml.contains(r#"<edge source="10.0.0.1" target="10.0.0.2">"#));
+    assert!(graphml.ends_with("</graphml>\n"));
+}
+
+#[test]
+fn gexf_flips_y_axis() {
+    let graph = graph();
+    let data = graph.get_node(&ip(2)).unwrap();
+    let nodes = [ExportNode { data, position: Some(Point { x: 1.0, y: 2.0 }), names: &[] }];
+    let gexf = export(GraphFormat::Gexf, &nodes);
+    assert!(gexf.contains(r#"<viz:position x="1" y="-2" z="0.0"/>"#));
+}
+
+#[test]
+fn nodes_carry_their_names_vendor_location_and_class() {
+    let graph = graph();
+    let mut data = graph.get_node(&ip(1)).unwrap().clone();
+    data.vendor = Some(r#"Example "Labs""#.to_string());
+    data.server_names.push("cdn.example.com".to_string());
+    data.geo = Some(GeoInfo {
+        country: Some("FR".to_string()),
+        city: Some("Paris".to_string()),
+        asn: Some(64500),
+        as_organization: Some("Example Networks".to_string()),
+        ..GeoInfo::default()
+    });
+    let names = [r"host\1.example.com".to_string(), "alias.example.com".to_string()];
+    let nodes = [ExportNode { data: &data, position: None, names: &names }];
+
+    let dot = export(GraphFormat::Dot, &nodes);
+    assert!(dot.contains(r#"names="host\\1.example.com, alias.example.com", server_names="cdn.example.com", vendor="Example \"Labs\"", "#));
+    assert!(dot.contains(r#"country="FR", city="Paris", as="AS64500 Example Networks", class="private", local="false"];"#));
+    assert!(!dot.contains("mac="));
+
+    let graphml = export(GraphFormat::GraphML, &nodes);
+    assert!(graphml.contains(r#"<key id="vendor" for="node" attr.name="vendor" attr.type="string"/>"#));
+    assert!(graphml.contains(r#"<data key="vendor">Example &quot;Labs&quot;</data>"#));
+    let gexf = export(GraphFormat::Gexf, &nodes);
+    assert!(gexf.contains(r#"<attvalue for="as" value="AS64500 Example Networks"/>"#));
+}
+
+#[test]
+fn csv_fields_with_separators_are_quoted() {
+    let alert = Alert {
+        timestamp: UNIX_EPOCH,
+        kind: AlertKind::Rule,
+        source: key(1),
+        target: None,
+        port: None,
+        protocol: 6,
+        count: 1,
+        rule: Some(r#"port:23 "telnet", again"#.to_string()),
+        actions: Vec::new(),
+    };
+    let mut output = Vec::new();
+    write_alerts(&mut output, StatsFormat::Csv, &[alert]).unwrap();
+    assert!(String::from_utf8(output).unwrap().ends_with(r#",6,1,"port:23 ""telnet"", again"
+"#));
+}
+
+fn packet_at(source: u8, dest: u8, payload_len: u16, second: u64) -> IPPacketInfo {
+    let mut packet = IPPacketInfo::new(ip(source), ip(dest), payload_len);
+    packet.timestamp = UNIX_EPOCH + Duration::from_secs(second);
+    packet
+}
+
+#[test]
+fn stats_csv_lists_nodes_and_pairs() {
+    let nodes = graph().window_nodes(TimeWindow::All);
+    let mut output = Vec::new();
+    write_node_stats(&mut output, StatsFormat::Csv, &nodes).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\
+ip,packets_sent,bytes_sent,packets_recv,bytes_recv
+10.0.0.1,2,150,0,0
+10.0.0.2,1,10,2,150
+10.0.0.3,0,0,1,10
+");
+    let mut output = Vec::new();
+    write_pair_stats(&mut output, StatsFormat::Csv, &nodes).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\
+source,dest,packets,bytes
+10.0.0.1,10.0.0.2,2,150
+10.0.0.2,10.0.0.3,1,10
+");
+}
+
+#[test]
+fn stats_json_lines_have_one_object_per_pair() {
+    let nodes = graph().window_nodes(TimeWindow::All);
+    let mut output = Vec::new();
+    write_pair_stats(&mut output, StatsFormat::Jsonl, &nodes).unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(output).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["source"], "10.0.0.1");
+    assert_eq!(lines[0]["bytes"], 150);
+}
+
+#[test]
+fn stats_window_keeps_only_recent_packets() {
+    let mut graph = TrafficGraph::new();
+    graph.add_packet(&packet_at(1, 2, 100, 1_000));
+    graph.add_packet(&packet_at(1, 3, 40, 1_050));
+    graph.add_packet(&packet_at(1, 3, 60, 1_059));
+
+    let nodes = graph.window_nodes(TimeWindow::Last(Duration::from_secs(10)));
+    let source = nodes.iter().find(|node| node.ip == ip(1)).unwrap();
+    assert_eq!(source.stats.packet_count_sent, 2);
+    assert_eq!(source.stats.packet_bytes_sent, 100);
+    assert!(!nodes.iter().any(|node| node.ip == ip(2)));
+
+    let start = UNIX_EPOCH + Duration::from_secs(1_000);
+    let nodes = graph.window_nodes(TimeWindow::Range(start, start));
+    assert_eq!(nodes.len(), 2);
+}
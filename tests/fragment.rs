@@ -0,0 +1,118 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use network_vizualizer::data::{parse_fragment, Reassembler};
+
+const UDP: u8 = 17;
+
+fn time(second: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(second)
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+// IPv4 fragment of 10.0.0.1 -> 10.0.0.2, the offset in bytes
+fn ipv4_fragment(id: u16, offset: usize, more: bool, data: &[u8]) -> Vec<u8> {
+    let flags = (offset / 8) as u16 | if more { 0x2000 } else { 0 };
+    let mut packet = vec![0x45, 0];
+    packet.extend_from_slice(&((20 + data.len()) as u16).to_be_bytes());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&flags.to_be_bytes());
+    packet.extend_from_slice(&[64, UDP, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2]);
+    packet.extend_from_slice(data);
+    packet
+}
+
+// IPv6 fragment with a hop-by-hop options header before the fragment header
+fn ipv6_fragment(offset: usize, more: bool, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x60, 0, 0, 0];
+    packet.extend_from_slice(&((8 + 8 + data.len()) as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 64]);
+    packet.extend_from_slice(&[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    packet.extend_from_slice(&[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    packet.extend_from_slice(&[44, 0, 1, 4, 0, 0, 0, 0]);
+    packet.push(UDP);
+    packet.push(0);
+    packet.extend_from_slice(&(offset as u16 | more as u16).to_be_bytes());
+    packet.extend_from_slice(&7u32.to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+#[test]
+fn whole_packets_are_not_fragments() {
+    assert!(parse_fragment(&ipv4_fragment(1, 0, false, &payload(8))).is_none());
+    // Atomic fragment, the only fragment of the datagram
+    assert!(parse_fragment(&ipv6_fragment(0, false, &payload(8))).is_none());
+    assert!(parse_fragment(&[0; 10]).is_none());
+
+    let fragment = ipv6_fragment(16, true, &payload(8));
+    let fragment = parse_fragment(&fragment).unwrap();
+    assert_eq!((fragment.offset, fragment.more, fragment.key.protocol, fragment.key.id), (16, true, UDP, 7));
+    assert_eq!(fragment.data, &payload(8)[..]);
+}
+
+#[test]
+fn ipv4_fragments_are_reassembled_in_any_order() {
+    let data = payload(40);
+    let fragments = [
+        ipv4_fragment(1, 16, true, &data[16..32]),
+        ipv4_fragment(1, 32, false, &data[32..]),
+        ipv4_fragment(1, 0, true, &data[..16]),
+    ];
+    let mut reassembler = Reassembler::new();
+    let mut packets = fragments.iter().filter_map(|fragment| reassembler.add(&parse_fragment(fragment).unwrap(), time(0)));
+    let packet = packets.next().unwrap();
+    assert!(packets.next().is_none());
+
+    assert_eq!(&packet[20..], &data[..]);
+    assert_eq!(u16::from_be_bytes([packet[2], packet[3]]), 60);
+    assert_eq!(&packet[6..8], &[0, 0]);
+    // The checksum of a valid header sums to 0xffff
+    let sum: u32 = packet[..20].chunks(2).map(|word| u16::from_be_bytes([word[0], word[1]]) as u32).sum();
+    assert_eq!((sum & 0xffff) + (sum >> 16), 0xffff);
+    assert_eq!((reassembler.pending(), reassembler.bytes()), (0, 0));
+}
+
+#[test]
+fn datagrams_too_long_for_the_length_field_are_dropped() {
+    // 65528 bytes of data fit the offsets but not the 16 bits total length with the header
+    let data = payload(65528);
+    let mut reassembler = Reassembler::new();
+    assert!(reassembler.add(&parse_fragment(&ipv4_fragment(1, 0, true, &data[..32768])).unwrap(), time(0)).is_none());
+    assert!(reassembler.add(&parse_fragment(&ipv4_fragment(1, 32768, false, &data[32768..])).unwrap(), time(0)).is_none());
+    assert_eq!((reassembler.pending(), reassembler.bytes()), (0, 0));
+}
+
+#[test]
+fn ipv6_fragment_header_is_taken_out_of_the_reassembled_packet() {
+    let data = payload(24);
+    let mut reassembler = Reassembler::new();
+    assert!(reassembler.add(&parse_fragment(&ipv6_fragment(0, true, &data[..16])).unwrap(), time(0)).is_none());
+    let packet = reassembler.add(&parse_fragment(&ipv6_fragment(16, false, &data[16..])).unwrap(), time(0)).unwrap();
+
+    // The hop-by-hop header now points at UDP
+    assert_eq!(packet[6], 0);
+    assert_eq!(packet[40], UDP);
+    assert_eq!(u16::from_be_bytes([packet[4], packet[5]]), 8 + 24);
+    assert_eq!(&packet[48..], &data[..]);
+}
+
+#[test]
+fn incomplete_datagrams_expire_and_memory_is_bounded() {
+    let data = payload(64);
+    let mut reassembler = Reassembler::with_limits(Duration::from_secs(10), 100);
+    reassembler.add(&parse_fragment(&ipv4_fragment(1, 0, true, &data[..48])).unwrap(), time(0));
+    assert_eq!((reassembler.pending(), reassembler.bytes()), (1, 48));
+    reassembler.expire(time(11));
+    assert_eq!(reassembler.pending(), 0);
+
+    // The oldest datagram makes room for the new one
+    reassembler.add(&parse_fragment(&ipv4_fragment(2, 0, true, &data[..48])).unwrap(), time(20));
+    reassembler.add(&parse_fragment(&ipv4_fragment(3, 0, true, &data[..48])).unwrap(), time(21));
+    reassembler.add(&parse_fragment(&ipv4_fragment(4, 0, true, &data[..48])).unwrap(), time(22));
+    assert_eq!((reassembler.pending(), reassembler.bytes()), (2, 96));
+    // The last fragment of the first datagram comes too late
+    assert!(reassembler.add(&parse_fragment(&ipv4_fragment(2, 48, false, &data[48..])).unwrap(), time(23)).is_none());
+    assert!(reassembler.add(&parse_fragment(&ipv4_fragment(4, 48, false, &data[48..])).unwrap(), time(23)).is_some());
+}
@@ -0,0 +1,103 @@
+use std::time::UNIX_EPOCH;
+use network_vizualizer::data::{parse_ethernet, MacAddress, NodeKey, OuiTable};
+use network_vizualizer::data::AddressClass;
+use network_vizualizer::engine::{ColorMode, Engine, YELLOW};
+use network_vizualizer::export::{write_graph, write_node_stats, write_pair_stats, GraphFormat, StatsFormat};
+
+const SOURCE: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+const DEST: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+
+fn frame(tags: &[u8], ether_type: u16) -> Vec<u8> {
+    let mut frame = DEST.to_vec();
+    frame.extend_from_slice(&SOURCE);
+    frame.extend_from_slice(tags);
+    frame.extend_from_slice(&ether_type.to_be_bytes());
+    frame.resize(60, 0);
+    frame
+}
+
+#[test]
+fn frames_give_addresses_and_type() {
+    let frame = parse_ethernet(&frame(&[], 0x88cc), 64, UNIX_EPOCH).unwrap();
+    assert_eq!(frame.source, MacAddress(SOURCE));
+    assert_eq!(frame.dest, MacAddress(DEST));
+    assert_eq!(frame.ether_type, Some(0x88cc));
+    assert_eq!(frame.length, 64);
+    assert!(parse_ethernet(&[0; 10], 10, UNIX_EPOCH).is_none());
+}
+
+#[test]
+fn vlan_tags_are_skipped_and_802_3_has_no_type() {
+    let tagged = parse_ethernet(&frame(&[0x81, 0x00, 0x00, 0x0a], 0x0806), 64, UNIX_EPOCH).unwrap();
+    assert_eq!(tagged.ether_type, Some(0x0806));
+    // STP, the field is the length of the frame
+    let stp = parse_ethernet(&frame(&[], 39), 60, UNIX_EPOCH).unwrap();
+    assert_eq!(stp.ether_type, None);
+}
+
+#[test]
+fn node_addresses_give_back_the_mac() {
+    let mac = MacAddress(SOURCE);
+    let ip = mac.to_node_ip();
+    assert_eq!(ip.to_string(), "fe80::211:22ff:fe33:4455");
+    assert_eq!(MacAddress::from_node_ip(&ip), Some(mac));
+    assert_eq!(MacAddress::from_node_ip(&"fe80::1".parse().unwrap()), None);
+    assert_eq!(MacAddress::from_node_ip(&"10.0.0.1".parse().unwrap()), None);
+}
+
+#[test]
+fn frames_create_nodes_named_by_mac() {
+    let mut engine = Engine::with_seed(42);
+    engine.set_oui_table(OuiTable::parse("00:11:22 Example Networks\n"));
+    engine.add_frame(&parse_ethernet(&frame(&[], 0x88cc), 64, UNIX_EPOCH).unwrap());
+    assert_eq!(engine.get_visible_packet_count(), 1);
+    let source = engine.get_node(&MacAddress(SOURCE).to_node_ip()).unwrap();
+    assert_eq!(*source.get_names(), vec!["00:11:22:33:44:55".to_string()]);
+    assert_eq!(source.get_data().vendor.as_deref(), Some("Example Networks"));
+    assert_eq!(source.get_data().stats.packet_bytes_sent, 64);
+
+    engine.set_search("01:80:c2");
+    assert_eq!(*engine.get_search_matches(), vec![NodeKey::from(MacAddress(DEST).to_node_ip())]);
+}
+
+#[test]
+fn mac_nodes_are_exported_and_searched_by_mac() {
+    let mut engine = Engine::with_seed(42);
+    engine.add_frame(&parse_ethernet(&frame(&[], 0x88cc), 64, UNIX_EPOCH).unwrap());
+    let session = engine.to_session();
+    assert!(session.nodes.iter().all(|node| node.data.mac_node));
+    let nodes: Vec<_> = session.nodes.iter().map(|node| node.data.clone()).collect();
+
+    let mut output = Vec::new();
+    write_graph(&mut output, GraphFormat::Dot, &engine.export_nodes()).unwrap();
+    write_node_stats(&mut output, StatsFormat::Csv, &nodes).unwrap();
+    write_pair_stats(&mut output, StatsFormat::Jsonl, &nodes).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\"00:11:22:33:44:55\" -> \"01:80:c2:00:00:0e\""));
+    assert!(output.contains("00:11:22:33:44:55,"));
+    assert!(output.contains("\"dest\":\"01:80:c2:00:00:0e\""));
+    assert!(!output.contains("fe80"));
+
+    // The derived addresses are not IP addresses of the network
+    engine.set_search("fe80::/10");
+    assert!(engine.get_search_matches().is_empty());
+    engine.set_search("fe80::211:22ff:fe33:4455");
+    assert!(engine.get_search_matches().is_empty());
+}
+
+#[test]
+fn mac_nodes_have_no_address_class() {
+    let mut engine = Engine::with_seed(42);
+    engine.get_settings().color_by = ColorMode::Class;
+    engine.get_settings().hidden_classes = vec![AddressClass::LinkLocal];
+    engine.add_frame(&parse_ethernet(&frame(&[], 0x88cc), 64, UNIX_EPOCH).unwrap());
+    let key = NodeKey::from(MacAddress(SOURCE).to_node_ip());
+    assert!(engine.is_node_shown(&key));
+    assert_eq!(engine.get_visible_packet_count(), 1);
+    assert_eq!(engine.get_node(&key.ip).unwrap().get_color(), YELLOW);
+    assert!(engine.get_node(&key.ip).unwrap().get_data().geo.is_none());
+    assert!(engine.get_color_legend().is_empty());
+
+    engine.set_search("class:link-local");
+    assert!(engine.get_search_matches().is_empty());
+}
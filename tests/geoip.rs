@@ -0,0 +1,203 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use network_vizualizer::data::{GeoInfo, GeoIpDatabase, IPPacketInfo, TrafficGraph};
+use network_vizualizer::engine::{ColorMode, Engine, LayoutMode, PhysicsEngine, StaticInput, WorldMap};
+use network_vizualizer::math::Point;
+use network_vizualizer::entities::Entity;
+use network_vizualizer::report::{write_report, write_summary};
+
+// Values of the MaxMind DB data section
+enum Value {
+    Str(&'static str),
+    U32(u32),
+    F64(f64),
+    Array(Vec<Value>),
+    Map(Vec<(&'static str, Value)>),
+}
+
+fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        // Sizes from 29 are given in the next byte
+        Value::Str(text) if text.len() >= 29 => {
+            out.extend_from_slice(&[2 << 5 | 29, text.len() as u8 - 29]);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Value::Str(text) => {
+            out.push(2 << 5 | text.len() as u8);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Value::U32(number) => {
+            out.push(6 << 5 | 4);
+            out.extend_from_slice(&number.to_be_bytes());
+        }
+        Value::F64(number) => {
+            out.push(3 << 5 | 8);
+            out.extend_from_slice(&number.to_be_bytes());
+        }
+        // Extended type 11
+        Value::Array(items) => {
+            out.extend_from_slice(&[items.len() as u8, 11 - 7]);
+            items.iter().for_each(|item| encode(item, out));
+        }
+        Value::Map(entries) => {
+            out.push(7 << 5 | entries.len() as u8);
+            for (key, value) in entries {
+                encode(&Value::Str(key), out);
+                encode(value, out);
+            }
+        }
+    }
+}
+
+// IPv4 database where only the addresses of `network`/24 have the record
+fn database(network: [u8; 3], database_type: &'static str, record: Value) -> Vec<u8> {
+    let node_count = 24u32;
+    let data_pointer = node_count + 16;
+    let mut out = Vec::new();
+    for bit in 0..24 {
+        let next = if bit == 23 { data_pointer } else { bit + 1 };
+        let set = network[bit as usize / 8] >> (7 - bit % 8) & 1 == 1;
+        let (left, right) = if set { (node_count, next) } else { (next, node_count) };
+        out.extend_from_slice(&left.to_be_bytes()[1..]);
+        out.extend_from_slice(&right.to_be_bytes()[1..]);
+    }
+    out.extend_from_slice(&[0; 16]);
+    encode(&record, &mut out);
+    out.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+    let metadata = Value::Map(vec![
+        ("binary_format_major_version", Value::U32(2)),
+        ("binary_format_minor_version", Value::U32(0)),
+        ("build_epoch", Value::U32(0)),
+        ("database_type", Value::Str(database_type)),
+        ("description", Value::Map(vec![])),
+        ("ip_version", Value::U32(4)),
+        ("languages", Value::Array(vec![Value::Str("en")])),
+        ("node_count", Value::U32(node_count)),
+        ("record_size", Value::U32(24)),
+    ]);
+    encode(&metadata, &mut out);
+    out
+}
+
+fn geoip() -> GeoIpDatabase {
+    let city = Value::Map(vec![
+        ("city", Value::Map(vec![("names", Value::Map(vec![("en", Value::Str("Paris"))]))])),
+        ("country", Value::Map(vec![
+            ("iso_code", Value::Str("FR")),
+            ("names", Value::Map(vec![("en", Value::Str("France"))])),
+        ])),
+        ("location", Value::Map(vec![("latitude", Value::F64(48.86)), ("longitude", Value::F64(2.35))])),
+    ]);
+    let asn = Value::Map(vec![
+        ("autonomous_system_number", Value::U32(64500)),
+        ("autonomous_system_organization", Value::Str("Example Networks")),
+    ]);
+    let mut geoip = GeoIpDatabase::new();
+    geoip.set_city_source(database([81, 2, 69], "GeoLite2-City", city)).unwrap();
+    geoip.set_asn_source(database([81, 2, 69], "GeoLite2-ASN", asn)).unwrap();
+    geoip
+}
+
+fn ip(a: u8, b: u8, c: u8, d: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+}
+
+#[test]
+fn public_addresses_are_located() {
+    let info = geoip().lookup(&ip(81, 2, 69, 7)).unwrap();
+    assert_eq!(info.country.as_deref(), Some("FR"));
+    assert_eq!(info.location().as_deref(), Some("Paris, France"));
+    assert_eq!(info.latitude, Some(48.86));
+    assert_eq!(info.as_label().as_deref(), Some("AS64500 Example Networks"));
+}
+
+#[test]
+fn unknown_and_local_addresses_have_no_information() {
+    let geoip = geoip();
+    assert_eq!(geoip.lookup(&ip(8, 8, 8, 8)), None);
+    assert_eq!(geoip.lookup(&ip(192, 168, 1, 1)), None);
+    assert_eq!(GeoIpDatabase::new().lookup(&ip(81, 2, 69, 7)), None);
+}
+
+#[test]
+fn labels_without_city_or_organization() {
+    let info = GeoInfo { country: Some("FR".to_string()), asn: Some(64500), ..GeoInfo::default() };
+    assert_eq!(info.location().as_deref(), Some("FR"));
+    assert_eq!(info.as_label().as_deref(), Some("AS64500"));
+}
+
+#[test]
+fn nodes_of_the_same_as_are_grouped() {
+    let mut engine = Engine::with_seed(42);
+    let input = StaticInput::new(800.0, 600.0);
+    engine.update(&input);
+    engine.set_geoip(geoip());
+    engine.get_settings().color_by = ColorMode::Asn;
+    engine.get_settings().group_by_asn = true;
+    engine.update(&input);
+    let local = ip(192, 168, 1, 2);
+    for last in 1..=3 {
+        engine.add_packet(&IPPacketInfo::new(local, ip(81, 2, 69, last), 100));
+    }
+    let node = |last: u8| engine.get_node(&ip(81, 2, 69, last)).unwrap();
+    assert_eq!(node(1).get_data().geo.as_ref().unwrap().asn, Some(64500));
+    assert!(engine.get_node(&local).unwrap().get_data().geo.is_none());
+    assert!(node(2).get_position().distance(node(1).get_position()) < 50.0);
+    assert!(node(3).get_position().distance(node(1).get_position()) < 50.0);
+    assert_eq!(node(1).get_color(), node(3).get_color());
+}
+
+#[test]
+fn world_map_projection() {
+    let map = WorldMap::fit((800.0, 600.0));
+    assert_eq!((map.width, map.height), (800.0, 400.0));
+    assert_eq!(map.project(0.0, 0.0), Point { x: 400.0, y: 300.0 });
+    assert_eq!(map.project(90.0, -180.0), Point { x: 0.0, y: 100.0 });
+    assert_eq!(map.project(-90.0, 180.0), Point { x: 800.0, y: 500.0 });
+}
+
+#[test]
+fn world_layout_places_nodes_by_location() {
+    let mut engine = Engine::with_seed(42);
+    let input = StaticInput::new(800.0, 600.0);
+    engine.set_geoip(geoip());
+    engine.get_settings().layout = LayoutMode::World;
+    engine.update(&input);
+    let local = ip(192, 168, 1, 2);
+    let public = ip(81, 2, 69, 1);
+    engine.add_packet(&IPPacketInfo::new(local, public, 100));
+
+    let map = WorldMap::fit((800.0, 600.0));
+    let public_pos = *engine.get_node(&public).unwrap().get_position();
+    assert_eq!(public_pos, map.project(48.86, 2.35));
+    let local_pos = *engine.get_node(&local).unwrap().get_position();
+    assert_eq!(local_pos, map.inset_center());
+
+    // Packets leave the straight line on the side of the curve
+    engine.update(&input);
+    let packet = *engine.get_packets().next().unwrap().get_position();
+    let slope = (public_pos.y - local_pos.y) / (public_pos.x - local_pos.x);
+    let side = |point: Point| point.y - (local_pos.y + slope * (point.x - local_pos.x));
+    let control = map.arc_control(&local_pos, &public_pos);
+    assert!(side(packet) * side(control) > 0.0);
+}
+
+#[test]
+fn headless_reports_give_the_location_of_public_nodes() {
+    let mut graph = TrafficGraph::new();
+    graph.add_packet(&IPPacketInfo::new(ip(192, 168, 1, 2), ip(81, 2, 69, 7), 100));
+    graph.set_geoip(geoip());
+    graph.add_packet(&IPPacketInfo::new(ip(192, 168, 1, 2), ip(81, 2, 69, 8), 50));
+    assert!(graph.get_node(&ip(81, 2, 69, 8)).unwrap().geo.is_some());
+
+    let mut summary = Vec::new();
+    write_summary(&mut summary, Duration::from_secs(5), &graph, 3).unwrap();
+    let summary = String::from_utf8(summary).unwrap();
+    let line = |ip: &str| summary.lines().find(|line| line.trim_start().starts_with(ip)).unwrap().to_string();
+    assert!(line("81.2.69.7 ").ends_with(" Paris, France (AS64500 Example Networks)"));
+    assert!(line("192.168.1.2 ").ends_with(" B"));
+
+    let mut report = Vec::new();
+    write_report(&mut report, graph.get_nodes()).unwrap();
+    assert_eq!(String::from_utf8(report).unwrap().lines().filter(|line| line.ends_with(" Paris, France (AS64500 Example Networks)")).count(), 2);
+}
@@ -0,0 +1,67 @@
+use network_vizualizer::data::{PacketDirection, TrafficGraph};
+use network_vizualizer::engine::PhysicsEngine;
+use network_vizualizer::entities::Entity;
+use network_vizualizer::math::Point;
+use network_vizualizer::report::write_summary;
+use std::time::Duration;
+use common::{ip, new_engine, packet};
+
+mod common;
+
+#[test]
+fn packets_are_classified_relative_to_the_local_host() {
+    let local = [ip(1)];
+    assert_eq!(PacketDirection::of(&packet(1, 2), &local), PacketDirection::Outbound);
+    assert_eq!(PacketDirection::of(&packet(2, 1), &local), PacketDirection::Inbound);
+    assert_eq!(PacketDirection::of(&packet(2, 3), &local), PacketDirection::Transit);
+    assert_eq!(PacketDirection::of(&packet(1, 2), &[]), PacketDirection::Transit);
+}
+
+#[test]
+fn local_nodes_are_tagged_and_pinned_to_the_center() {
+    let (mut engine, input) = new_engine();
+    engine.set_local_addresses(vec![ip(1)]);
+    engine.add_packet(&packet(1, 2));
+    engine.add_packet(&packet(3, 1));
+    engine.add_packet(&packet(3, 2));
+
+    let local = engine.get_node(&ip(1)).unwrap();
+    assert!(local.get_data().local && local.is_pinned());
+    assert_eq!(*local.get_position(), Point { x: 400.0, y: 300.0 });
+    assert!(!engine.get_node(&ip(2)).unwrap().get_data().local);
+
+    let directions: Vec<PacketDirection> = engine.get_packets().map(|packet| packet.get_direction()).collect();
+    assert_eq!(directions, vec![PacketDirection::Outbound, PacketDirection::Inbound, PacketDirection::Transit]);
+    assert_eq!(engine.get_directions().get(PacketDirection::Inbound).packet_bytes, 100);
+
+    engine.get_settings().pin_local = false;
+    engine.update(&input);
+    assert!(!engine.get_node(&ip(1)).unwrap().is_pinned());
+}
+
+#[test]
+fn nodes_seen_before_the_local_addresses_are_moved() {
+    let (mut engine, _) = new_engine();
+    engine.add_packet(&packet(1, 2));
+    engine.set_local_addresses(vec![ip(2)]);
+    let local = engine.get_node(&ip(2)).unwrap();
+    assert!(local.get_data().local && local.is_pinned());
+    assert_eq!(*local.get_position(), Point { x: 400.0, y: 300.0 });
+}
+
+#[test]
+fn headless_summary_gives_the_traffic_by_direction() {
+    let mut graph = TrafficGraph::new();
+    graph.set_local_addresses(vec![ip(1)]);
+    graph.add_packet(&packet(1, 2));
+    graph.add_packet(&packet(2, 1));
+    graph.add_packet(&packet(2, 1));
+    assert!(graph.get_node(&ip(1)).unwrap().local);
+
+    let mut summary = Vec::new();
+    write_summary(&mut summary, Duration::from_secs(5), &graph, 0).unwrap();
+    assert_eq!(String::from_utf8(summary).unwrap(), "\
+[     5s] 3 packets, 2 nodes, top talkers:
+  inbound 2 (200 B), outbound 1 (100 B), transit 0 (0 B)
+");
+}
@@ -0,0 +1,16 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use network_vizualizer::math::capture_time;
+
+#[test]
+fn corrupt_capture_times_give_the_current_time() {
+    assert_eq!(capture_time(1_700_000_000, 250_000), UNIX_EPOCH + Duration::new(1_700_000_000, 250_000_000));
+    // Microseconds past one second don't roll over to the next second
+    assert_eq!(capture_time(10, 5_000_000), UNIX_EPOCH + Duration::new(10, 999_999_000));
+    assert_eq!(capture_time(10, -1), UNIX_EPOCH + Duration::from_secs(10));
+
+    let before = SystemTime::now();
+    for time in [capture_time(-1, 0), capture_time(i64::MIN, 0)] {
+        assert!(time >= before && time <= SystemTime::now());
+    }
+    assert!(capture_time(i64::MAX, 999_999) >= before);
+}
@@ -0,0 +1,123 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use network_vizualizer::data::{IPPacketInfo, NodeData};
+use network_vizualizer::engine::{DrawCommand, Engine, GraphicsEngine, PhysicsEngine, RecordingRenderer, StaticInput, GREEN};
+use common::ip;
+
+mod common;
+
+fn packet(source: u8, dest: u8, seconds: u64) -> IPPacketInfo {
+    let mut packet = common::packet(source, dest);
+    packet.timestamp = UNIX_EPOCH + Duration::from_secs(seconds);
+    packet
+}
+
+fn glow(engine: &Engine, last: u8) -> f32 {
+    engine.get_node(&ip(last)).unwrap().get_glow()
+}
+
+// Green rings of the new nodes and green lines of the new pairs
+fn draw_new(engine: &Engine) -> (usize, usize) {
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    let rings = renderer.commands.iter().filter(|command| matches!(command, DrawCommand::CircleLines { color, .. } if color.r == GREEN.r && color.a > 0.0)).count();
+    let lines = renderer.commands.iter().filter(|command| matches!(command, DrawCommand::Line { color, .. } if color.r == GREEN.r && color.a > 0.0)).count();
+    (rings, lines)
+}
+
+#[test]
+fn first_seen_is_the_earliest_packet() {
+    let mut data = NodeData::new(ip(1));
+    assert_eq!(data.first_seen, None);
+    data.add_packet(&packet(1, 2, 200));
+    data.add_packet(&packet(2, 1, 100));
+    data.add_packet(&packet(1, 2, 300));
+    assert_eq!(data.first_seen, Some(UNIX_EPOCH + Duration::from_secs(100)));
+}
+
+#[test]
+fn new_nodes_and_pairs_fade_with_their_age() {
+    let mut engine = Engine::with_seed(1);
+    engine.get_settings().new_highlight = Duration::from_secs(30);
+    let input = StaticInput::new(800.0, 600.0);
+    engine.update(&input);
+    engine.add_packet(&packet(1, 2, 100));
+    assert_eq!(glow(&engine, 1), 1.0);
+    assert!(engine.is_new_pair(&ip(1).into(), &ip(2).into()));
+    assert!(engine.is_new_pair(&ip(2).into(), &ip(1).into()));
+    assert_eq!(draw_new(&engine), (2, 1));
+
+    // The node 3 is new, the pair of two known nodes too
+    engine.add_packet(&packet(3, 1, 115));
+    engine.add_packet(&packet(2, 3, 115));
+    engine.update(&input);
+    assert!((glow(&engine, 1) - 0.5).abs() < 1e-6);
+    assert_eq!(glow(&engine, 3), 1.0);
+    assert!(engine.is_new_pair(&ip(2).into(), &ip(3).into()));
+    assert_eq!(draw_new(&engine), (3, 3));
+
+    engine.add_packet(&packet(1, 2, 130));
+    engine.update(&input);
+    assert_eq!(glow(&engine, 1), 0.0);
+    assert!(!engine.is_new_pair(&ip(1).into(), &ip(2).into()));
+    assert_eq!(draw_new(&engine), (1, 2));
+
+    engine.add_packet(&packet(1, 2, 200));
+    engine.update(&input);
+    assert_eq!(draw_new(&engine), (0, 0));
+}
+
+#[test]
+fn nothing_glows_when_the_highlight_is_off() {
+    let mut engine = Engine::with_seed(1);
+    engine.get_settings().new_highlight = Duration::ZERO;
+    engine.add_packet(&packet(1, 2, 100));
+    assert_eq!(glow(&engine, 1), 0.0);
+    assert!(!engine.is_new_pair(&ip(1).into(), &ip(2).into()));
+}
+
+#[test]
+fn baseline_nodes_and_pairs_are_not_new() {
+    let mut previous = Engine::with_seed(1);
+    previous.add_packet(&packet(1, 2, 100));
+    previous.add_packet(&packet(2, 3, 100));
+    let session = previous.to_session();
+    assert!(session.nodes.iter().all(|node| node.data.first_seen.is_some()));
+
+    let mut engine = Engine::with_seed(1);
+    engine.set_baseline(&session);
+    assert_eq!(engine.get_baseline().unwrap().len(), 3);
+    engine.add_packet(&packet(2, 1, 500));
+    assert_eq!(glow(&engine, 1), 0.0);
+    assert_eq!(glow(&engine, 2), 0.0);
+    assert!(!engine.is_new_pair(&ip(1).into(), &ip(2).into()));
+
+    // Known nodes talking for the first time, and a node missing from the baseline
+    engine.add_packet(&packet(1, 3, 500));
+    engine.add_packet(&packet(4, 1, 500));
+    assert_eq!(glow(&engine, 3), 0.0);
+    assert_eq!(glow(&engine, 4), 1.0);
+    assert!(engine.is_new_pair(&ip(1).into(), &ip(3).into()));
+    assert!(engine.is_new_pair(&ip(1).into(), &ip(4).into()));
+
+    engine.clear_baseline();
+    engine.add_packet(&packet(5, 1, 500));
+    assert_eq!(glow(&engine, 5), 1.0);
+}
+
+#[test]
+fn live_nodes_fade_when_the_capture_is_quiet() {
+    let mut engine = Engine::with_seed(1);
+    engine.get_settings().new_highlight = Duration::from_secs(30);
+    let input = StaticInput::new(800.0, 600.0);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    engine.add_packet(&packet(1, 2, now - 60));
+    engine.update(&input);
+    // A replayed file has no packet after this one, its nodes keep their glow
+    assert_eq!(glow(&engine, 1), 1.0);
+
+    engine.set_live(true);
+    engine.update(&input);
+    assert_eq!(glow(&engine, 1), 0.0);
+    assert!(!engine.is_new_pair(&ip(1).into(), &ip(2).into()));
+    assert_eq!(draw_new(&engine), (0, 0));
+}
@@ -0,0 +1,76 @@
+use network_vizualizer::data::{MacAddress, OuiTable};
+
+fn mac(text: &str) -> MacAddress {
+    text.parse().unwrap()
+}
+
+#[test]
+fn reads_the_ieee_text_format() {
+    let table = OuiTable::parse(
+        "OUI/MA-L                                                    Organization\n\
+         company_id                                                  Organization\n\
+         \n\
+         00-11-22   (hex)\t\tExample Networks\n\
+         001122     (base 16)\t\tExample Networks\n\
+         \t\t\t\t1 Example Street\n",
+    );
+    assert_eq!(table.len(), 1);
+    assert_eq!(table.lookup(&mac("00:11:22:33:44:55")), Some("Example Networks"));
+}
+
+#[test]
+fn address_lines_are_not_vendors() {
+    let table = OuiTable::parse(
+        "00-11-22   (hex)\t\tExample Networks\n\
+         001122     (base 16)\t\tExample Networks\n\
+         \t\t\t\tSt.-Martin-Strasse 53\n\
+         \t\t\t\tD-80339 Munich\n\
+         \t\t\t\tDE\n\
+         \n\
+         AC-DE-48   (hex)\t\tPrivate\n\
+         ACDE48     (base 16)\t\tPrivate\n\
+         \t\t\t\t7-1, Shiba 5-chome Minato-ku\n\
+         \t\t\t\tCupertino  CA  95014\n",
+    );
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.lookup(&mac("d8:03:39:00:00:01")), None);
+    assert_eq!(table.lookup(&mac("ac:de:48:00:00:01")), Some("Private"));
+}
+
+#[test]
+fn address_lines_starting_with_digits_are_not_vendors() {
+    let table = OuiTable::parse(
+        "00-11-22   (hex)\t\tExample Networks\n\
+         001122     (base 16)\t\tExample Networks\n\
+         \t\t\t\tNo.1 Example Road\n\
+         \t\t\t\t100080 Beijing\n\
+         \t\t\t\tCN\n",
+    );
+    assert_eq!(table.len(), 1);
+    assert_eq!(table.lookup(&mac("10:00:80:00:00:01")), None);
+}
+
+#[test]
+fn reads_the_ieee_csv_format() {
+    let table = OuiTable::parse(
+        "Registry,Assignment,Organization Name,Organization Address\n\
+         MA-L,001122,Example Networks,1 Example Street\n\
+         MA-L,0CBBCC,\"Example, Inc.\",2 Example Street\n",
+    );
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.lookup(&mac("00:11:22:00:00:01")), Some("Example Networks"));
+    assert_eq!(table.lookup(&mac("0c:bb:cc:00:00:01")), Some("Example, Inc."));
+}
+
+#[test]
+fn randomized_addresses_have_no_vendor() {
+    let table = OuiTable::parse("02:11:22 Example Networks\n06-11-22 Other\n");
+    assert_eq!(table.lookup(&mac("02:11:22:33:44:55")), None);
+}
+
+#[test]
+fn embedded_table_knows_common_vendors() {
+    let table = OuiTable::embedded();
+    assert_eq!(table.lookup(&mac("00:50:56:01:02:03")), Some("VMware, Inc."));
+    assert_eq!(table.lookup(&mac("b8:27:eb:01:02:03")), Some("Raspberry Pi Foundation"));
+}
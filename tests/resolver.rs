@@ -0,0 +1,104 @@
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use network_vizualizer::data::{reverse_name, DnsRecord};
+use network_vizualizer::resolver::{ResolverConfig, ReverseResolver};
+
+const NAMED: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+const UNNAMED: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+fn encode_name(name: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for label in name.split('.') {
+        bytes.push(label.len() as u8);
+        bytes.extend_from_slice(label.as_bytes());
+    }
+    bytes.push(0);
+    bytes
+}
+
+// DNS server answering the PTR query of NAMED and NXDOMAIN to the others,
+// every query received is sent on the returned channel
+fn start_stub() -> (ResolverConfig, Receiver<Vec<u8>>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut config = ResolverConfig::new(socket.local_addr().unwrap());
+    config.max_per_second = 100;
+    config.timeout = Duration::from_millis(500);
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let mut buffer = [0; 512];
+        while let Ok((size, client)) = socket.recv_from(&mut buffer) {
+            let query = buffer[..size].to_vec();
+            let mut response = query.clone();
+            let named = encode_name(&reverse_name(&NAMED));
+            if query.windows(named.len()).any(|window| window == named) {
+                response[2..4].copy_from_slice(&0x8180u16.to_be_bytes());
+                response[6..8].copy_from_slice(&1u16.to_be_bytes());
+                let name = encode_name("host.example.com");
+                response.extend_from_slice(&[0xc0, 12, 0, 12, 0, 1, 0, 0, 0x0e, 0x10]);
+                response.extend_from_slice(&(name.len() as u16).to_be_bytes());
+                response.extend_from_slice(&name);
+            } else {
+                response[2..4].copy_from_slice(&0x8183u16.to_be_bytes());
+            }
+            if sender.send(query).is_err() {
+                return;
+            }
+            socket.send_to(&response, client).unwrap();
+        }
+    });
+    (config, receiver)
+}
+
+fn wait_results(resolver: &mut ReverseResolver) -> Vec<DnsRecord> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let results = resolver.results();
+        if !results.is_empty() || Instant::now() > deadline {
+            return results;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn reverse_lookup_names_the_address() {
+    let (config, _queries) = start_stub();
+    let mut resolver = ReverseResolver::start(config).unwrap();
+    resolver.request(NAMED);
+    assert_eq!(wait_results(&mut resolver), vec![
+        DnsRecord { ip: NAMED, name: "host.example.com".to_string(), ttl: 3600 },
+    ]);
+}
+
+#[test]
+fn addresses_are_not_asked_twice() {
+    let (config, queries) = start_stub();
+    let mut resolver = ReverseResolver::start(config).unwrap();
+    resolver.request(UNNAMED);
+    resolver.request(UNNAMED);
+    resolver.request(NAMED);
+    assert_eq!(wait_results(&mut resolver).len(), 1);
+    assert_eq!(queries.try_iter().count(), 2);
+}
+
+#[test]
+fn requests_are_forgotten_after_their_ttl() {
+    let (mut config, queries) = start_stub();
+    config.negative_ttl = Duration::from_millis(100);
+    let mut resolver = ReverseResolver::start(config).unwrap();
+    resolver.request(UNNAMED);
+    resolver.request(NAMED);
+    assert_eq!(wait_results(&mut resolver).len(), 1);
+    assert_eq!(resolver.requested(), 2);
+
+    // The name is kept for its TTL of an hour
+    thread::sleep(Duration::from_millis(200));
+    assert!(resolver.results().is_empty());
+    assert_eq!(resolver.requested(), 1);
+    resolver.request(UNNAMED);
+    resolver.request(NAMED);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(queries.try_iter().count(), 3);
+}
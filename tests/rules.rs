@@ -0,0 +1,198 @@
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
+use std::rc::Rc;
+use std::time::{Duration, UNIX_EPOCH};
+use network_vizualizer::data::{AlertKind, IPPacketInfo, Rule, RuleAction, RuleSet, RuleTrigger, StatsHistory, TrafficGraph};
+use network_vizualizer::engine::{Engine, PhysicsEngine, StaticInput};
+use network_vizualizer::notifier::{beep_wav, AlertNotifier};
+
+fn ip(a: u8, b: u8, c: u8, d: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+}
+
+fn packet(source: IpAddr, dest: IpAddr, dest_port: u16, len: u16, seconds: u64) -> IPPacketInfo {
+    let mut packet = IPPacketInfo::new(source, dest, len);
+    packet.protocol = 6;
+    packet.source_port = Some(40000);
+    packet.dest_port = Some(dest_port);
+    packet.timestamp = UNIX_EPOCH + Duration::from_secs(seconds);
+    packet
+}
+
+fn rule(text: &str) -> Rule {
+    text.parse().unwrap()
+}
+
+#[test]
+fn rules_are_parsed_and_printed_back() {
+    let parsed = rule("sent>50MB/1m dst:public => highlight,log:alerts.log");
+    assert_eq!(parsed.trigger, RuleTrigger::Sent { bytes: 50 * 1024 * 1024, window: Duration::from_secs(60) });
+    assert_eq!(parsed.actions, vec![RuleAction::Highlight, RuleAction::Log("alerts.log".into())]);
+    assert_eq!(parsed.to_string(), "sent>50MB/60s dst:public => highlight,log:alerts.log");
+    assert_eq!(rule(&parsed.to_string()), parsed);
+
+    assert_eq!(rule("new src:10.1.0.0/16").to_string(), "new src:10.1.0.0/16 => highlight");
+    assert_eq!(rule("port:23 => sound, webhook:http://localhost:8080/alerts").to_string(), "port:23 => sound,webhook:http://localhost:8080/alerts");
+    assert_eq!(rule("PORT:23 SRC:private").condition(), "port:23 src:private");
+
+    assert!("".parse::<Rule>().is_err());
+    assert!("port:23 new".parse::<Rule>().is_err());
+    assert!("new dst:public".parse::<Rule>().is_err());
+    assert!("sent>50XB".parse::<Rule>().is_err());
+    assert!("sent>1MB/2h".parse::<Rule>().is_err());
+    assert!("sent>1MB/9999999999999999999h".parse::<Rule>().is_err());
+    assert!("sent>1MB/9999999999999999999m".parse::<Rule>().is_err());
+    assert!("port:23 src:somewhere".parse::<Rule>().is_err());
+    assert!("port:23 => beep".parse::<Rule>().is_err());
+    assert!("port:23 => webhook:https://example.com".parse::<Rule>().is_err());
+}
+
+#[test]
+fn port_rules_are_raised_once_per_cooldown() {
+    let mut rules = RuleSet::new(vec![rule("port:23 dst:10.0.0.0/8")]);
+    let mut history = StatsHistory::default();
+    let mut raised = Vec::new();
+    for (dest, seconds) in [(ip(10, 0, 0, 2), 100), (ip(10, 0, 0, 2), 110), (ip(8, 8, 8, 8), 120), (ip(10, 0, 0, 2), 200)] {
+        let packet = packet(ip(10, 0, 0, 1), dest, 23, 60, seconds);
+        history.add_packet(&packet);
+        raised.push(rules.add_packet(&packet, &history).len());
+    }
+    assert_eq!(raised, vec![1, 0, 0, 1]);
+
+    let packet = packet(ip(10, 0, 0, 1), ip(10, 0, 0, 3), 23, 60, 200);
+    history.add_packet(&packet);
+    let alerts = rules.add_packet(&packet, &history);
+    assert_eq!(alerts[0].kind, AlertKind::Rule);
+    assert_eq!(alerts[0].to_string(), "Rule 'port:23 dst:10.0.0.0/8' matched by 10.0.0.1 to 10.0.0.3 on port 23");
+}
+
+#[test]
+fn new_nodes_in_a_prefix_raise_an_alert() {
+    let mut graph = TrafficGraph::new();
+    graph.set_rules(vec![rule("new src:10.1.0.0/16")]);
+    let alerts = graph.add_packet(&packet(ip(10, 1, 2, 3), ip(10, 2, 0, 1), 443, 60, 100));
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].source.ip, ip(10, 1, 2, 3));
+    assert!(graph.add_packet(&packet(ip(10, 1, 2, 3), ip(10, 2, 0, 1), 443, 60, 101)).is_empty());
+    assert_eq!(graph.add_packet(&packet(ip(10, 2, 0, 1), ip(10, 1, 0, 9), 443, 60, 102)).len(), 1);
+    assert_eq!(graph.get_alerts().len(), 2);
+}
+
+#[test]
+fn rate_rules_sum_the_bytes_sent_over_their_window() {
+    let mut rules = RuleSet::new(vec![rule("sent>1KB/10s dst:public")]);
+    let mut history = StatsHistory::default();
+    let mut add = |dest: IpAddr, seconds: u64| {
+        let packet = packet(ip(10, 0, 0, 1), dest, 443, 400, seconds);
+        history.add_packet(&packet);
+        rules.add_packet(&packet, &history)
+    };
+    // Private destinations are not counted
+    for seconds in 100..105 {
+        assert!(add(ip(10, 0, 0, 2), seconds).is_empty());
+    }
+    assert!(add(ip(8, 8, 8, 8), 105).is_empty());
+    assert!(add(ip(8, 8, 8, 8), 106).is_empty());
+    let alerts = add(ip(8, 8, 8, 8), 107);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].count, 1200);
+    assert_eq!(alerts[0].to_string(), "Rule 'sent>1KB/10s dst:public' matched by 10.0.0.1, 1.17 KB sent");
+    // Not raised again during the window
+    assert!(add(ip(8, 8, 8, 8), 108).is_empty());
+}
+
+#[test]
+fn engine_highlights_only_when_the_rule_asks_for_it() {
+    let mut engine = Engine::with_seed(1);
+    engine.set_rules(vec![rule("port:23 => sound"), rule("port:21")]);
+    engine.update(&StaticInput::new(800.0, 600.0));
+    engine.add_packet(&packet(ip(10, 0, 0, 1), ip(10, 0, 0, 2), 23, 60, 100));
+    assert_eq!(engine.get_alerts().len(), 1);
+    assert!(!engine.get_node(&ip(10, 0, 0, 1)).unwrap().is_flashing());
+    assert_eq!(engine.take_new_alerts().len(), 1);
+    assert!(engine.take_new_alerts().is_empty());
+
+    engine.add_packet(&packet(ip(10, 0, 0, 3), ip(10, 0, 0, 2), 21, 60, 100));
+    assert!(engine.get_node(&ip(10, 0, 0, 3)).unwrap().is_flashing());
+    // Highlighting is done by the engine, nothing is left to run
+    assert!(engine.take_new_alerts().is_empty());
+
+    engine.remove_rule(0);
+    assert_eq!(engine.get_rules().len(), 1);
+}
+
+#[test]
+fn log_action_appends_the_alerts_to_the_file() {
+    let path = std::env::temp_dir().join(format!("network_vizualizer_rules_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut rules = RuleSet::new(vec![rule(&format!("port:23 => log:{}", path.display()))]);
+    let mut history = StatsHistory::default();
+    let mut notifier = AlertNotifier::new();
+    for dest in [2, 3] {
+        let packet = packet(ip(10, 0, 0, 1), ip(10, 0, 0, dest), 23, 60, 1_700_000_000);
+        history.add_packet(&packet);
+        for alert in rules.add_packet(&packet, &history) {
+            notifier.notify(&alert).unwrap();
+        }
+    }
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("[2023-11-14T22:13:20Z] Rule 'port:23' matched by 10.0.0.1 to 10.0.0.2"));
+}
+
+#[test]
+fn sound_action_plays_the_sound_when_set() {
+    let mut rules = RuleSet::new(vec![rule("port:23 => sound")]);
+    let mut history = StatsHistory::default();
+    let packet = packet(ip(10, 0, 0, 1), ip(10, 0, 0, 2), 23, 60, 1_700_000_000);
+    history.add_packet(&packet);
+    let played = Rc::new(Cell::new(0));
+    let mut notifier = AlertNotifier::new();
+    let counter = played.clone();
+    notifier.set_sound(move || counter.set(counter.get() + 1));
+    for alert in rules.add_packet(&packet, &history) {
+        notifier.notify(&alert).unwrap();
+    }
+    assert_eq!(played.get(), 1);
+
+    let wav = beep_wav();
+    assert!(wav.starts_with(b"RIFF") && &wav[8..16] == b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+    assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize, wav.len() - 44);
+}
+
+#[test]
+fn webhook_action_posts_the_alert_as_json() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let mut rules = RuleSet::new(vec![rule(&format!("port:23 => webhook:http://127.0.0.1:{}/alerts", port))]);
+    let mut history = StatsHistory::default();
+    let packet = packet(ip(10, 0, 0, 1), ip(10, 0, 0, 2), 23, 60, 1_700_000_000);
+    history.add_packet(&packet);
+    let mut notifier = AlertNotifier::new();
+    for alert in rules.add_packet(&packet, &history) {
+        notifier.notify(&alert).unwrap();
+    }
+
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"}") {
+        let read = stream.read(&mut buffer).unwrap();
+        assert!(read > 0);
+        request.extend_from_slice(&buffer[..read]);
+    }
+    stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+    let request = String::from_utf8(request).unwrap();
+    assert!(request.starts_with("POST /alerts HTTP/1.1\r\n"));
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["kind"], "rule");
+    assert_eq!(body["rule"], "port:23");
+    assert_eq!(body["source"], "10.0.0.1");
+    assert_eq!(body["port"], 23);
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, UNIX_EPOCH};
+use network_vizualizer::data::{AlertKind, FlowTable, IPPacketInfo, ScanConfig, ScanDetector, TrafficGraph};
+use network_vizualizer::engine::{DrawCommand, Engine, GraphicsEngine, PhysicsEngine, RecordingRenderer, StaticInput, RED};
+use network_vizualizer::export::{write_alerts, StatsFormat};
+use network_vizualizer::math::convert_time_str;
+use common::ip;
+
+mod common;
+
+fn packet(source: u8, dest: u8, source_port: u16, dest_port: u16, seconds: u64) -> IPPacketInfo {
+    let mut packet = IPPacketInfo::new(ip(source), ip(dest), 60);
+    packet.protocol = 6;
+    packet.source_port = Some(source_port);
+    packet.dest_port = Some(dest_port);
+    packet.timestamp = UNIX_EPOCH + Duration::from_secs(seconds);
+    packet
+}
+
+fn config() -> ScanConfig {
+    ScanConfig { window: Duration::from_secs(10), port_threshold: 5, host_threshold: 4 }
+}
+
+// Returns the kinds of the alerts raised by each packet
+fn detect(detector: &mut ScanDetector, flows: &mut FlowTable, packets: &[IPPacketInfo]) -> Vec<Vec<AlertKind>> {
+    packets.iter().map(|packet| {
+        flows.add_packet(packet);
+        detector.add_packet(packet, flows).iter().map(|alert| alert.kind).collect()
+    }).collect()
+}
+
+#[test]
+fn many_ports_of_one_host_are_a_port_scan_raised_once_per_window() {
+    let mut detector = ScanDetector::new(config());
+    let mut flows = FlowTable::new();
+    let probes: Vec<IPPacketInfo> = (0..6).map(|port| packet(1, 2, 40000, 20 + port, 100)).collect();
+    let kinds = detect(&mut detector, &mut flows, &probes);
+    assert_eq!(kinds.iter().filter(|kinds| !kinds.is_empty()).count(), 1);
+    assert_eq!(kinds[4], vec![AlertKind::PortScan]);
+
+    // Ports seen before the window are forgotten, the scan is raised again after it
+    let late: Vec<IPPacketInfo> = (0..5).map(|port| packet(1, 2, 40000, 100 + port, 120)).collect();
+    let kinds = detect(&mut detector, &mut flows, &late);
+    assert_eq!(kinds[4], vec![AlertKind::PortScan]);
+}
+
+#[test]
+fn many_hosts_on_one_port_are_a_host_sweep() {
+    let mut detector = ScanDetector::new(config());
+    let mut flows = FlowTable::new();
+    let probes: Vec<IPPacketInfo> = (2..6).map(|host| packet(1, host, 40000, 22, 100)).collect();
+    let alerts: Vec<_> = probes.iter().flat_map(|packet| {
+        flows.add_packet(packet);
+        detector.add_packet(packet, &flows)
+    }).collect();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].kind, AlertKind::HostSweep);
+    assert_eq!(alerts[0].port, Some(22));
+    assert_eq!(alerts[0].count, 4);
+    assert_eq!(alerts[0].to_string(), "Host sweep from 10.0.0.1 on port 22, 4 hosts");
+}
+
+#[test]
+fn replies_to_the_ephemeral_ports_of_a_client_are_not_a_scan() {
+    let mut detector = ScanDetector::new(config());
+    let mut flows = FlowTable::new();
+    let mut packets = Vec::new();
+    for port in 0..10 {
+        packets.push(packet(1, 2, 50000 + port, 443, 100));
+        packets.push(packet(2, 1, 443, 50000 + port, 100));
+    }
+    let kinds = detect(&mut detector, &mut flows, &packets);
+    assert!(kinds.iter().all(|kinds| kinds.is_empty()));
+}
+
+#[test]
+fn engine_flashes_the_scanner_and_lists_the_alert() {
+    let mut engine = Engine::with_seed(1);
+    engine.get_settings().scan = config();
+    let input = StaticInput::new(800.0, 600.0);
+    engine.update(&input);
+    for port in 0..5 {
+        engine.add_packet(&packet(1, 2, 40000, 20 + port, 100));
+    }
+    assert_eq!(engine.get_alerts().len(), 1);
+    assert_eq!(engine.get_alerts()[0].to_string(), "Port scan from 10.0.0.1 to 10.0.0.2, 5 ports");
+    assert!(engine.get_node(&ip(1)).unwrap().is_flashing());
+    assert!(!engine.get_node(&ip(2)).unwrap().is_flashing());
+
+    let mut renderer = RecordingRenderer::new();
+    engine.draw(&mut renderer);
+    let rings = renderer.commands.iter().filter(|command| matches!(command, DrawCommand::CircleLines { color, .. } if *color == RED)).count();
+    assert_eq!(rings, 1);
+
+    for _ in 0..180 {
+        engine.update(&input);
+    }
+    assert!(!engine.get_node(&ip(1)).unwrap().is_flashing());
+}
+
+#[test]
+fn headless_graph_keeps_alerts_and_exports_them() {
+    let mut graph = TrafficGraph::new();
+    graph.set_scan_config(config());
+    for port in 0..5 {
+        graph.add_packet(&packet(1, 2, 40000, 20 + port, 1_700_000_000));
+    }
+    assert_eq!(graph.get_alerts().len(), 1);
+    assert_eq!(convert_time_str(graph.get_alerts()[0].timestamp), "2023-11-14T22:13:20Z");
+
+    let mut output = Vec::new();
+    write_alerts(&mut output, StatsFormat::Csv, graph.get_alerts()).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\
+timestamp,kind,source,target,port,protocol,count,rule
+2023-11-14T22:13:20Z,port-scan,10.0.0.1,10.0.0.2,,6,5,
+");
+    let mut output = Vec::new();
+    write_alerts(&mut output, StatsFormat::Jsonl, graph.get_alerts()).unwrap();
+    let line: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(line["kind"], "port-scan");
+    assert_eq!(line["target"], "10.0.0.2");
+    assert!(line["port"].is_null());
+}
+
+#[test]
+fn flows_are_forgotten_when_idle_or_the_table_is_full() {
+    let mut flows = FlowTable::with_limits(10, Duration::from_secs(60));
+    for port in 0..10 {
+        flows.add_packet(&packet(1, 2, 1000, port, 100 + port as u64));
+    }
+    assert_eq!(flows.len(), 10);
+    // Nothing is idle yet, the least recently seen flow goes
+    flows.add_packet(&packet(1, 2, 1000, 10, 110));
+    assert_eq!(flows.len(), 10);
+    assert!(flows.iter().all(|(key, _)| key.dest_port != Some(0)));
+
+    // Known flows are updated without eviction, the idle ones go first
+    flows.add_packet(&packet(1, 2, 1000, 9, 200));
+    assert_eq!(flows.len(), 10);
+    flows.add_packet(&packet(1, 2, 1000, 11, 200));
+    let ports: Vec<_> = flows.iter().filter_map(|(key, _)| key.dest_port).collect();
+    assert_eq!(ports.len(), 2);
+    assert!(ports.contains(&9) && ports.contains(&11));
+}
@@ -0,0 +1,59 @@
+use network_vizualizer::data::{parse_http_host, parse_server_name, parse_tls_sni};
+
+fn with_length(length_size: usize, data: &[u8]) -> Vec<u8> {
+    let mut bytes = data.len().to_be_bytes()[8 - length_size..].to_vec();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn client_hello(extensions: &[u8]) -> Vec<u8> {
+    let mut hello = vec![0x03, 0x03];
+    hello.extend_from_slice(&[0; 32]);
+    hello.extend(with_length(1, &[1, 2, 3]));
+    hello.extend(with_length(2, &[0x13, 0x01]));
+    hello.extend(with_length(1, &[0]));
+    hello.extend(with_length(2, extensions));
+    let mut handshake = vec![0x01];
+    handshake.extend(with_length(3, &hello));
+    let mut record = vec![0x16, 0x03, 0x01];
+    record.extend(with_length(2, &handshake));
+    record
+}
+
+fn sni_extension(name: &str) -> Vec<u8> {
+    let mut entry = vec![0];
+    entry.extend(with_length(2, name.as_bytes()));
+    let mut extension = vec![0, 0];
+    extension.extend(with_length(2, &with_length(2, &entry)));
+    extension
+}
+
+#[test]
+fn sni_is_read_after_other_extensions() {
+    // Supported groups extension before the server name
+    let mut extensions = vec![0x00, 0x0a, 0x00, 0x04, 0x00, 0x02, 0x00, 0x1d];
+    extensions.extend(sni_extension("Www.Example.com"));
+    assert_eq!(parse_tls_sni(&client_hello(&extensions)), Some("www.example.com".to_string()));
+}
+
+#[test]
+fn hello_without_sni_or_truncated_gives_nothing() {
+    assert_eq!(parse_tls_sni(&client_hello(&[])), None);
+    let hello = client_hello(&sni_extension("example.com"));
+    assert_eq!(parse_tls_sni(&hello[..hello.len() - 3]), None);
+    assert_eq!(parse_tls_sni(b"\x17\x03\x03\x00\x01\x00"), None);
+}
+
+#[test]
+fn http_host_is_read_without_port() {
+    let request = b"GET /index.html HTTP/1.1\r\nUser-Agent: test\r\nHOST: api.example.com:8080\r\n\r\n";
+    assert_eq!(parse_http_host(request), Some("api.example.com".to_string()));
+    assert_eq!(parse_http_host(b"GET / HTTP/1.1\r\nHost: [::1]:80\r\n\r\n"), Some("::1".to_string()));
+}
+
+#[test]
+fn host_after_the_headers_or_in_responses_is_ignored() {
+    assert_eq!(parse_http_host(b"GET / HTTP/1.1\r\n\r\nHost: body.example.com\r\n"), None);
+    assert_eq!(parse_http_host(b"HTTP/1.1 200 OK\r\nHost: example.com\r\n\r\n"), None);
+    assert_eq!(parse_server_name(b"POST /x HTTP/1.0\nhost: example.org\n\n"), Some("example.org".to_string()));
+}
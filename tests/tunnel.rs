@@ -0,0 +1,62 @@
+use network_vizualizer::data::{parse_encapsulation, Encapsulation, IPPacketInfo, TrafficGraph, Tunnel, TunnelKind, GENEVE_PORT, VXLAN_PORT};
+use common::ip;
+
+mod common;
+
+const GRE: u8 = 47;
+const UDP: u8 = 17;
+
+fn with_inner(header: &[u8]) -> Vec<u8> {
+    let mut payload = header.to_vec();
+    payload.extend_from_slice(&[0x45, 0, 0, 20]);
+    payload
+}
+
+#[test]
+fn gre_headers_give_the_key_and_inner_offset() {
+    // Key and sequence number, IPv4 inside
+    let keyed = with_inner(&[0x30, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00, 0x01]);
+    assert_eq!(parse_encapsulation(GRE, None, &keyed), Some(Encapsulation { kind: TunnelKind::Gre, id: Some(42), offset: 12, ethernet: false }));
+    // Transparent Ethernet bridging
+    let bridged = with_inner(&[0x00, 0x00, 0x65, 0x58]);
+    assert!(parse_encapsulation(GRE, None, &bridged).unwrap().ethernet);
+    // PPTP uses the version 1
+    assert_eq!(parse_encapsulation(GRE, None, &with_inner(&[0x30, 0x01, 0x88, 0x0b, 0, 0, 0, 0])), None);
+}
+
+#[test]
+fn vxlan_and_geneve_headers_give_the_vni() {
+    let vxlan = with_inner(&[0x08, 0, 0, 0, 0x00, 0x13, 0x89, 0x00]);
+    assert_eq!(parse_encapsulation(UDP, Some(VXLAN_PORT), &vxlan), Some(Encapsulation { kind: TunnelKind::Vxlan, id: Some(5001), offset: 8, ethernet: true }));
+    // No valid VNI
+    assert_eq!(parse_encapsulation(UDP, Some(VXLAN_PORT), &with_inner(&[0; 8])), None);
+    assert_eq!(parse_encapsulation(UDP, Some(53), &vxlan), None);
+
+    // One 4 byte option, IPv4 inside
+    let geneve = with_inner(&[0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x64, 0x00, 0x01, 0x02, 0x03, 0x04]);
+    assert_eq!(parse_encapsulation(UDP, Some(GENEVE_PORT), &geneve), Some(Encapsulation { kind: TunnelKind::Geneve, id: Some(100), offset: 12, ethernet: false }));
+}
+
+#[test]
+fn ip_in_ip_starts_right_away_and_truncated_tunnels_are_ignored() {
+    let ipip = parse_encapsulation(4, None, &with_inner(&[])).unwrap();
+    assert_eq!((ipip.kind, ipip.offset, ipip.ethernet), (TunnelKind::IpInIp, 0, false));
+    assert_eq!(parse_encapsulation(4, None, &[]), None);
+    assert_eq!(parse_encapsulation(UDP, Some(VXLAN_PORT), &[0x08, 0, 0, 0, 0, 0, 1, 0]), None);
+}
+
+#[test]
+fn nodes_record_the_tunnel_endpoints() {
+    let mut packet = IPPacketInfo::new(ip(1), ip(2), 100);
+    let outer = IPPacketInfo::new(ip(100), ip(200), 150);
+    packet.tunnel = Some(Box::new(Tunnel { kind: TunnelKind::Vxlan, id: Some(5001), outer }));
+    assert_eq!(packet.outer().source, ip(100));
+
+    let mut graph = TrafficGraph::new();
+    graph.add_packet(&packet);
+    graph.add_packet(&packet);
+    let tunnels = &graph.get_node(&ip(2)).unwrap().tunnels;
+    assert_eq!(tunnels.len(), 1);
+    assert_eq!(tunnels[0].to_string(), "VXLAN 5001 10.0.0.100 -> 10.0.0.200");
+    assert!(graph.get_node(&ip(100)).is_none());
+}
@@ -0,0 +1,160 @@
+use network_vizualizer::data::{parse_link_tags, IPPacketInfo, NodeKey, SearchQuery, TrafficGraph};
+use network_vizualizer::engine::{ColorMode, Engine, PhysicsEngine, StaticInput};
+use network_vizualizer::export::{write_node_stats, StatsFormat};
+use common::ip;
+
+mod common;
+
+fn packet(source: u8, dest: u8, vlans: &[u16]) -> IPPacketInfo {
+    let mut packet = common::packet(source, dest);
+    packet.vlans = vlans.to_vec();
+    packet
+}
+
+// Ethernet header with the given tags, followed by an empty IPv4 header
+fn frame(tags: &[u8], ether_type: u16) -> Vec<u8> {
+    let mut frame = vec![0; 12];
+    frame.extend_from_slice(tags);
+    frame.extend_from_slice(&ether_type.to_be_bytes());
+    frame.push(0x45);
+    frame.resize(frame.len() + 19, 0);
+    frame
+}
+
+#[test]
+fn vlan_and_qinq_tags_are_read() {
+    let single = parse_link_tags(&frame(&[0x81, 0x00, 0x20, 0x0a], 0x0800)).unwrap();
+    // The priority bits are not part of the ID
+    assert_eq!(single.vlans, vec![10]);
+    assert_eq!(single.ether_type, 0x0800);
+    assert_eq!(single.network_offset, 18);
+
+    let double = parse_link_tags(&frame(&[0x88, 0xa8, 0x00, 0x64, 0x81, 0x00, 0x00, 0x0a], 0x86dd)).unwrap();
+    assert_eq!(double.vlans, vec![100, 10]);
+    assert_eq!(double.ether_type, 0x86dd);
+    assert_eq!(double.network_offset, 22);
+    assert!(!double.is_mpls());
+
+    assert!(parse_link_tags(&[0; 10]).is_none());
+}
+
+#[test]
+fn mpls_labels_are_read_until_the_bottom_of_the_stack() {
+    // Labels 16 and 1000, the second one has the bottom of stack bit
+    let labels = [0x00, 0x01, 0x00, 0x40, 0x00, 0x3e, 0x81, 0x40];
+    let mut tags = vec![0x81, 0x00, 0x00, 0x0a, 0x88, 0x47];
+    tags.extend_from_slice(&labels);
+    let mut frame = vec![0; 12];
+    frame.extend_from_slice(&tags);
+    frame.push(0x45);
+
+    let tags = parse_link_tags(&frame).unwrap();
+    assert_eq!(tags.vlans, vec![10]);
+    assert_eq!(tags.mpls_labels, vec![16, 1000]);
+    assert_eq!(tags.ether_type, 0x8847);
+    assert_eq!(tags.network_offset, 26);
+    assert_eq!(frame[tags.network_offset], 0x45);
+}
+
+#[test]
+fn nodes_are_unique_per_vlan_when_configured() {
+    let input = StaticInput::new(800.0, 600.0);
+    let mut shared = Engine::with_seed(1);
+    let mut per_vlan = Engine::with_seed(1);
+    per_vlan.set_per_vlan(true);
+    for engine in [&mut shared, &mut per_vlan] {
+        engine.update(&input);
+        engine.add_packet(&packet(1, 2, &[10]));
+        engine.add_packet(&packet(1, 2, &[20]));
+        // QinQ, the inner tag is the VLAN of the hosts
+        engine.add_packet(&packet(1, 2, &[100, 10]));
+    }
+
+    assert_eq!(shared.get_nodes().count(), 2);
+    assert_eq!(shared.get_node(&ip(1)).unwrap().get_data().vlans, vec![10, 20, 100]);
+
+    assert_eq!(per_vlan.get_nodes().count(), 4);
+    assert!(per_vlan.get_node(&ip(1)).is_none());
+    let node = per_vlan.get_node(&NodeKey::new(ip(1), Some(10))).unwrap();
+    assert_eq!(node.get_data().vlan, Some(10));
+    assert_eq!(node.get_data().stats.packet_count_sent, 2);
+    assert_eq!(per_vlan.get_node(&NodeKey::new(ip(2), Some(20))).unwrap().get_data().stats.packet_count_recv, 1);
+    assert_eq!(NodeKey::new(ip(2), Some(20)).to_string(), "10.0.0.2 (VLAN 20)");
+}
+
+#[test]
+fn nodes_are_searched_and_colored_by_vlan() {
+    let mut engine = Engine::with_seed(1);
+    engine.update(&StaticInput::new(800.0, 600.0));
+    engine.add_packet(&packet(1, 2, &[10]));
+    engine.add_packet(&packet(3, 4, &[20]));
+    engine.add_packet(&packet(5, 6, &[]));
+
+    assert_eq!(SearchQuery::parse("VLAN: 10"), SearchQuery::Vlan(10));
+    engine.set_search("vlan:10");
+    assert_eq!(*engine.get_search_matches(), vec![NodeKey::from(ip(1)), NodeKey::from(ip(2))]);
+
+    engine.get_settings().color_by = ColorMode::Vlan;
+    engine.update(&StaticInput::new(800.0, 600.0));
+    let color = |last: u8| engine.get_node(&ip(last)).unwrap().get_color();
+    assert_eq!(color(1), color(2));
+    assert_ne!(color(1), color(3));
+    let legend: Vec<String> = engine.get_color_legend().into_iter().map(|(key, _, _)| key).collect();
+    assert_eq!(legend, vec!["VLAN 10", "VLAN 20"]);
+}
+
+#[test]
+fn live_nodes_are_searched_and_colored_by_vlan() {
+    let mut engine = Engine::with_seed(1);
+    engine.get_settings().color_by = ColorMode::Vlan;
+    engine.update(&StaticInput::new(800.0, 600.0));
+    engine.set_search("vlan:10");
+    engine.add_packet(&packet(1, 2, &[10]));
+    engine.add_packet(&packet(3, 4, &[20]));
+    assert_eq!(*engine.get_search_matches(), vec![NodeKey::from(ip(1)), NodeKey::from(ip(2))]);
+    let color = |last: u8| engine.get_node(&ip(last)).unwrap().get_color();
+    assert_eq!(color(1), color(2));
+    assert_ne!(color(1), color(3));
+
+    // The node 3 joins the VLAN 10 later
+    engine.add_packet(&packet(3, 1, &[10]));
+    assert_eq!(*engine.get_search_matches(), vec![NodeKey::from(ip(1)), NodeKey::from(ip(2)), NodeKey::from(ip(3))]);
+}
+
+#[test]
+fn sessions_keep_nodes_unique_per_vlan() {
+    let mut engine = Engine::with_seed(1);
+    engine.set_per_vlan(true);
+    engine.add_packet(&packet(1, 2, &[10]));
+    engine.add_packet(&packet(1, 2, &[]));
+    let session = engine.to_session();
+    assert!(session.per_vlan);
+    assert_eq!(session.nodes.len(), 4);
+
+    // The packets of the VLAN 10 go to the saved nodes of the VLAN 10
+    let mut loaded = Engine::with_seed(1);
+    loaded.load_session(session);
+    loaded.add_packet(&packet(1, 2, &[10]));
+    let session = loaded.to_session();
+    assert!(session.per_vlan);
+    assert_eq!(session.nodes.len(), 4);
+}
+
+#[test]
+fn stats_get_a_vlan_column_when_nodes_are_unique_per_vlan() {
+    let mut graph = TrafficGraph::new();
+    graph.set_per_vlan(true);
+    graph.add_packet(&packet(1, 2, &[10]));
+    graph.add_packet(&packet(1, 2, &[]));
+    let nodes: Vec<_> = graph.get_nodes().cloned().collect();
+
+    let mut csv = Vec::new();
+    write_node_stats(&mut csv, StatsFormat::Csv, &nodes).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "\
+ip,vlan,packets_sent,bytes_sent,packets_recv,bytes_recv
+10.0.0.1,,1,100,0,0
+10.0.0.1,10,1,100,0,0
+10.0.0.2,,0,0,1,100
+10.0.0.2,10,0,0,1,100
+");
+}